@@ -432,6 +432,23 @@ pub(crate) fn dispatch(
             }
         }
 
+        DidReceiveResources {
+            action,
+            context,
+            device,
+            resources,
+        } => {
+            let v = views::DidReceiveResources {
+                action,
+                context,
+                device,
+                resources,
+            };
+            if let Some(a) = mgr.ensure_ready(cx, action, context) {
+                a.did_receive_resources(cx, &v);
+            }
+        }
+
         _ => {
             for (_, a) in mgr.instances.iter_mut() {
                 a.on_global_event(cx, &ev);
@@ -439,3 +456,79 @@ pub(crate) fn dispatch(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::actions::{Action, ActionFactory};
+    use crate::context::Context;
+    use crate::plugin::Plugin;
+    use crate::recorder::RecordEntry;
+    use crate::replay::replay;
+    use crate::sd_protocol::{Outgoing, StreamDeckEvent, parse_incoming, views};
+
+    /// Logs which hook saw a resources reply.
+    struct Resources(&'static str);
+
+    impl Action for Resources {
+        fn id(&self) -> &str {
+            self.0
+        }
+        fn did_receive_resources(&mut self, cx: &Context, ev: &views::DidReceiveResources) {
+            cx.sd()
+                .log_message(format!("{} resources {}", ev.context, ev.resources.len()));
+        }
+        fn on_global_event(&mut self, cx: &Context, ev: &StreamDeckEvent) {
+            cx.sd().log_message(format!("{} global {ev}", self.0));
+        }
+    }
+
+    fn incoming(v: serde_json::Value) -> RecordEntry {
+        RecordEntry::Incoming {
+            t_ms: 0,
+            event: parse_incoming(&v.to_string()).unwrap(),
+        }
+    }
+
+    fn will_appear(action: &str, context: &str) -> RecordEntry {
+        incoming(json!({
+            "event": "willAppear",
+            "action": action,
+            "context": context,
+            "device": "dev",
+            "payload": { "controller": "Keypad", "settings": {} },
+        }))
+    }
+
+    #[test]
+    fn resources_go_to_the_owning_instance_only() {
+        let plugin = Plugin::new()
+            .add_action(ActionFactory::new("com.example.a", || {
+                Resources("com.example.a")
+            }))
+            .add_action(ActionFactory::new("com.example.b", || {
+                Resources("com.example.b")
+            }));
+        let entries = [
+            will_appear("com.example.a", "a"),
+            will_appear("com.example.b", "b"),
+            incoming(json!({
+                "event": "didReceiveResources",
+                "action": "com.example.a",
+                "context": "a",
+                "device": "dev",
+                "payload": { "resources": { "icon": "imgs/icon.png" } },
+            })),
+        ];
+        let logs: Vec<String> = replay(&plugin, &entries)
+            .produced
+            .into_iter()
+            .filter_map(|m| match m {
+                Outgoing::LogMessage { message } => Some(message),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(logs, vec!["a resources 1".to_string()]);
+    }
+}
@@ -4,6 +4,7 @@ use std::sync::Arc;
 use crate::{
     context::Context,
     events::ErasedTopic,
+    pi::{PiAction, PiRouted},
     sd_protocol::{StreamDeckEvent, views::*},
 };
 
@@ -44,6 +45,9 @@ pub trait Action: Send + 'static {
     ) {
     }
     fn did_receive_settings(&mut self, _cx: &Context, _ev: &DidReceiveSettings) {}
+    /// Reply to [`SdClient::get_resources`](crate::sd_protocol::SdClient::get_resources).
+    fn did_receive_resources(&mut self, _cx: &Context, _ev: &DidReceiveResources) {}
+    /// Raw PI message. `PiAction` implementors get typed `on_pi_message` instead.
     fn did_receive_property_inspector_message(
         &mut self,
         _cx: &Context,
@@ -106,6 +110,35 @@ impl ActionFactory {
     {
         Self::from_static::<A, _>(|| A::default())
     }
+
+    /// Like `new`, but routes property inspector messages through `PiAction`.
+    pub fn new_pi<F, A>(id: impl Into<String>, factory: F) -> Self
+    where
+        F: Fn() -> A + Send + Sync + 'static,
+        A: PiAction + 'static,
+    {
+        Self {
+            id: id.into(),
+            build: Arc::new(move || Box::new(PiRouted(factory()))),
+        }
+    }
+
+    /// Like `from_static`, with typed PI messages.
+    pub fn pi_from_static<A, F>(factory: F) -> Self
+    where
+        A: PiAction + ActionStatic + 'static,
+        F: Fn() -> A + Send + Sync + 'static,
+    {
+        Self::new_pi(A::ID, factory)
+    }
+
+    /// Like `default_of`, with typed PI messages.
+    pub fn pi_default_of<A>() -> Self
+    where
+        A: PiAction + ActionStatic + Default + 'static,
+    {
+        Self::pi_from_static::<A, _>(|| A::default())
+    }
 }
 
 /// Tiny helper so you can register with less ceremony.
@@ -180,24 +180,6 @@ impl AdapterManager {
         self.start_by_name(cx, name);
     }
 
-    #[inline]
-    fn has_topic(topics: &'static [&'static str], t: &str) -> bool {
-        topics.contains(&t)
-    }
-
-    pub(crate) fn start_by_topic(&mut self, cx: &Context, topic: &str) {
-        self.start_where(cx, |a| a.topics().contains(&topic));
-    }
-
-    pub(crate) fn stop_by_topic(&mut self, topic: &str) {
-        self.stop_where(|r| !Self::has_topic(r.topics, topic));
-    }
-
-    pub(crate) fn restart_by_topic(&mut self, cx: &Context, topic: &str) {
-        self.stop_by_topic(topic);
-        self.start_by_topic(cx, topic);
-    }
-
     pub(crate) fn start_by_label(&mut self, cx: &Context, label: &str) {
         self.start_where(cx, |a| a.labels().contains(&label));
     }
@@ -288,12 +270,13 @@ impl AdapterManager {
 
     /// Drive deferred work; call this regularly from the runtime loop.
     pub(crate) fn tick(&mut self) {
-        if let Some(due) = self.app_stop_due {
-            if Instant::now() >= due && self.apps_up == 0 {
-                self.stop_by_policy(StartPolicy::OnAppLaunch);
-                self.app_stop_due = None;
-                debug!("🛑 OnAppLaunch adapters stopped (no apps, debounced)");
-            }
+        if let Some(due) = self.app_stop_due
+            && Instant::now() >= due
+            && self.apps_up == 0
+        {
+            self.stop_by_policy(StartPolicy::OnAppLaunch);
+            self.app_stop_due = None;
+            debug!("🛑 OnAppLaunch adapters stopped (no apps, debounced)");
         }
     }
 
@@ -1,26 +1,163 @@
 // lib/context.rs
 use std::{
     any::{Any, TypeId},
-    collections::HashMap,
-    sync::{Arc, RwLock},
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Condvar, Mutex, RwLock},
+    time::{Duration, Instant},
 };
 
 use serde_json::{Map, Value};
-use tracing::error;
+use tracing::{debug, error, warn};
 
-use crate::sd_protocol::SdClient;
+use crate::{
+    input::{Layout, LayoutKey},
+    pi::{PiInfo, PiTracker},
+    sd_protocol::SdClient,
+    typed_globals::{GlobalsSchema, TypedGlobals, VersionedSettings},
+};
 
 // ======================
 // Global Settings
 // ======================
 
+/// One key that changed: `None` means absent on that side.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlobalsChange {
+    pub key: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+/// Keys that differ between two global settings maps, sorted by key.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GlobalsDiff {
+    pub changes: Vec<GlobalsChange>,
+}
+
+impl GlobalsDiff {
+    pub fn between(old: &Map<String, Value>, new: &Map<String, Value>) -> Self {
+        let mut changes: Vec<GlobalsChange> = old
+            .iter()
+            .filter(|(k, v)| new.get(*k) != Some(*v))
+            .map(|(k, v)| GlobalsChange {
+                key: k.clone(),
+                old: Some(v.clone()),
+                new: new.get(k).cloned(),
+            })
+            .chain(
+                new.iter()
+                    .filter(|(k, _)| !old.contains_key(*k))
+                    .map(|(k, v)| GlobalsChange {
+                        key: k.clone(),
+                        old: None,
+                        new: Some(v.clone()),
+                    }),
+            )
+            .collect();
+        changes.sort_by(|a, b| a.key.cmp(&b.key));
+        Self { changes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// The change for `key`, if it changed.
+    pub fn get(&self, key: &str) -> Option<&GlobalsChange> {
+        self.changes.iter().find(|c| c.key == key)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.changes.iter().map(|c| c.key.as_str())
+    }
+}
+
+/// Returned by `GlobalSettings::watch*`; pass to `unwatch` to remove the watcher.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WatchId(u64);
+
+#[derive(Clone)]
+enum Watcher {
+    Key(String, Arc<dyn Fn(&GlobalsChange) + Send + Sync>),
+    All(Arc<dyn Fn(&GlobalsDiff) + Send + Sync>),
+}
+
+#[derive(Default)]
+struct Watchers {
+    next: u64,
+    list: Vec<(WatchId, Watcher)>,
+}
+
+/// What to do with pending local writes when a Stream Deck snapshot arrives
+/// before they were pushed (write-behind mode only).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GlobalsConflict {
+    /// Re-apply the locally changed keys on top of the snapshot; they are pushed
+    /// at the next flush.
+    #[default]
+    KeepLocal,
+    /// Drop the pending local changes and take the snapshot as-is.
+    TakeRemote,
+}
+
+/// Write-behind mode for global settings: writes update the cache right away,
+/// but are pushed to Stream Deck at most once per `window`.
+///
+/// Writes still pending when Stream Deck closes the connection are pushed as it
+/// closes, which Stream Deck may ignore, and are lost if the process is killed
+/// first. Call [`GlobalSettings::flush`] after writes that must not be lost.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WriteBehind {
+    pub window: Duration,
+    pub conflict: GlobalsConflict,
+}
+
+impl WriteBehind {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            conflict: GlobalsConflict::default(),
+        }
+    }
+
+    /// Conflict policy (chainable).
+    pub fn on_conflict(mut self, conflict: GlobalsConflict) -> Self {
+        self.conflict = conflict;
+        self
+    }
+}
+
+/// Local changes not yet pushed.
+#[derive(Default)]
+struct Pending {
+    /// Set by the first unpushed write; the push is due then.
+    due: Option<Instant>,
+    keys: BTreeSet<String>,
+}
+
 /// Thread-safe, push-on-write global settings cache.
 /// All mutations push to Stream Deck automatically.
 /// Only `hydrate_from_sd` writes without pushing (used when SD sends us a snapshot).
+/// Watchers run after every change, local or from SD, outside the cache lock.
+/// With [`WriteBehind`] configured, pushes are coalesced; see [`GlobalSettings::flush`].
 #[derive(Clone)]
 pub struct GlobalSettings {
     sd: Arc<SdClient>,
     map: Arc<RwLock<Map<String, Value>>>,
+    schema: Option<Arc<GlobalsSchema>>,
+    watchers: Arc<Mutex<Watchers>>,
+    write_behind: Option<WriteBehind>,
+    pending: Arc<Mutex<Pending>>,
+    /// Set once the first Stream Deck snapshot has been stored.
+    hydrated: Arc<(Mutex<bool>, Condvar)>,
 }
 
 impl GlobalSettings {
@@ -28,21 +165,86 @@ impl GlobalSettings {
         Self {
             sd,
             map: Arc::new(RwLock::new(Map::new())),
+            schema: None,
+            watchers: Arc::new(Mutex::new(Watchers::default())),
+            write_behind: None,
+            pending: Arc::new(Mutex::new(Pending::default())),
+            hydrated: Arc::new((Mutex::new(false), Condvar::new())),
         }
     }
 
+    pub(crate) fn with_write_behind(mut self, write_behind: Option<WriteBehind>) -> Self {
+        self.write_behind = write_behind;
+        self
+    }
+
+    pub(crate) fn with_schema(mut self, schema: Option<GlobalsSchema>) -> Self {
+        self.schema = schema.map(Arc::new);
+        self
+    }
+
     // ---- SD <-> cache sync (no push) -----------------------------------
 
     /// Replace the whole map from Stream Deck's snapshot (no push).
     /// Call from your `didReceiveGlobalSettings` handler.
-    pub(crate) fn hydrate_from_sd(&self, from_sd: Map<String, Value>) {
-        match self.map.write() {
+    ///
+    /// With a registered schema, an older snapshot is migrated first and the
+    /// result pushed back once. Pending write-behind changes are resolved with
+    /// the configured [`GlobalsConflict`]. Returns the map as stored and what changed.
+    pub(crate) fn hydrate_from_sd(
+        &self,
+        mut from_sd: Map<String, Value>,
+    ) -> (Map<String, Value>, GlobalsDiff) {
+        let migrated = self
+            .schema
+            .as_ref()
+            .is_some_and(|schema| schema.migrate(&mut from_sd));
+        let diff = match self.map.write() {
             Ok(mut w) => {
-                *w = from_sd;
+                self.resolve_pending(&w, &mut from_sd);
+                let diff = GlobalsDiff::between(&w, &from_sd);
+                *w = from_sd.clone();
+                diff
             }
-            Err(_) => error!(
-                "GlobalSettings: write lock poisoned while hydrating from SD; keeping old cache"
-            ),
+            Err(_) => {
+                error!(
+                    "GlobalSettings: write lock poisoned while hydrating from SD; keeping old cache"
+                );
+                GlobalsDiff::default()
+            }
+        };
+        if migrated {
+            self.sd.set_global_settings(from_sd.clone());
+        }
+        self.mark_hydrated();
+        self.notify(&diff);
+        (from_sd, diff)
+    }
+
+    /// Whether the first snapshot from Stream Deck has arrived. Until then the
+    /// cache only holds local writes.
+    pub fn is_hydrated(&self) -> bool {
+        *self.hydrated.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Block until the first snapshot arrives or `timeout` passes; returns `is_hydrated()`.
+    /// Meant for adapter threads; never call it from an action or hook, which run
+    /// on the thread that delivers the snapshot.
+    pub fn wait_hydrated(&self, timeout: Duration) -> bool {
+        let (lock, cvar) = &*self.hydrated;
+        let guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        let (guard, _) = cvar
+            .wait_timeout_while(guard, timeout, |hydrated| !*hydrated)
+            .unwrap_or_else(|e| e.into_inner());
+        *guard
+    }
+
+    fn mark_hydrated(&self) {
+        let (lock, cvar) = &*self.hydrated;
+        let mut hydrated = lock.lock().unwrap_or_else(|e| e.into_inner());
+        if !*hydrated {
+            *hydrated = true;
+            cvar.notify_all();
         }
     }
 
@@ -90,20 +292,16 @@ impl GlobalSettings {
 
     /// Replace all settings and push.
     pub fn replace(&self, new_map: Map<String, Value>) {
-        if let Some(snapshot) = self.with_write_snapshot(|w| {
+        self.write("replace", |w| {
             *w = new_map;
-        }) {
-            self.sd.set_global_settings(snapshot);
-        }
+        });
     }
 
     /// Set a single key and push.
     pub fn set(&self, key: impl Into<String>, value: Value) {
-        if let Some(snapshot) = self.with_write_snapshot(|w| {
+        self.write("set", |w| {
             w.insert(key.into(), value);
-        }) {
-            self.sd.set_global_settings(snapshot);
-        }
+        });
     }
 
     /// Set multiple keys and push.
@@ -112,40 +310,32 @@ impl GlobalSettings {
         I: IntoIterator<Item = (K, Value)>,
         K: Into<String>,
     {
-        if let Some(snapshot) = self.with_write_snapshot(|w| {
+        self.write("set_many", |w| {
             for (k, v) in entries {
                 w.insert(k.into(), v);
             }
-        }) {
-            self.sd.set_global_settings(snapshot);
-        }
+        });
     }
 
     /// Delete everything and push (leaves an empty object on SD).
     pub fn delete_all(&self) {
-        if let Some(snapshot) = self.with_write_snapshot(|w| w.clear()) {
-            self.sd.set_global_settings(snapshot);
-        }
+        self.write("delete_all", |w| w.clear());
     }
 
     /// Delete a single key and push.
     pub fn delete(&self, key: &str) {
-        if let Some(snapshot) = self.with_write_snapshot(|w| {
+        self.write("delete", |w| {
             w.remove(key);
-        }) {
-            self.sd.set_global_settings(snapshot);
-        }
+        });
     }
 
     /// Delete multiple keys and push.
     pub fn delete_many(&self, keys: &[&str]) {
-        if let Some(snapshot) = self.with_write_snapshot(|w| {
+        self.write("delete_many", |w| {
             for &k in keys {
                 w.remove(k);
             }
-        }) {
-            self.sd.set_global_settings(snapshot);
-        }
+        });
     }
 
     /// Batch-edit the settings and push once to Stream Deck.
@@ -157,40 +347,201 @@ impl GlobalSettings {
     where
         F: FnOnce(&mut Map<String, Value>) -> R,
     {
-        match self.map.write() {
-            Ok(mut w) => {
-                let ret = f(&mut w);
-                let snapshot = w.clone(); // single push with the final state
-                drop(w);
-                self.sd.set_global_settings(snapshot);
-                Some(ret)
+        self.write("with_mut", f)
+    }
+
+    /// Like [`GlobalSettings::with_mut`], but a closure returning `None` aborts:
+    /// nothing is pushed and watchers are not called. It must leave the map unchanged then.
+    pub(crate) fn try_with_mut<R, F>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut Map<String, Value>) -> Option<R>,
+    {
+        self.try_write("with_mut", f)
+    }
+
+    // ---- Write-behind ---------------------------------------------------
+
+    /// Push pending write-behind changes now. Returns false if nothing was pending.
+    pub fn flush(&self) -> bool {
+        let snapshot = match self.map.read() {
+            Ok(r) => {
+                let mut p = self.pending();
+                if p.due.is_none() {
+                    return false;
+                }
+                *p = Pending::default();
+                r.clone()
             }
             Err(_) => {
-                error!(
-                    "GlobalSettings: write lock poisoned during with_mut; skipping mutation & push"
-                );
-                None
+                error!("GlobalSettings: read lock poisoned during flush; skipping push");
+                return false;
             }
+        };
+        self.sd.set_global_settings(snapshot);
+        true
+    }
+
+    /// Whether local changes are waiting to be pushed.
+    pub fn has_pending(&self) -> bool {
+        self.pending().due.is_some()
+    }
+
+    /// Flush if the write-behind window of the oldest pending change has passed.
+    pub(crate) fn flush_due(&self) {
+        if self.pending().due.is_some_and(|due| due <= Instant::now()) {
+            self.flush();
         }
     }
 
+    // ---- Watchers -------------------------------------------------------
+
+    /// Call `f` with the old and new value whenever `key` changes.
+    pub fn watch<F>(&self, key: impl Into<String>, f: F) -> WatchId
+    where
+        F: Fn(&GlobalsChange) + Send + Sync + 'static,
+    {
+        self.add_watcher(Watcher::Key(key.into(), Arc::new(f)))
+    }
+
+    /// Call `f` with every non-empty diff.
+    pub fn watch_all<F>(&self, f: F) -> WatchId
+    where
+        F: Fn(&GlobalsDiff) + Send + Sync + 'static,
+    {
+        self.add_watcher(Watcher::All(Arc::new(f)))
+    }
+
+    /// Remove a watcher. Returns false if it was already gone.
+    pub fn unwatch(&self, id: WatchId) -> bool {
+        let mut w = self.watchers.lock().unwrap_or_else(|e| e.into_inner());
+        let before = w.list.len();
+        w.list.retain(|(i, _)| *i != id);
+        w.list.len() != before
+    }
+
     // ---- Internals ------------------------------------------------------
 
-    /// Helper: run a write op and return the fresh snapshot, logging on lock errors.
-    fn with_write_snapshot<F>(&self, f: F) -> Option<Map<String, Value>>
+    /// Helper: run a write op, push the fresh snapshot (or mark it pending in
+    /// write-behind mode) and notify watchers, logging on lock errors.
+    fn write<R, F>(&self, op: &str, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut Map<String, Value>) -> R,
+    {
+        self.try_write(op, |map| Some(f(map)))
+    }
+
+    /// [`GlobalSettings::write`] for closures that may abort with `None`.
+    fn try_write<R, F>(&self, op: &str, f: F) -> Option<R>
     where
-        F: FnOnce(&mut Map<String, Value>),
+        F: FnOnce(&mut Map<String, Value>) -> Option<R>,
     {
-        match self.map.write() {
+        let tracked = self.write_behind.is_some() || self.has_watchers();
+        let (ret, snapshot, diff) = match self.map.write() {
             Ok(mut w) => {
-                f(&mut w);
-                Some(w.clone())
+                let before = tracked.then(|| w.clone());
+                let ret = f(&mut w)?;
+                let diff = before.map(|before| GlobalsDiff::between(&before, &w));
+                if let (Some(wb), Some(diff)) = (self.write_behind, &diff) {
+                    // marked under the map lock so a concurrent hydrate sees it
+                    self.mark_pending(wb, diff);
+                    (ret, None, diff.clone())
+                } else {
+                    // single push with the final state
+                    (ret, Some(w.clone()), diff.unwrap_or_default())
+                }
             }
             Err(_) => {
-                error!(
-                    "GlobalSettings: write lock poisoned during mutation; skipping mutation & push"
+                error!("GlobalSettings: write lock poisoned during {op}; skipping mutation & push");
+                return None;
+            }
+        };
+        if let Some(snapshot) = snapshot {
+            self.sd.set_global_settings(snapshot);
+        }
+        self.notify(&diff);
+        Some(ret)
+    }
+
+    fn pending(&self) -> std::sync::MutexGuard<'_, Pending> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn mark_pending(&self, wb: WriteBehind, diff: &GlobalsDiff) {
+        if diff.is_empty() {
+            return;
+        }
+        let mut p = self.pending();
+        p.due.get_or_insert_with(|| Instant::now() + wb.window);
+        p.keys.extend(diff.keys().map(str::to_string));
+    }
+
+    /// Apply the conflict policy to a snapshot arriving while local writes are pending.
+    fn resolve_pending(&self, local: &Map<String, Value>, from_sd: &mut Map<String, Value>) {
+        let Some(wb) = self.write_behind else {
+            return;
+        };
+        let mut p = self.pending();
+        if p.due.is_none() {
+            return;
+        }
+        match wb.conflict {
+            GlobalsConflict::KeepLocal => {
+                for key in &p.keys {
+                    match local.get(key) {
+                        Some(v) => from_sd.insert(key.clone(), v.clone()),
+                        None => from_sd.remove(key),
+                    };
+                }
+                debug!(
+                    "🔀 kept {} pending global setting(s) over SD snapshot",
+                    p.keys.len()
                 );
-                None
+            }
+            GlobalsConflict::TakeRemote => {
+                warn!(
+                    "⚠️ SD snapshot replaced {} pending global setting(s)",
+                    p.keys.len()
+                );
+                *p = Pending::default();
+            }
+        }
+    }
+
+    fn add_watcher(&self, watcher: Watcher) -> WatchId {
+        let mut w = self.watchers.lock().unwrap_or_else(|e| e.into_inner());
+        let id = WatchId(w.next);
+        w.next += 1;
+        w.list.push((id, watcher));
+        id
+    }
+
+    fn has_watchers(&self) -> bool {
+        !self
+            .watchers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .list
+            .is_empty()
+    }
+
+    /// Run watchers for `diff`. The list is copied first so callbacks may read,
+    /// write or (un)watch without deadlocking.
+    fn notify(&self, diff: &GlobalsDiff) {
+        if diff.is_empty() {
+            return;
+        }
+        let list: Vec<Watcher> = {
+            let w = self.watchers.lock().unwrap_or_else(|e| e.into_inner());
+            w.list.iter().map(|(_, watcher)| watcher.clone()).collect()
+        };
+        for watcher in list {
+            match watcher {
+                Watcher::Key(key, f) => {
+                    if let Some(change) = diff.get(&key) {
+                        f(change);
+                    }
+                }
+                Watcher::All(f) => f(diff),
             }
         }
     }
@@ -257,6 +608,8 @@ pub struct Context {
     globals: GlobalSettings,
     exts: Extensions,
     bus: Arc<dyn crate::bus::Bus>,
+    pi: PiTracker,
+    layout: Layout,
 }
 
 impl Context {
@@ -273,9 +626,26 @@ impl Context {
             globals,
             exts,
             bus,
+            pi: PiTracker::default(),
+            layout: Layout::default(),
         }
     }
 
+    pub(crate) fn with_layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub(crate) fn with_globals_schema(mut self, schema: Option<GlobalsSchema>) -> Self {
+        self.globals = self.globals.with_schema(schema);
+        self
+    }
+
+    pub(crate) fn with_globals_write_behind(mut self, write_behind: Option<WriteBehind>) -> Self {
+        self.globals = self.globals.with_write_behind(write_behind);
+        self
+    }
+
     pub fn sd(&self) -> &SdClient {
         &self.sd
     }
@@ -292,12 +662,46 @@ impl Context {
         self.exts.clone()
     }
 
+    /// Global settings decoded as `T` (see [`VersionedSettings`]).
+    pub fn globals_typed<T: VersionedSettings>(&self) -> TypedGlobals<T> {
+        TypedGlobals::new(self.globals.clone())
+    }
+
     pub fn try_ext<T>(&self) -> Option<Arc<T>>
     where
         T: Send + Sync + 'static,
     {
         self.exts.get::<T>()
     }
+
+    /// Keyboard layout chosen with `Plugin::keyboard_layout`.
+    pub fn keyboard_layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Parse a key name with the plugin's keyboard layout.
+    pub fn parse_key(&self, name: &str) -> Option<LayoutKey> {
+        self.layout.parse_key(name)
+    }
+
+    /// Whether the property inspector of `ctx_id` is currently open.
+    pub fn pi_open_for(&self, ctx_id: &str) -> bool {
+        self.pi.is_open(ctx_id)
+    }
+
+    /// The most recently opened property inspector that is still open.
+    pub fn current_pi(&self) -> Option<PiInfo> {
+        self.pi.current()
+    }
+
+    /// All open property inspectors (oldest first).
+    pub fn open_pis(&self) -> Vec<PiInfo> {
+        self.pi.all()
+    }
+
+    pub(crate) fn pi_tracker(&self) -> &PiTracker {
+        &self.pi
+    }
 }
 
 impl std::fmt::Debug for Context {
@@ -1,6 +1,6 @@
 // hooks.rs
 use crate::{
-    context::Context,
+    context::{Context, GlobalsDiff},
     events::{AdapterControl, AdapterTarget, ErasedTopic},
     sd_protocol::{DeviceInfo, Outgoing, StreamDeckEvent},
 };
@@ -19,6 +19,8 @@ pub enum HookEvent<'a> {
     DeviceDidChange(&'a str, &'a DeviceInfo),
     DidReceiveDeepLink(&'a str),
     DidReceiveGlobalSettings(&'a serde_json::Map<String, serde_json::Value>),
+    /// Keys a Stream Deck snapshot changed in the cache (never empty).
+    GlobalSettingsChanged(&'a GlobalsDiff),
 
     // Runtime mirrors
     Outgoing(&'a Outgoing),
@@ -134,4 +136,8 @@ impl AppHooks {
     ) {
         self.fire(cx, &HookEvent::DidReceiveGlobalSettings(gs));
     }
+    #[inline]
+    pub fn fire_global_settings_changed(&self, cx: &Context, diff: &GlobalsDiff) {
+        self.fire(cx, &HookEvent::GlobalSettingsChanged(diff));
+    }
 }
@@ -0,0 +1,410 @@
+// src/input/bindings.rs
+//! Game bindings: logical action ids ("fire", "v_toggle_landing") mapped to key
+//! chords the user configured, with defaults as fallback.
+//!
+//! Load them from JSON/TOML (`{ "fire": ["lctrl+f", "f1"] }`, a single string is
+//! fine too) or from a Star Citizen `actionmaps.xml`, then turn an action into
+//! steps with [`BindingMap::steps`]. That is the only XML format read; other
+//! games' keybinding files need converting to JSON/TOML first.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use quick_xml::events::{BytesStart, Event};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+use tracing::warn;
+
+use super::dsl;
+use super::key::Key;
+use super::types::InputStep;
+
+#[derive(Debug, Error)]
+pub enum BindingError {
+    #[error("empty binding")]
+    Empty,
+    #[error("unknown key `{token}` in binding `{binding}`")]
+    UnknownKey { binding: String, token: String },
+    #[error("`{token}` is not a modifier, but comes before the key in binding `{binding}`")]
+    NotAModifier { binding: String, token: String },
+    #[error("invalid bindings JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid bindings TOML: {0}")]
+    TomlDe(#[from] toml::de::Error),
+    #[error("cannot write bindings as TOML: {0}")]
+    TomlSer(#[from] toml::ser::Error),
+    #[error("invalid keybinding XML: {0}")]
+    Xml(String),
+}
+
+/// Modifiers plus a main key, written `lctrl+lshift+f`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Binding {
+    /// Sorted and deduplicated, so equal chords compare equal.
+    pub mods: Vec<Key>,
+    pub key: Key,
+}
+
+impl Binding {
+    pub fn new(mods: impl IntoIterator<Item = Key>, key: Key) -> Self {
+        let mut mods: Vec<Key> = mods.into_iter().collect();
+        mods.sort();
+        mods.dedup();
+        Self { mods, key }
+    }
+
+    pub fn key(key: Key) -> Self {
+        Self::new([], key)
+    }
+
+    /// Parse `mod+mod+key` with [`Key::parse`] tokens (case-insensitive).
+    /// Everything before the last token must be a modifier.
+    pub fn parse(text: &str) -> Result<Self, BindingError> {
+        let mut keys = Vec::new();
+        for token in text.split('+').map(str::trim) {
+            if token.is_empty() {
+                return Err(BindingError::Empty);
+            }
+            let key = Key::parse(token).ok_or_else(|| BindingError::UnknownKey {
+                binding: text.to_string(),
+                token: token.to_string(),
+            })?;
+            keys.push((token, key));
+        }
+        let (_, key) = keys.pop().ok_or(BindingError::Empty)?;
+        if let Some((token, _)) = keys.iter().find(|(_, k)| !k.is_modifier()) {
+            return Err(BindingError::NotAModifier {
+                binding: text.to_string(),
+                token: token.to_string(),
+            });
+        }
+        Ok(Self::new(keys.into_iter().map(|(_, k)| k), key))
+    }
+
+    /// Press and release the chord.
+    pub fn steps(&self) -> Vec<InputStep> {
+        dsl::chord(&self.mods, self.key)
+    }
+
+    /// Hold the chord for `ms`.
+    pub fn hold_steps(&self, ms: u64) -> Vec<InputStep> {
+        dsl::hold(&self.mods, self.key, ms)
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for m in &self.mods {
+            write!(f, "{}+", m.to_token())?;
+        }
+        f.write_str(self.key.to_token())
+    }
+}
+
+impl FromStr for Binding {
+    type Err = BindingError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Serialize for Binding {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Binding {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(d)?;
+        Self::parse(&text).map_err(serde::de::Error::custom)
+    }
+}
+
+/// One chord bound to several actions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BindingConflict {
+    pub binding: Binding,
+    pub actions: Vec<String>,
+}
+
+/// Either one binding or a list, as accepted in files.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(Binding),
+    Many(Vec<Binding>),
+}
+
+/// Logical action ids → chords, with defaults used for actions the user did not bind.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BindingMap {
+    bindings: BTreeMap<String, Vec<Binding>>,
+    defaults: BTreeMap<String, Vec<Binding>>,
+}
+
+impl BindingMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use another map's effective bindings as fallbacks (chainable). User bindings win per action.
+    pub fn with_defaults(mut self, defaults: BindingMap) -> Self {
+        let BindingMap { bindings, defaults } = defaults;
+        self.defaults = defaults;
+        self.defaults.extend(bindings);
+        self
+    }
+
+    /// Add a fallback for one action (chainable).
+    pub fn default_for(mut self, action: impl Into<String>, binding: Binding) -> Self {
+        self.defaults
+            .entry(action.into())
+            .or_default()
+            .push(binding);
+        self
+    }
+
+    /// Replace an action's bindings. An empty list explicitly unbinds it (defaults included).
+    pub fn set(&mut self, action: impl Into<String>, bindings: Vec<Binding>) {
+        self.bindings.insert(action.into(), bindings);
+    }
+
+    /// Add one more binding to an action.
+    pub fn add(&mut self, action: impl Into<String>, binding: Binding) {
+        let list = self.bindings.entry(action.into()).or_default();
+        if !list.contains(&binding) {
+            list.push(binding);
+        }
+    }
+
+    /// Drop the user's bindings for an action; its defaults apply again.
+    pub fn reset(&mut self, action: &str) {
+        self.bindings.remove(action);
+    }
+
+    /// Bindings in effect for `action`: the user's, else the defaults.
+    pub fn get(&self, action: &str) -> &[Binding] {
+        self.bindings
+            .get(action)
+            .or_else(|| self.defaults.get(action))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// First binding in effect for `action`.
+    pub fn primary(&self, action: &str) -> Option<&Binding> {
+        self.get(action).first()
+    }
+
+    pub fn is_bound(&self, action: &str) -> bool {
+        !self.get(action).is_empty()
+    }
+
+    /// Every action with user bindings or defaults, sorted.
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        let mut all: Vec<&str> = self
+            .bindings
+            .keys()
+            .chain(self.defaults.keys())
+            .map(String::as_str)
+            .collect();
+        all.sort_unstable();
+        all.dedup();
+        all.into_iter()
+    }
+
+    /// Action(s) a chord triggers.
+    pub fn actions_for(&self, binding: &Binding) -> Vec<&str> {
+        self.actions()
+            .filter(|a| self.get(a).contains(binding))
+            .collect()
+    }
+
+    /// Chords bound to more than one action (effective bindings, defaults included).
+    pub fn conflicts(&self) -> Vec<BindingConflict> {
+        let mut by_binding: BTreeMap<&Binding, Vec<String>> = BTreeMap::new();
+        for action in self.actions() {
+            for b in self.get(action) {
+                by_binding.entry(b).or_default().push(action.to_string());
+            }
+        }
+        by_binding
+            .into_iter()
+            .filter(|(_, actions)| actions.len() > 1)
+            .map(|(b, actions)| BindingConflict {
+                binding: b.clone(),
+                actions,
+            })
+            .collect()
+    }
+
+    // ---- steps ----
+
+    /// Tap the action's primary chord; `None` if unbound.
+    pub fn steps(&self, action: &str) -> Option<Vec<InputStep>> {
+        self.primary(action).map(Binding::steps)
+    }
+
+    /// Hold the action's primary chord for `ms`; `None` if unbound.
+    pub fn hold_steps(&self, action: &str, ms: u64) -> Option<Vec<InputStep>> {
+        self.primary(action).map(|b| b.hold_steps(ms))
+    }
+
+    // ---- loading / saving (user bindings only) ----
+
+    pub fn from_json(text: &str) -> Result<Self, BindingError> {
+        Ok(Self::from_entries(serde_json::from_str(text)?))
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, BindingError> {
+        Ok(Self::from_entries(toml::from_str(text)?))
+    }
+
+    fn from_entries(entries: BTreeMap<String, OneOrMany>) -> Self {
+        let bindings = entries
+            .into_iter()
+            .map(|(action, v)| match v {
+                OneOrMany::One(b) => (action, vec![b]),
+                OneOrMany::Many(list) => (action, list),
+            })
+            .collect();
+        Self {
+            bindings,
+            defaults: BTreeMap::new(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.bindings).unwrap_or_default()
+    }
+
+    pub fn to_toml(&self) -> Result<String, BindingError> {
+        Ok(toml::to_string_pretty(&self.bindings)?)
+    }
+
+    /// Keyboard bindings from a Star Citizen `actionmaps.xml` (`<rebind input="kb1_lalt+f"/>`),
+    /// keyed by action name. No other XML format is supported. Joystick, mouse and
+    /// gamepad rebinds are skipped, as are keys `Key` does not know (logged).
+    pub fn from_actionmaps_xml(xml: &str) -> Result<Self, BindingError> {
+        let mut reader = quick_xml::Reader::from_str(xml);
+        let mut map = Self::new();
+        let mut action: Option<String> = None;
+
+        loop {
+            let ev = reader
+                .read_event()
+                .map_err(|e| BindingError::Xml(e.to_string()))?;
+            match ev {
+                Event::Start(e) if e.local_name().as_ref() == b"action" => {
+                    action = xml_attr(&e, "name")?;
+                }
+                Event::End(e) if e.local_name().as_ref() == b"action" => action = None,
+                Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"rebind" => {
+                    let (Some(action), Some(input)) = (&action, xml_attr(&e, "input")?) else {
+                        continue;
+                    };
+                    let Some(chord) = input.strip_prefix("kb1_") else {
+                        continue;
+                    };
+                    // a blank input means "unbound"
+                    if chord.trim().is_empty() {
+                        map.set(action.clone(), Vec::new());
+                        continue;
+                    }
+                    match Binding::parse(chord) {
+                        Ok(b) => map.add(action.clone(), b),
+                        Err(e) => warn!("⚠️ skipping binding for {}: {}", action, e),
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(map)
+    }
+}
+
+fn xml_attr(e: &BytesStart<'_>, name: &str) -> Result<Option<String>, BindingError> {
+    let attr = e
+        .try_get_attribute(name)
+        .map_err(|err| BindingError::Xml(err.to_string()))?;
+    attr.map(|a| {
+        a.unescape_value()
+            .map(|v| v.into_owned())
+            .map_err(|err| BindingError::Xml(err.to_string()))
+    })
+    .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sorts_modifiers_and_round_trips() {
+        let b = Binding::parse("LShift + lctrl+F").unwrap();
+        assert_eq!(b, Binding::new([Key::LCtrl, Key::LShift], Key::F));
+        assert_eq!(Binding::parse(&b.to_string()).unwrap(), b);
+        assert_eq!(Binding::parse("lshift").unwrap(), Binding::key(Key::LShift));
+    }
+
+    #[test]
+    fn parse_rejects_non_modifiers_before_the_key() {
+        assert!(matches!(
+            Binding::parse("a+b+f"),
+            Err(BindingError::NotAModifier { ref token, .. }) if token == "a"
+        ));
+        assert!(matches!(
+            Binding::parse("lctrl+space+f"),
+            Err(BindingError::NotAModifier { ref token, .. }) if token == "space"
+        ));
+        assert!(matches!(
+            Binding::parse("lctrl+nope"),
+            Err(BindingError::UnknownKey { .. })
+        ));
+        assert!(matches!(Binding::parse("lctrl+"), Err(BindingError::Empty)));
+        assert!(matches!(Binding::parse(""), Err(BindingError::Empty)));
+    }
+
+    #[test]
+    fn files_reject_bad_bindings() {
+        assert!(BindingMap::from_json(r#"{ "fire": "a+f" }"#).is_err());
+        let map = BindingMap::from_toml("fire = [\"lctrl+f\", \"f1\"]\njump = \"space\"").unwrap();
+        assert_eq!(map.get("fire").len(), 2);
+        assert_eq!(map.primary("jump"), Some(&Binding::key(Key::Space)));
+        assert_eq!(BindingMap::from_json(&map.to_json()).unwrap(), map);
+    }
+
+    #[test]
+    fn defaults_conflicts_and_steps() {
+        let mut map = BindingMap::new()
+            .default_for("fire", Binding::key(Key::F))
+            .default_for("use", Binding::key(Key::F));
+        assert_eq!(map.conflicts().len(), 1);
+        map.set("use", vec![Binding::key(Key::E)]);
+        assert!(map.conflicts().is_empty());
+        map.set("fire", Vec::new());
+        assert_eq!(map.steps("fire"), None);
+        map.reset("fire");
+        assert_eq!(map.steps("fire"), Some(dsl::tap(Key::F)));
+    }
+
+    #[test]
+    fn actionmaps_xml_keeps_keyboard_rebinds() {
+        let xml = r#"
+            <ActionMaps>
+              <actionmap name="spaceship_weapons">
+                <action name="v_attack1"><rebind input="kb1_lalt+f"/></action>
+                <action name="v_attack2"><rebind input="js1_button1"/></action>
+                <action name="v_toggle"><rebind input="kb1_ "/></action>
+                <action name="v_bad"><rebind input="kb1_q+f"/></action>
+              </actionmap>
+            </ActionMaps>"#;
+        let map = BindingMap::from_actionmaps_xml(xml).unwrap();
+        assert_eq!(map.get("v_attack1"), &[Binding::new([Key::LAlt], Key::F)]);
+        assert!(!map.is_bound("v_attack2"));
+        assert!(!map.is_bound("v_toggle"));
+        assert!(!map.is_bound("v_bad"));
+    }
+}
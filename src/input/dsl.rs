@@ -1,4 +1,4 @@
-use super::{InputStep, Key, MouseButton};
+use super::{InputStep, InputSynth, Key, MouseButton};
 use std::time::Duration;
 
 #[inline]
@@ -50,6 +50,45 @@ pub fn chord(mods: &[Key], main: Key) -> Vec<InputStep> {
     v
 }
 
+/// Like [`chord`], but leaves the user's modifiers as they were: modifiers in `mods`
+/// that are already `held` are neither pressed nor released. Other held modifiers
+/// stay down and join the chord.
+pub fn chord_from_state(mods: &[Key], main: Key, held: &[Key]) -> Vec<InputStep> {
+    let press: Vec<Key> = mods
+        .iter()
+        .copied()
+        .filter(|m| !held.iter().any(|h| same_modifier(*h, *m)))
+        .collect();
+
+    let mut v = Vec::new();
+    v.extend(press.iter().filter_map(|&k| down(k)));
+    v.extend(tap(main));
+    v.extend(press.iter().rev().filter_map(|&k| up(k)));
+    v
+}
+
+/// [`chord_from_state`] with the modifiers `synth` reports as held right now;
+/// a plain [`chord`] if it cannot tell. Build it right before submitting.
+pub fn chord_for<S: InputSynth + ?Sized>(synth: &S, mods: &[Key], main: Key) -> Vec<InputStep> {
+    match synth.modifier_state() {
+        Some(held) => chord_from_state(mods, main, &held),
+        None => chord(mods, main),
+    }
+}
+
+/// Left and right variants count as the same modifier.
+fn same_modifier(a: Key, b: Key) -> bool {
+    use Key::*;
+    let kind = |k: Key| match k {
+        LShift | RShift => Some(0),
+        LCtrl | RCtrl => Some(1),
+        LAlt | RAlt => Some(2),
+        LWin | RWin => Some(3),
+        _ => None,
+    };
+    a == b || (kind(a).is_some() && kind(a) == kind(b))
+}
+
 /// Hold `main` for `ms` with modifiers held.
 #[inline]
 pub fn hold(mods: &[Key], main: Key, ms: u64) -> Vec<InputStep> {
@@ -94,11 +133,122 @@ pub fn click_n(btn: MouseButton, n: usize, between: Option<Duration>) -> Vec<Inp
     for i in 0..n {
         v.push(InputStep::MouseDown(btn));
         v.push(InputStep::MouseUp(btn));
-        if i + 1 != n {
-            if let Some(d) = between {
-                v.push(InputStep::Sleep(d));
-            }
+        if i + 1 != n
+            && let Some(d) = between
+        {
+            v.push(InputStep::Sleep(d));
         }
     }
     v
 }
+
+/// Type `text` character by character (layout-independent where the backend supports it).
+#[inline]
+pub fn type_text(text: &str) -> Vec<InputStep> {
+    text.chars().map(InputStep::Unicode).collect()
+}
+
+/// Move the cursor by `dx`/`dy` pixels.
+#[inline]
+pub fn move_by(dx: i32, dy: i32) -> InputStep {
+    InputStep::MouseMove { dx, dy }
+}
+
+/// Move the cursor to pixel `x`/`y` on the primary screen.
+#[inline]
+pub fn move_to(x: i32, y: i32) -> InputStep {
+    InputStep::MouseMoveTo {
+        x,
+        y,
+        normalized: false,
+    }
+}
+
+/// Move the cursor to `x`/`y` in `0..=65535` across the whole desktop.
+#[inline]
+pub fn move_to_normalized(x: i32, y: i32) -> InputStep {
+    InputStep::MouseMoveTo {
+        x,
+        y,
+        normalized: true,
+    }
+}
+
+/// Scroll by wheel notches; positive is up / right.
+#[inline]
+pub fn scroll(vertical: i32, horizontal: i32) -> InputStep {
+    InputStep::Scroll {
+        vertical,
+        horizontal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::RecordingSynth;
+
+    fn steps(keys: &[(Key, bool)]) -> Vec<InputStep> {
+        keys.iter()
+            .map(|&(k, pressed)| if pressed { down(k) } else { up(k) }.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn nothing_held_is_a_plain_chord() {
+        assert_eq!(
+            chord_from_state(&[Key::LCtrl, Key::LShift], Key::S, &[]),
+            chord(&[Key::LCtrl, Key::LShift], Key::S)
+        );
+    }
+
+    #[test]
+    fn held_modifiers_are_not_pressed_or_released() {
+        // the user holds the right shift; it counts for the left one
+        assert_eq!(
+            chord_from_state(&[Key::LCtrl, Key::LShift], Key::S, &[Key::RShift]),
+            steps(&[
+                (Key::LCtrl, true),
+                (Key::S, true),
+                (Key::S, false),
+                (Key::LCtrl, false),
+            ])
+        );
+    }
+
+    #[test]
+    fn other_held_modifiers_are_left_alone() {
+        assert_eq!(chord_from_state(&[], Key::Tab, &[Key::LAlt]), tap(Key::Tab));
+    }
+
+    #[test]
+    fn chord_for_keeps_the_users_shift_down() {
+        let synth = RecordingSynth::new();
+        synth.hold_physically(Key::LShift);
+        synth
+            .send_steps(chord_for(&synth, &[Key::LShift, Key::LCtrl], Key::Z))
+            .unwrap();
+
+        synth.assert_chord(&[Key::LShift, Key::LCtrl], Key::Z);
+        assert_eq!(synth.presses(Key::LShift), 0);
+        assert!(synth.is_held(Key::LShift));
+        assert!(!synth.is_held(Key::LCtrl));
+
+        synth.release_physically(Key::LShift);
+        synth.assert_nothing_held();
+    }
+
+    #[test]
+    fn chord_for_without_state_presses_everything() {
+        struct Blind;
+        impl InputSynth for Blind {
+            fn send_step(&self, _: &InputStep) -> Result<(), String> {
+                Ok(())
+            }
+        }
+        assert_eq!(
+            chord_for(&Blind, &[Key::LCtrl], Key::C),
+            chord(&[Key::LCtrl], Key::C)
+        );
+    }
+}
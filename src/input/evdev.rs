@@ -0,0 +1,485 @@
+// src/input/evdev.rs
+//! Linux evdev codes and step encoding. Pure data, so it compiles (and can be
+//! checked) on every platform; `LinuxSynth` writes the encoded events to uinput.
+
+use super::key::Key;
+use super::layout::Layout;
+use super::types::{InputStep, MouseButton, Scan};
+
+// Event types (linux/input-event-codes.h)
+pub const EV_SYN: u16 = 0x00;
+pub const EV_KEY: u16 = 0x01;
+pub const EV_REL: u16 = 0x02;
+
+pub const SYN_REPORT: u16 = 0;
+
+pub const REL_X: u16 = 0x00;
+pub const REL_Y: u16 = 0x01;
+pub const REL_HWHEEL: u16 = 0x06;
+pub const REL_WHEEL: u16 = 0x08;
+
+pub const BTN_LEFT: u16 = 0x110;
+pub const BTN_RIGHT: u16 = 0x111;
+pub const BTN_MIDDLE: u16 = 0x112;
+pub const BTN_SIDE: u16 = 0x113;
+pub const BTN_EXTRA: u16 = 0x114;
+
+/// Highest keyboard code we enable on the virtual device.
+pub const KEY_MAX_USED: u16 = 0xf8;
+
+/// One `input_event` without the timestamp (the kernel fills it in).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RawEvent {
+    pub type_: u16,
+    pub code: u16,
+    pub value: i32,
+}
+
+impl RawEvent {
+    pub const fn new(type_: u16, code: u16, value: i32) -> Self {
+        Self { type_, code, value }
+    }
+
+    /// `EV_SYN/SYN_REPORT`, terminates a batch of events.
+    pub const fn syn() -> Self {
+        Self::new(EV_SYN, SYN_REPORT, 0)
+    }
+}
+
+/// evdev keycode for a typed key. `Custom` goes through its scancode.
+pub fn key_code(key: Key) -> Option<u16> {
+    use Key::*;
+    Some(match key {
+        // letters
+        A => 30,
+        B => 48,
+        C => 46,
+        D => 32,
+        E => 18,
+        F => 33,
+        G => 34,
+        H => 35,
+        I => 23,
+        J => 36,
+        K => 37,
+        L => 38,
+        M => 50,
+        N => 49,
+        O => 24,
+        P => 25,
+        Q => 16,
+        R => 19,
+        S => 31,
+        T => 20,
+        U => 22,
+        V => 47,
+        W => 17,
+        X => 45,
+        Y => 21,
+        Z => 44,
+
+        // number row
+        D1 => 2,
+        D2 => 3,
+        D3 => 4,
+        D4 => 5,
+        D5 => 6,
+        D6 => 7,
+        D7 => 8,
+        D8 => 9,
+        D9 => 10,
+        D0 => 11,
+
+        // function
+        F1 => 59,
+        F2 => 60,
+        F3 => 61,
+        F4 => 62,
+        F5 => 63,
+        F6 => 64,
+        F7 => 65,
+        F8 => 66,
+        F9 => 67,
+        F10 => 68,
+        F11 => 87,
+        F12 => 88,
+        F13 => 183,
+        F14 => 184,
+        F15 => 185,
+        F16 => 186,
+        F17 => 187,
+        F18 => 188,
+        F19 => 189,
+        F20 => 190,
+        F21 => 191,
+        F22 => 192,
+        F23 => 193,
+        F24 => 194,
+
+        // modifiers
+        LShift => 42,
+        RShift => 54,
+        LCtrl => 29,
+        RCtrl => 97,
+        LAlt => 56,
+        RAlt => 100,
+        LWin => 125,
+        RWin => 126,
+
+        // misc
+        Space => 57,
+        Tab => 15,
+        Enter => 28,
+        Escape => 1,
+        Backspace => 14,
+        Minus => 12,
+        Equal => 13,
+        LBracket => 26,
+        RBracket => 27,
+        Semicolon => 39,
+        Apostrophe => 40,
+        Comma => 51,
+        Period => 52,
+        Slash => 53,
+        Backslash => 43,
+        Grave => 41,
+        IntlBackslash => 86,
+        CapsLock => 58,
+        Print => 99, // KEY_SYSRQ
+        Pause => 119,
+
+        // nav
+        Insert => 110,
+        Delete => 111,
+        Home => 102,
+        End => 107,
+        PageUp => 104,
+        PageDown => 109,
+        ArrowUp => 103,
+        ArrowDown => 108,
+        ArrowLeft => 105,
+        ArrowRight => 106,
+
+        // numpad
+        Np0 => 82,
+        Np1 => 79,
+        Np2 => 80,
+        Np3 => 81,
+        Np4 => 75,
+        Np5 => 76,
+        Np6 => 77,
+        Np7 => 71,
+        Np8 => 72,
+        Np9 => 73,
+        NpAdd => 78,
+        NpSubtract => 74,
+        NpMultiply => 55,
+        NpDivide => 98,
+        NpEnter => 96,
+        NpDecimal => 83,
+        NpLock => 69,
+
+        Menu => 127, // KEY_COMPOSE
+
+        VolumeUp => 115,
+        VolumeDown => 114,
+        VolumeMute => 113,
+        MediaPlayPause => 164,
+        MediaNext => 163,      // KEY_NEXTSONG
+        MediaPrev => 165,      // KEY_PREVIOUSSONG
+        MediaStop => 166,      // KEY_STOPCD
+        BrowserBack => 158,    // KEY_BACK
+        BrowserForward => 159, // KEY_FORWARD
+        BrowserRefresh => 173, // KEY_REFRESH
+        BrowserHome => 172,    // KEY_HOMEPAGE
+        LaunchMail => 155,
+        LaunchCalculator => 140, // KEY_CALC
+
+        Custom { scan, extended } => return from_scan(Scan::new(scan, extended)),
+    })
+}
+
+/// Translate a Windows set-1 scancode (what `InputStep` carries) to an evdev code.
+///
+/// Plain set-1 codes are numerically the evdev codes; E0-extended keys are mapped
+/// explicitly.
+pub fn from_scan(scan: Scan) -> Option<u16> {
+    if !scan.extended {
+        return match scan.code {
+            0x01..=0x58 => Some(scan.code),
+            0x64..=0x6e => Some(scan.code - 0x64 + 183), // F13..F23
+            0x76 => Some(194),                           // F24
+            _ => None,
+        };
+    }
+    Some(match scan.code {
+        0x10 => 165, // MediaPrev
+        0x19 => 163, // MediaNext
+        0x20 => 113, // VolumeMute
+        0x21 => 140, // LaunchCalculator
+        0x22 => 164, // MediaPlayPause
+        0x24 => 166, // MediaStop
+        0x2e => 114, // VolumeDown
+        0x30 => 115, // VolumeUp
+        0x32 => 172, // BrowserHome
+        0x1c => 96,  // NpEnter
+        0x1d => 97,  // RCtrl
+        0x35 => 98,  // NpDivide
+        0x37 => 99,  // Print
+        0x38 => 100, // RAlt
+        0x47 => 102, // Home
+        0x48 => 103, // Up
+        0x49 => 104, // PageUp
+        0x4b => 105, // Left
+        0x4d => 106, // Right
+        0x4f => 107, // End
+        0x50 => 108, // Down
+        0x51 => 109, // PageDown
+        0x52 => 110, // Insert
+        0x53 => 111, // Delete
+        0x5b => 125, // LWin
+        0x5c => 126, // RWin
+        0x5d => 127, // Menu
+        0x67 => 173, // BrowserRefresh
+        0x69 => 159, // BrowserForward
+        0x6a => 158, // BrowserBack
+        0x6c => 155, // LaunchMail
+        _ => return None,
+    })
+}
+
+/// evdev button code for a mouse button.
+pub fn button_code(btn: MouseButton) -> Option<u16> {
+    match btn {
+        MouseButton::Left => Some(BTN_LEFT),
+        MouseButton::Right => Some(BTN_RIGHT),
+        MouseButton::Middle => Some(BTN_MIDDLE),
+        MouseButton::X(1) => Some(BTN_SIDE),
+        MouseButton::X(2) => Some(BTN_EXTRA),
+        MouseButton::X(_) => None,
+    }
+}
+
+/// [`encode_step_in`] with the US layout.
+pub fn encode_step(step: &InputStep) -> Result<Vec<RawEvent>, String> {
+    encode_step_in(step, Layout::Us)
+}
+
+/// Encode one step as the events to write, including the trailing `SYN_REPORT`.
+/// `Sleep` encodes to nothing; the caller handles the delay.
+/// `Unicode` is typed as key presses for `layout`, which must match the desktop's
+/// layout (uinput has no text input).
+/// `MouseMoveTo` is rejected: the virtual device is a relative mouse.
+pub fn encode_step_in(step: &InputStep, layout: Layout) -> Result<Vec<RawEvent>, String> {
+    let ev = match *step {
+        InputStep::KeyDown(s) => RawEvent::new(EV_KEY, scan_code(s)?, 1),
+        InputStep::KeyUp(s) => RawEvent::new(EV_KEY, scan_code(s)?, 0),
+        InputStep::MouseDown(b) => RawEvent::new(EV_KEY, btn_code(b)?, 1),
+        InputStep::MouseUp(b) => RawEvent::new(EV_KEY, btn_code(b)?, 0),
+        InputStep::Sleep(_) => return Ok(Vec::new()),
+        InputStep::MouseMove { dx, dy } => {
+            return Ok(vec![
+                RawEvent::new(EV_REL, REL_X, dx),
+                RawEvent::new(EV_REL, REL_Y, dy),
+                RawEvent::syn(),
+            ]);
+        }
+        InputStep::MouseMoveTo { .. } => {
+            return Err("absolute mouse moves are not supported by uinput backend".into());
+        }
+        InputStep::Scroll {
+            vertical,
+            horizontal,
+        } => {
+            let mut out = Vec::with_capacity(3);
+            if vertical != 0 {
+                out.push(RawEvent::new(EV_REL, REL_WHEEL, vertical));
+            }
+            if horizontal != 0 {
+                out.push(RawEvent::new(EV_REL, REL_HWHEEL, horizontal));
+            }
+            if !out.is_empty() {
+                out.push(RawEvent::syn());
+            }
+            return Ok(out);
+        }
+        InputStep::Unicode(c) => {
+            let steps = layout
+                .char_steps(c)
+                .ok_or_else(|| format!("cannot type {c:?}: not on the {layout} layout"))?;
+            let mut out = Vec::with_capacity(steps.len() * 2);
+            for s in &steps {
+                out.extend(encode_step_in(s, layout)?);
+            }
+            return Ok(out);
+        }
+    };
+    Ok(vec![ev, RawEvent::syn()])
+}
+
+fn scan_code(s: Scan) -> Result<u16, String> {
+    from_scan(s).ok_or_else(|| format!("no evdev code for {s}"))
+}
+
+fn btn_code(b: MouseButton) -> Result<u16, String> {
+    button_code(b).ok_or_else(|| format!("no evdev button for {b:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn codes_match_input_event_codes_h() {
+        // spot checks against linux/input-event-codes.h
+        let known = [
+            (Key::Escape, 1),
+            (Key::A, 30),
+            (Key::Z, 44),
+            (Key::D0, 11),
+            (Key::Enter, 28),
+            (Key::LShift, 42),
+            (Key::RAlt, 100),
+            (Key::LWin, 125),
+            (Key::F12, 88),
+            (Key::F13, 183),
+            (Key::F24, 194),
+            (Key::ArrowUp, 103),
+            (Key::NpEnter, 96),
+            (Key::Print, 99),
+            (Key::Pause, 119),
+            (Key::Menu, 127),
+            (Key::MediaPlayPause, 164),
+        ];
+        for (key, code) in known {
+            assert_eq!(key_code(key), Some(code), "{key:?}");
+        }
+    }
+
+    #[test]
+    fn every_named_key_has_a_unique_enabled_code() {
+        let mut seen: HashMap<u16, Key> = HashMap::new();
+        for key in Key::iter() {
+            let code = key_code(key).unwrap_or_else(|| panic!("{key:?} has no evdev code"));
+            assert!(
+                code <= KEY_MAX_USED,
+                "{key:?} ({code}) is not enabled on the device"
+            );
+            if let Some(other) = seen.insert(code, key) {
+                panic!("{key:?} and {other:?} share evdev code {code}");
+            }
+        }
+    }
+
+    #[test]
+    fn scancode_route_agrees_with_key_table() {
+        // steps carry scancodes, so from_scan must land on the same code as key_code
+        for key in Key::iter() {
+            if let Some(scan) = key.windows_scan() {
+                assert_eq!(from_scan(scan), key_code(key), "{key:?} via {scan}");
+            }
+        }
+        assert_eq!(
+            key_code(Key::Custom {
+                scan: 0x1e,
+                extended: false
+            }),
+            Some(30)
+        );
+        assert_eq!(from_scan(Scan::new(0x7f, true)), None);
+    }
+
+    #[test]
+    fn key_steps_encode_as_press_plus_syn() {
+        let scan = Key::A.to_scan().unwrap();
+        assert_eq!(
+            encode_step(&InputStep::KeyDown(scan)).unwrap(),
+            vec![RawEvent::new(EV_KEY, 30, 1), RawEvent::syn()]
+        );
+        assert_eq!(
+            encode_step(&InputStep::KeyUp(scan)).unwrap(),
+            vec![RawEvent::new(EV_KEY, 30, 0), RawEvent::syn()]
+        );
+        assert_eq!(
+            encode_step(&InputStep::MouseDown(MouseButton::X(2))).unwrap(),
+            vec![RawEvent::new(EV_KEY, BTN_EXTRA, 1), RawEvent::syn()]
+        );
+    }
+
+    #[test]
+    fn pointer_steps_encode_as_relative_events() {
+        assert_eq!(
+            encode_step(&InputStep::MouseMove { dx: -3, dy: 4 }).unwrap(),
+            vec![
+                RawEvent::new(EV_REL, REL_X, -3),
+                RawEvent::new(EV_REL, REL_Y, 4),
+                RawEvent::syn()
+            ]
+        );
+        assert_eq!(
+            encode_step(&InputStep::Scroll {
+                vertical: 0,
+                horizontal: -1
+            })
+            .unwrap(),
+            vec![RawEvent::new(EV_REL, REL_HWHEEL, -1), RawEvent::syn()]
+        );
+        let none = InputStep::Scroll {
+            vertical: 0,
+            horizontal: 0,
+        };
+        assert!(encode_step(&none).unwrap().is_empty());
+        assert!(
+            encode_step(&InputStep::Sleep(Duration::from_millis(5)))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn unicode_goes_through_the_us_table() {
+        let events = encode_step(&InputStep::Unicode('A')).unwrap();
+        let keys: Vec<_> = events
+            .iter()
+            .filter(|e| e.type_ == EV_KEY)
+            .map(|e| (e.code, e.value))
+            .collect();
+        assert_eq!(keys, vec![(42, 1), (30, 1), (30, 0), (42, 0)]);
+        assert!(encode_step(&InputStep::Unicode('€')).is_err());
+    }
+
+    #[test]
+    fn unicode_follows_the_given_layout() {
+        let keys = |c, layout| -> Vec<(u16, i32)> {
+            encode_step_in(&InputStep::Unicode(c), layout)
+                .unwrap()
+                .iter()
+                .filter(|e| e.type_ == EV_KEY)
+                .map(|e| (e.code, e.value))
+                .collect()
+        };
+        // German `z` sits where the US `y` is (KEY_Y = 21)
+        assert_eq!(keys('z', Layout::De), vec![(21, 1), (21, 0)]);
+        // `€` is AltGr+E on German, AltGr (KEY_RIGHTALT = 100) held around the tap
+        assert_eq!(
+            keys('€', Layout::De),
+            vec![(100, 1), (18, 1), (18, 0), (100, 0)]
+        );
+        // French `a` is on the US `q` key (KEY_Q = 16)
+        assert_eq!(keys('a', Layout::Fr), vec![(16, 1), (16, 0)]);
+    }
+
+    #[test]
+    fn unsupported_steps_are_errors() {
+        let abs = InputStep::MouseMoveTo {
+            x: 1,
+            y: 1,
+            normalized: false,
+        };
+        assert!(encode_step(&abs).is_err());
+        assert!(encode_step(&InputStep::MouseDown(MouseButton::X(3))).is_err());
+    }
+}
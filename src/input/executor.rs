@@ -0,0 +1,680 @@
+// src/input/executor.rs
+//! Worker thread that runs macros as jobs: one at a time, highest priority first,
+//! cancellable at any step.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crossbeam_channel::{Receiver, SendError, Sender};
+
+use super::InputSynth;
+use super::safety::{self, Held, ReleaseAll, Tracked};
+use super::timing::TimingProfile;
+use super::types::InputStep;
+
+/// Identifies a job within one `Executor`.
+pub type JobId = u64;
+
+/// Queue order: higher priorities run first, equal priorities in submit order.
+/// A running job is never interrupted by priority; cancel it instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum JobPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// Where a job is in its life.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    /// Cancelled before or while running; anything it held was released.
+    Cancelled,
+    /// The backend rejected a step; anything it held was released.
+    Failed(String),
+}
+
+impl JobStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Cancelled | JobStatus::Failed(_)
+        )
+    }
+}
+
+/// Called once when a job completes, is cancelled or fails: on the worker thread, or
+/// on the cancelling thread for a job cancelled while still queued.
+pub type StatusFn = Arc<dyn Fn(JobId, &JobStatus) + Send + Sync>;
+
+/// A macro to run: steps plus options.
+#[derive(Clone)]
+pub struct Job {
+    steps: Vec<InputStep>,
+    repeat: u32,
+    priority: JobPriority,
+    timing: Option<TimingProfile>,
+    on_status: Option<StatusFn>,
+}
+
+impl Job {
+    pub fn new(steps: impl IntoIterator<Item = InputStep>) -> Self {
+        Self {
+            steps: steps.into_iter().collect(),
+            repeat: 1,
+            priority: JobPriority::default(),
+            timing: None,
+            on_status: None,
+        }
+    }
+
+    /// Queue priority (chainable).
+    pub fn priority(mut self, priority: JobPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Run the steps `n` times back to back (chainable); 0 counts as 1.
+    /// Cancelling stops the current run and skips the rest.
+    pub fn repeat(mut self, n: u32) -> Self {
+        self.repeat = n.max(1);
+        self
+    }
+
+    /// Humanized timing, applied when the job starts (chainable).
+    /// Repeats get their own jitter; a seeded profile stays reproducible.
+    pub fn timing(mut self, profile: TimingProfile) -> Self {
+        self.timing = Some(profile);
+        self
+    }
+
+    /// Status callback for this job (chainable).
+    pub fn on_status<F>(mut self, f: F) -> Self
+    where
+        F: Fn(JobId, &JobStatus) + Send + Sync + 'static,
+    {
+        self.on_status = Some(Arc::new(f));
+        self
+    }
+}
+
+impl From<Vec<InputStep>> for Job {
+    fn from(steps: Vec<InputStep>) -> Self {
+        Job::new(steps)
+    }
+}
+
+/// State shared between a job's handle and the worker.
+struct JobShared {
+    id: JobId,
+    status: Mutex<JobStatus>,
+    changed: Condvar,
+    cancel: AtomicBool,
+    /// Set by whoever reports the final status, so it is reported once.
+    done: AtomicBool,
+    on_status: Option<StatusFn>,
+}
+
+impl JobShared {
+    fn new(id: JobId, on_status: Option<StatusFn>) -> Self {
+        Self {
+            id,
+            status: Mutex::new(JobStatus::Queued),
+            changed: Condvar::new(),
+            cancel: AtomicBool::new(false),
+            done: AtomicBool::new(false),
+            on_status,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, JobStatus> {
+        self.status.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set(&self, status: JobStatus) {
+        *self.lock() = status;
+        self.changed.notify_all();
+    }
+
+    fn cancelled(&self) -> bool {
+        self.cancel.load(AtomicOrdering::SeqCst)
+    }
+
+    /// Queued → Running, unless cancelled first.
+    fn start(&self) -> bool {
+        let mut status = self.lock();
+        if self.cancelled() {
+            return false;
+        }
+        *status = JobStatus::Running;
+        self.changed.notify_all();
+        true
+    }
+
+    /// A queued job is finished right away; a running one stops at its next step.
+    fn request_cancel(&self) {
+        let queued = {
+            let status = self.lock();
+            self.cancel.store(true, AtomicOrdering::SeqCst);
+            self.changed.notify_all();
+            *status == JobStatus::Queued
+        };
+        if queued {
+            self.finish(JobStatus::Cancelled);
+        }
+    }
+
+    /// Report the final status (first caller wins). The callback runs first so
+    /// `wait()` returning means the callback has run too.
+    fn finish(&self, status: JobStatus) {
+        if self.done.swap(true, AtomicOrdering::SeqCst) {
+            return;
+        }
+        if let Some(cb) = &self.on_status {
+            cb(self.id, &status);
+        }
+        self.set(status);
+    }
+
+    /// Sleep for `d` unless cancelled first. Returns true if cancelled.
+    fn sleep(&self, d: Duration) -> bool {
+        let guard = self.lock();
+        let _ = self
+            .changed
+            .wait_timeout_while(guard, d, |_| !self.cancelled());
+        self.cancelled()
+    }
+}
+
+/// Handle to a submitted job. Cheap to clone.
+#[derive(Clone)]
+pub struct JobHandle {
+    id: JobId,
+    shared: Arc<JobShared>,
+}
+
+impl JobHandle {
+    pub fn id(&self) -> JobId {
+        self.id
+    }
+
+    pub fn status(&self) -> JobStatus {
+        self.shared.lock().clone()
+    }
+
+    /// True while the job is queued or running.
+    pub fn is_running(&self) -> bool {
+        !self.shared.lock().is_finished()
+    }
+
+    /// Stop the job; keys and buttons it pressed are released. A queued job reports
+    /// `Cancelled` right away. No-op once finished.
+    pub fn cancel(&self) {
+        self.shared.request_cancel();
+    }
+
+    /// Block until the job finishes.
+    pub fn wait(&self) -> JobStatus {
+        let guard = self.shared.lock();
+        let guard = self
+            .shared
+            .changed
+            .wait_while(guard, |s| !s.is_finished())
+            .unwrap_or_else(|e| e.into_inner());
+        guard.clone()
+    }
+
+    /// Block until the job finishes or `timeout` passes (`None` on timeout).
+    pub fn wait_timeout(&self, timeout: Duration) -> Option<JobStatus> {
+        let guard = self.shared.lock();
+        let (guard, _) = self
+            .shared
+            .changed
+            .wait_timeout_while(guard, timeout, |s| !s.is_finished())
+            .unwrap_or_else(|e| e.into_inner());
+        guard.is_finished().then(|| guard.clone())
+    }
+}
+
+/// A job in the worker's queue.
+struct Queued {
+    id: JobId,
+    job: Job,
+    shared: Arc<JobShared>,
+}
+
+impl Queued {
+    fn key(&self) -> (JobPriority, std::cmp::Reverse<JobId>) {
+        (self.job.priority, std::cmp::Reverse(self.id))
+    }
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+impl Eq for Queued {}
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// Jobs not yet finished, so `cancel_all` can reach them.
+type Live = Arc<Mutex<Vec<(JobId, Arc<JobShared>)>>>;
+
+/// Worker that runs input jobs one at a time.
+pub struct Executor<S: InputSynth + ?Sized> {
+    tx: Sender<Queued>,
+    join: Option<std::thread::JoinHandle<()>>,
+    out: Arc<Tracked<S>>,
+    next_id: AtomicU64,
+    live: Live,
+}
+
+impl<S: InputSynth + ?Sized> Executor<S> {
+    pub fn new(synth: Arc<S>) -> Self {
+        Self::new_inner(synth, crossbeam_channel::unbounded())
+    }
+
+    /// Bounded queue with backpressure (optional).
+    pub fn new_bounded(synth: Arc<S>, cap: usize) -> Self {
+        Self::new_inner(synth, crossbeam_channel::bounded(cap))
+    }
+
+    fn new_inner(synth: Arc<S>, (tx, rx): (Sender<Queued>, Receiver<Queued>)) -> Self {
+        let out = Arc::new(Tracked::new(synth));
+        let weak: std::sync::Weak<dyn ReleaseAll> = Arc::downgrade(&out) as _;
+        let live: Live = Arc::default();
+        let weak_live = Arc::downgrade(&live);
+        safety::register(
+            weak,
+            Box::new(move || {
+                if let Some(live) = weak_live.upgrade() {
+                    cancel_live(&live);
+                }
+            }),
+        );
+        let out2 = Arc::clone(&out);
+        let live2 = Arc::clone(&live);
+        let join = std::thread::spawn(move || worker(&out2, rx, &live2));
+        Self {
+            tx,
+            join: Some(join),
+            out,
+            next_id: AtomicU64::new(1),
+            live,
+        }
+    }
+
+    /// Queue a job and get a handle to it.
+    pub fn submit(&self, job: impl Into<Job>) -> JobHandle {
+        let (handle, res) = self.try_submit_inner(job.into());
+        if let Err(SendError(q)) = res {
+            // worker gone: report the job as failed right away
+            q.shared
+                .finish(JobStatus::Failed("executor stopped".into()));
+            self.forget(q.id);
+        }
+        handle
+    }
+
+    fn try_submit_inner(&self, job: Job) -> (JobHandle, Result<(), SendError<Queued>>) {
+        let id = self.next_id.fetch_add(1, AtomicOrdering::Relaxed);
+        let shared = Arc::new(JobShared::new(id, job.on_status.clone()));
+        lock_live(&self.live).push((id, Arc::clone(&shared)));
+        let handle = JobHandle {
+            id,
+            shared: Arc::clone(&shared),
+        };
+        (handle, self.tx.send(Queued { id, job, shared }))
+    }
+
+    fn forget(&self, id: JobId) {
+        lock_live(&self.live).retain(|(i, _)| *i != id);
+    }
+
+    /// Cancel every queued and running job.
+    pub fn cancel_all(&self) {
+        cancel_live(&self.live);
+    }
+
+    /// Queue a single step (fire-and-forget).
+    pub fn enqueue(&self, step: InputStep) {
+        let _ = self.try_enqueue(step);
+    }
+
+    /// Queue a single step; surface send error.
+    pub fn try_enqueue(&self, step: InputStep) -> Result<(), SendError<InputStep>> {
+        let (_, res) = self.try_submit_inner(Job::new([step]));
+        res.map_err(|SendError(q)| {
+            self.forget(q.id);
+            SendError(step)
+        })
+    }
+
+    /// Queue steps as one job (fire-and-forget).
+    pub fn enqueue_all<I: IntoIterator<Item = InputStep>>(&self, steps: I) {
+        self.submit(Job::new(steps));
+    }
+
+    /// Cancel every job, then release every key and button still held through this executor.
+    pub fn release_all(&self) {
+        self.cancel_all();
+        self.out.release_all();
+    }
+
+    pub fn synth(&self) -> &Arc<S> {
+        &self.out.synth
+    }
+}
+
+impl<S: InputSynth + ?Sized> Drop for Executor<S> {
+    fn drop(&mut self) {
+        // Cancel first so queued jobs report `Cancelled` and a running one stops;
+        // closing the sender then ends the worker loop once the queue is drained.
+        // Take the join handle so we only join once.
+        self.cancel_all();
+        if let Some(j) = self.join.take() {
+            // swap in a dead sender so ours (the last one) drops and closes the channel
+            let (dead, _) = crossbeam_channel::bounded(0);
+            drop(std::mem::replace(&mut self.tx, dead));
+            let _ = j.join();
+        }
+        // nothing may stay pressed once the executor is gone
+        self.out.release_all();
+    }
+}
+
+/// Cancel every job in `live`. Status callbacks of queued jobs run here, so the
+/// list is copied first: a callback may submit again.
+fn cancel_live(live: &Live) {
+    let jobs: Vec<Arc<JobShared>> = lock_live(live)
+        .iter()
+        .map(|(_, shared)| Arc::clone(shared))
+        .collect();
+    for shared in jobs {
+        shared.request_cancel();
+    }
+}
+
+fn lock_live(live: &Live) -> MutexGuard<'_, Vec<(JobId, Arc<JobShared>)>> {
+    live.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn worker<S: InputSynth + ?Sized>(out: &Tracked<S>, rx: Receiver<Queued>, live: &Live) {
+    let mut queue: BinaryHeap<Queued> = BinaryHeap::new();
+    loop {
+        if queue.is_empty() {
+            match rx.recv() {
+                Ok(q) => queue.push(q),
+                Err(_) => break,
+            }
+        }
+        queue.extend(rx.try_iter());
+
+        let Some(q) = queue.pop() else { continue };
+        let status = run_job(out, &q);
+        q.shared.finish(status);
+        lock_live(live).retain(|(i, _)| *i != q.id);
+    }
+}
+
+fn run_job<S: InputSynth + ?Sized>(out: &Tracked<S>, q: &Queued) -> JobStatus {
+    let shared = &q.shared;
+    if !shared.start() {
+        return JobStatus::Cancelled;
+    }
+
+    let mut held = Held::default();
+    for run in 0..q.job.repeat {
+        let timed;
+        let steps = match q.job.timing {
+            Some(mut t) => {
+                t.seed = t.seed.map(|seed| seed.wrapping_add(u64::from(run)));
+                timed = t.apply(&q.job.steps);
+                &timed
+            }
+            None => &q.job.steps,
+        };
+        if let Some(status) = run_steps(out, shared, steps, &mut held) {
+            return status;
+        }
+    }
+    JobStatus::Completed
+}
+
+/// Send `steps`; `Some` if the job ends early (cancelled or failed), with `held` released.
+fn run_steps<S: InputSynth + ?Sized>(
+    out: &Tracked<S>,
+    shared: &JobShared,
+    steps: &[InputStep],
+    held: &mut Held,
+) -> Option<JobStatus> {
+    for step in steps {
+        if shared.cancelled() {
+            out.release(held);
+            return Some(JobStatus::Cancelled);
+        }
+        if let InputStep::Sleep(d) = *step
+            && out.synth.blocks_on_sleep()
+        {
+            if shared.sleep(d) {
+                out.release(held);
+                return Some(JobStatus::Cancelled);
+            }
+            continue;
+        }
+        if let Err(e) = out.send(step) {
+            out.release(held);
+            return Some(JobStatus::Failed(e));
+        }
+        held.track(step);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Key, RecordingSynth};
+
+    const WAIT: Duration = Duration::from_secs(5);
+
+    fn down(k: Key) -> InputStep {
+        k.to_step_down().unwrap()
+    }
+
+    fn up(k: Key) -> InputStep {
+        k.to_step_up().unwrap()
+    }
+
+    /// Park the worker (in a job's status callback) until the returned sender fires,
+    /// so later submissions pile up in the queue. Returns once the worker is parked.
+    fn park<S: InputSynth + ?Sized>(exec: &Executor<S>) -> crossbeam_channel::Sender<()> {
+        let (go, gate) = crossbeam_channel::bounded::<()>(0);
+        let (parked_tx, parked) = crossbeam_channel::bounded::<()>(1);
+        exec.submit(Job::new([]).on_status(move |_, _| {
+            let _ = parked_tx.send(());
+            let _ = gate.recv_timeout(WAIT);
+        }));
+        parked.recv_timeout(WAIT).expect("worker parked");
+        go
+    }
+
+    #[test]
+    fn job_runs_on_the_virtual_clock() {
+        let synth = Arc::new(RecordingSynth::new());
+        let exec = Executor::new(Arc::clone(&synth));
+        let steps = vec![
+            down(Key::A),
+            InputStep::Sleep(Duration::from_millis(50)),
+            up(Key::A),
+        ];
+        let job = exec.submit(steps.clone());
+
+        assert_eq!(job.wait_timeout(WAIT), Some(JobStatus::Completed));
+        assert_eq!(synth.steps(), steps);
+        assert_eq!(synth.elapsed(), Duration::from_millis(50));
+        synth.assert_nothing_held();
+    }
+
+    #[test]
+    fn higher_priority_runs_first() {
+        let synth = Arc::new(RecordingSynth::new());
+        let exec = Executor::new(Arc::clone(&synth));
+        let go = park(&exec);
+
+        let low = exec.submit(Job::new([down(Key::L), up(Key::L)]).priority(JobPriority::Low));
+        let high = exec.submit(Job::new([down(Key::H), up(Key::H)]).priority(JobPriority::High));
+        go.send(()).unwrap();
+
+        assert_eq!(low.wait_timeout(WAIT), Some(JobStatus::Completed));
+        assert_eq!(high.wait_timeout(WAIT), Some(JobStatus::Completed));
+        assert_eq!(
+            synth.steps(),
+            vec![down(Key::H), up(Key::H), down(Key::L), up(Key::L)]
+        );
+    }
+
+    #[test]
+    fn status_callback_sees_the_final_status() {
+        let synth = Arc::new(RecordingSynth::new());
+        let exec = Executor::new(synth);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen2 = Arc::clone(&seen);
+        let job = exec.submit(
+            Job::new([down(Key::A), up(Key::A)])
+                .on_status(move |id, s| seen2.lock().unwrap().push((id, s.clone()))),
+        );
+
+        job.wait_timeout(WAIT).unwrap();
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![(job.id(), JobStatus::Completed)]
+        );
+    }
+
+    #[test]
+    fn dropping_the_executor_releases_held_keys() {
+        let synth = Arc::new(RecordingSynth::new());
+        let exec = Executor::new(Arc::clone(&synth));
+        exec.submit(vec![down(Key::LShift), down(Key::A)])
+            .wait_timeout(WAIT)
+            .unwrap();
+        assert!(synth.is_held(Key::LShift));
+
+        drop(exec);
+        synth.assert_nothing_held();
+    }
+
+    #[test]
+    fn cancelling_a_queued_job_reports_cancelled_at_once() {
+        let synth = Arc::new(RecordingSynth::new());
+        let exec = Executor::new(Arc::clone(&synth));
+        let go = park(&exec);
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let calls2 = Arc::clone(&calls);
+        let job = exec.submit(
+            Job::new([down(Key::A), up(Key::A)])
+                .on_status(move |_, s| calls2.lock().unwrap().push(s.clone())),
+        );
+        assert_eq!(job.status(), JobStatus::Queued);
+        job.cancel();
+        // no waiting on the worker, which is still parked
+        assert_eq!(job.status(), JobStatus::Cancelled);
+        assert!(!job.is_running());
+
+        go.send(()).unwrap();
+        drop(exec);
+        assert_eq!(*calls.lock().unwrap(), vec![JobStatus::Cancelled]);
+        assert!(synth.steps().is_empty());
+    }
+
+    #[test]
+    fn dropping_the_executor_cancels_queued_jobs() {
+        let synth = Arc::new(RecordingSynth::new());
+        let exec = Executor::new(Arc::clone(&synth));
+        let go = park(&exec);
+        let queued: Vec<_> = (0..3)
+            .map(|_| exec.submit(vec![down(Key::A), up(Key::A)]))
+            .collect();
+
+        // release the worker only once the drop is under way
+        let t = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            go.send(()).unwrap();
+        });
+        drop(exec);
+        t.join().unwrap();
+
+        for job in queued {
+            assert_eq!(job.status(), JobStatus::Cancelled);
+        }
+        assert!(synth.steps().is_empty());
+    }
+
+    #[test]
+    fn cancel_callback_may_submit_again() {
+        let synth = Arc::new(RecordingSynth::new());
+        let exec = Arc::new(Executor::new(Arc::clone(&synth)));
+        let go = park(&exec);
+
+        let weak = Arc::downgrade(&exec);
+        let retried = Arc::new(Mutex::new(None));
+        let retried2 = Arc::clone(&retried);
+        exec.submit(Job::new([down(Key::A), up(Key::A)]).on_status(move |_, s| {
+            if *s == JobStatus::Cancelled
+                && let Some(exec) = weak.upgrade()
+            {
+                *retried2.lock().unwrap() = Some(exec.submit(vec![down(Key::B), up(Key::B)]));
+            }
+        }));
+        exec.cancel_all();
+        go.send(()).unwrap();
+
+        let retry = retried.lock().unwrap().take().expect("callback ran");
+        assert_eq!(retry.wait_timeout(WAIT), Some(JobStatus::Completed));
+        assert_eq!(synth.presses(Key::A), 0);
+        assert_eq!(synth.presses(Key::B), 1);
+    }
+
+    #[test]
+    fn global_release_cancels_queued_jobs_first() {
+        let synth = Arc::new(RecordingSynth::new());
+        let exec = Executor::new(Arc::clone(&synth));
+        exec.submit(vec![down(Key::LShift)])
+            .wait_timeout(WAIT)
+            .unwrap();
+        let go = park(&exec);
+        let queued = exec.submit(vec![down(Key::A)]);
+
+        // `safety::release_all` restricted to this executor; other tests run in parallel
+        let ours: Arc<dyn ReleaseAll> = exec.out.clone();
+        let mine: Vec<_> = safety::live_entries()
+            .into_iter()
+            .filter(|(t, _)| Arc::ptr_eq(t, &ours))
+            .collect();
+        assert_eq!(mine.len(), 1);
+        safety::release(&mine);
+
+        assert_eq!(queued.status(), JobStatus::Cancelled);
+        synth.assert_nothing_held();
+        go.send(()).unwrap();
+        drop(exec);
+        assert_eq!(synth.presses(Key::A), 0);
+    }
+}
@@ -1,5 +1,5 @@
 // src/input/key.rs
-//! Typed key identifiers with a built-in Windows scancode map.
+//! Typed key identifiers with built-in Windows scancode, evdev and macOS keycode maps.
 //! Keeps scancode math out of plugins. No game semantics here.
 
 use std::fmt;
@@ -8,10 +8,11 @@ use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
+use super::layout::{Layout, LayoutKey};
 use super::types::{InputStep, Scan};
 
 /// Typed keys. Add more as you need; `Custom` lets you provide raw scancodes.
-/// Mapping is Windows-only right now (guarded with #[cfg(windows)]).
+/// Code tables are plain data and work on every platform.
 #[non_exhaustive]
 #[derive(
     Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, PartialOrd, Ord, EnumIter,
@@ -70,6 +71,18 @@ pub enum Key {
     F10,
     F11,
     F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
 
     // Modifiers
     LShift,
@@ -98,6 +111,8 @@ pub enum Key {
     Slash,
     Backslash,
     Grave, // `~ key
+    /// Extra key left of Z on ISO keyboards (`<>` on DE/FR, `\|` on UK).
+    IntlBackslash,
     CapsLock,
     Print, // Print Screen
     Pause, // Pause/Break
@@ -136,6 +151,23 @@ pub enum Key {
     // Menu / context
     Menu,
 
+    // Media
+    VolumeUp,
+    VolumeDown,
+    VolumeMute,
+    MediaPlayPause,
+    MediaNext,
+    MediaPrev,
+    MediaStop,
+
+    // Browser / launch
+    BrowserBack,
+    BrowserForward,
+    BrowserRefresh,
+    BrowserHome,
+    LaunchMail,
+    LaunchCalculator,
+
     /// Raw scancode (Windows SetScanCode) + extended flag.
     /// Use to cover keys not yet in the enum.
     #[strum(disabled)]
@@ -182,68 +214,68 @@ impl Key {
     /// Best-effort name parser (case-insensitive). Keeps this in the lib so
     /// plugin PIs can pass strings safely without hand-rolled tables.
     ///
-    /// Examples: "a", "F5", "lctrl", "np_1", "npDivide", "arrow_left", "left"
+    /// Examples: "a", "F5", "lctrl", "ctrl", "np_1", "arrow_left", "left", "-"
     pub fn parse(name: &str) -> Option<Self> {
         let s = name.trim().to_lowercase();
 
         // letters
-        if s.len() == 1 {
-            if let Some(c) = s.chars().next() {
-                if c.is_ascii_lowercase() {
-                    return Some(match c {
-                        'a' => Key::A,
-                        'b' => Key::B,
-                        'c' => Key::C,
-                        'd' => Key::D,
-                        'e' => Key::E,
-                        'f' => Key::F,
-                        'g' => Key::G,
-                        'h' => Key::H,
-                        'i' => Key::I,
-                        'j' => Key::J,
-                        'k' => Key::K,
-                        'l' => Key::L,
-                        'm' => Key::M,
-                        'n' => Key::N,
-                        'o' => Key::O,
-                        'p' => Key::P,
-                        'q' => Key::Q,
-                        'r' => Key::R,
-                        's' => Key::S,
-                        't' => Key::T,
-                        'u' => Key::U,
-                        'v' => Key::V,
-                        'w' => Key::W,
-                        'x' => Key::X,
-                        'y' => Key::Y,
-                        'z' => Key::Z,
-                        _ => {
-                            return None;
-                        }
-                    });
-                }
-                if c.is_ascii_digit() {
-                    return Some(match c {
-                        '0' => Key::D0,
-                        '1' => Key::D1,
-                        '2' => Key::D2,
-                        '3' => Key::D3,
-                        '4' => Key::D4,
-                        '5' => Key::D5,
-                        '6' => Key::D6,
-                        '7' => Key::D7,
-                        '8' => Key::D8,
-                        '9' => Key::D9,
-                        _ => {
-                            return None;
-                        }
-                    });
-                }
+        if s.len() == 1
+            && let Some(c) = s.chars().next()
+        {
+            if c.is_ascii_lowercase() {
+                return Some(match c {
+                    'a' => Key::A,
+                    'b' => Key::B,
+                    'c' => Key::C,
+                    'd' => Key::D,
+                    'e' => Key::E,
+                    'f' => Key::F,
+                    'g' => Key::G,
+                    'h' => Key::H,
+                    'i' => Key::I,
+                    'j' => Key::J,
+                    'k' => Key::K,
+                    'l' => Key::L,
+                    'm' => Key::M,
+                    'n' => Key::N,
+                    'o' => Key::O,
+                    'p' => Key::P,
+                    'q' => Key::Q,
+                    'r' => Key::R,
+                    's' => Key::S,
+                    't' => Key::T,
+                    'u' => Key::U,
+                    'v' => Key::V,
+                    'w' => Key::W,
+                    'x' => Key::X,
+                    'y' => Key::Y,
+                    'z' => Key::Z,
+                    _ => {
+                        return None;
+                    }
+                });
+            }
+            if c.is_ascii_digit() {
+                return Some(match c {
+                    '0' => Key::D0,
+                    '1' => Key::D1,
+                    '2' => Key::D2,
+                    '3' => Key::D3,
+                    '4' => Key::D4,
+                    '5' => Key::D5,
+                    '6' => Key::D6,
+                    '7' => Key::D7,
+                    '8' => Key::D8,
+                    '9' => Key::D9,
+                    _ => {
+                        return None;
+                    }
+                });
             }
         }
 
-        // common aliases
-        let s = s.replace('-', "_");
+        // common aliases ("-" itself is the minus key, not a separator)
+        let s = if s == "-" { s } else { s.replace('-', "_") };
         let s = s.as_str();
         Some(match s {
             // function
@@ -259,13 +291,25 @@ impl Key {
             "f10" => Key::F10,
             "f11" => Key::F11,
             "f12" => Key::F12,
+            "f13" => Key::F13,
+            "f14" => Key::F14,
+            "f15" => Key::F15,
+            "f16" => Key::F16,
+            "f17" => Key::F17,
+            "f18" => Key::F18,
+            "f19" => Key::F19,
+            "f20" => Key::F20,
+            "f21" => Key::F21,
+            "f22" => Key::F22,
+            "f23" => Key::F23,
+            "f24" => Key::F24,
 
             // modifiers
-            "lshift" | "left_shift" => Key::LShift,
+            "lshift" | "left_shift" | "shift" => Key::LShift,
             "rshift" | "right_shift" => Key::RShift,
-            "lctrl" | "left_ctrl" => Key::LCtrl,
+            "lctrl" | "left_ctrl" | "ctrl" | "control" => Key::LCtrl,
             "rctrl" | "right_ctrl" => Key::RCtrl,
-            "lalt" | "left_alt" => Key::LAlt,
+            "lalt" | "left_alt" | "alt" => Key::LAlt,
             "ralt" | "right_alt" => Key::RAlt,
             "lwin" | "left_win" | "lmeta" | "super" | "meta" | "win" => Key::LWin,
             "rwin" | "right_win" | "rmeta" => Key::RWin,
@@ -277,7 +321,7 @@ impl Key {
             "esc" | "escape" => Key::Escape,
             "backspace" => Key::Backspace,
             "minus" | "-" => Key::Minus,
-            "equal" | "=" => Key::Equal,
+            "equal" | "equals" | "=" => Key::Equal,
             "[" | "lbracket" => Key::LBracket,
             "]" | "rbracket" => Key::RBracket,
             ";" | "semicolon" => Key::Semicolon,
@@ -287,6 +331,7 @@ impl Key {
             "/" | "slash" => Key::Slash,
             "\\" | "backslash" => Key::Backslash,
             "`" | "grave" | "tilde" => Key::Grave,
+            "intl_backslash" | "oem_102" | "102nd" => Key::IntlBackslash,
 
             // arrows / nav
             "up" | "arrow_up" => Key::ArrowUp,
@@ -317,161 +362,411 @@ impl Key {
             "np_divide" | "numpad_divide" => Key::NpDivide,
             "np_enter" | "numpad_enter" => Key::NpEnter,
             "np_period" | "numpad_decimal" | "np_decimal" => Key::NpDecimal,
+            "np_lock" | "numlock" | "num_lock" => Key::NpLock,
 
             "menu" | "apps" | "context" => Key::Menu,
             "capslock" | "caps_lock" => Key::CapsLock,
             "print" | "prtsc" | "print_screen" => Key::Print,
             "pause" | "break" => Key::Pause,
+
+            // media / browser / launch
+            "volume_up" | "vol_up" | "volumeup" => Key::VolumeUp,
+            "volume_down" | "vol_down" | "volumedown" => Key::VolumeDown,
+            "volume_mute" | "mute" => Key::VolumeMute,
+            "media_play_pause" | "play_pause" | "playpause" => Key::MediaPlayPause,
+            "media_next" | "next_track" => Key::MediaNext,
+            "media_prev" | "prev_track" | "previous_track" => Key::MediaPrev,
+            "media_stop" | "stop" => Key::MediaStop,
+            "browser_back" => Key::BrowserBack,
+            "browser_forward" => Key::BrowserForward,
+            "browser_refresh" => Key::BrowserRefresh,
+            "browser_home" => Key::BrowserHome,
+            "launch_mail" | "mail" => Key::LaunchMail,
+            "launch_calculator" | "calculator" | "calc" => Key::LaunchCalculator,
             _ => {
                 return None;
             }
         })
     }
 
+    /// Layout-aware [`Key::parse`]: also accepts the layout's characters (`ß`, `<`,
+    /// AZERTY letters) and localized names (`Entf`, `Suppr`). Returns the physical
+    /// key plus the modifiers the character needs.
+    pub fn parse_with_layout(name: &str, layout: Layout) -> Option<LayoutKey> {
+        layout.parse_key(name)
+    }
+
+    /// Name shown for this key on `layout`; round-trips through [`Key::parse_with_layout`].
+    pub fn display_name(self, layout: Layout) -> String {
+        layout.display_name(self)
+    }
+
     /// Convert to a Windows scancode (SetScanCode) + extended flag.
-    /// For non-Windows targets, this returns `None`.
-    /// /// (e.g. `Print` and `Pause`) or when compiled for non-Windows targets.
+    /// Steps always carry set-1 scancodes on every platform; backends translate them.
+    /// Returns `None` for keys without a single scancode (e.g. `Print` and `Pause`).
+    #[inline]
     pub fn to_scan(self) -> Option<Scan> {
-        #[cfg(windows)]
-        {
-            use Key::*;
-            let (ext, sc) = match self {
-                // letters
-                A => (false, 0x1e),
-                B => (false, 0x30),
-                C => (false, 0x2e),
-                D => (false, 0x20),
-                E => (false, 0x12),
-                F => (false, 0x21),
-                G => (false, 0x22),
-                H => (false, 0x23),
-                I => (false, 0x17),
-                J => (false, 0x24),
-                K => (false, 0x25),
-                L => (false, 0x26),
-                M => (false, 0x32),
-                N => (false, 0x31),
-                O => (false, 0x18),
-                P => (false, 0x19),
-                Q => (false, 0x10),
-                R => (false, 0x13),
-                S => (false, 0x1f),
-                T => (false, 0x14),
-                U => (false, 0x16),
-                V => (false, 0x2f),
-                W => (false, 0x11),
-                X => (false, 0x2d),
-                Y => (false, 0x15),
-                Z => (false, 0x2c),
-
-                // number row
-                D1 => (false, 0x02),
-                D2 => (false, 0x03),
-                D3 => (false, 0x04),
-                D4 => (false, 0x05),
-                D5 => (false, 0x06),
-                D6 => (false, 0x07),
-                D7 => (false, 0x08),
-                D8 => (false, 0x09),
-                D9 => (false, 0x0a),
-                D0 => (false, 0x0b),
-
-                // function
-                F1 => (false, 0x3b),
-                F2 => (false, 0x3c),
-                F3 => (false, 0x3d),
-                F4 => (false, 0x3e),
-                F5 => (false, 0x3f),
-                F6 => (false, 0x40),
-                F7 => (false, 0x41),
-                F8 => (false, 0x42),
-                F9 => (false, 0x43),
-                F10 => (false, 0x44),
-                F11 => (false, 0x57),
-                F12 => (false, 0x58),
-
-                // modifiers
-                LShift => (false, 0x2a),
-                RShift => (false, 0x36),
-                LCtrl => (false, 0x1d),
-                RCtrl => (true, 0x1d),
-                LAlt => (false, 0x38),
-                RAlt => (true, 0x38),
-                LWin => (true, 0x5b),
-                RWin => (true, 0x5c),
-
-                // misc
-                Space => (false, 0x39),
-                Tab => (false, 0x0f),
-                Enter => (false, 0x1c),
-                Escape => (false, 0x01),
-                Backspace => (false, 0x0e),
-                Minus => (false, 0x0c),
-                Equal => (false, 0x0d),
-                LBracket => (false, 0x1a),
-                RBracket => (false, 0x1b),
-                Semicolon => (false, 0x27),
-                Apostrophe => (false, 0x28),
-                Comma => (false, 0x33),
-                Period => (false, 0x34),
-                Slash => (false, 0x35),
-                Backslash => (false, 0x2b),
-                Grave => (false, 0x29),
-                CapsLock => (false, 0x3a),
-                // Print => E0 2A E0 37
-                // Pause => E1 1D 45 E1 9D C5
-
-                // nav
-                Insert => (true, 0x52),
-                Delete => (true, 0x53),
-                Home => (true, 0x47),
-                End => (true, 0x4f),
-                PageUp => (true, 0x49),
-                PageDown => (true, 0x51),
-                ArrowUp => (true, 0x48),
-                ArrowDown => (true, 0x50),
-                ArrowLeft => (true, 0x4b),
-                ArrowRight => (true, 0x4d),
-
-                // numpad
-                Np0 => (false, 0x52),
-                Np1 => (false, 0x4f),
-                Np2 => (false, 0x50),
-                Np3 => (false, 0x51),
-                Np4 => (false, 0x4b),
-                Np5 => (false, 0x4c),
-                Np6 => (false, 0x4d),
-                Np7 => (false, 0x47),
-                Np8 => (false, 0x48),
-                Np9 => (false, 0x49),
-                NpAdd => (false, 0x4e),
-                NpSubtract => (false, 0x4a),
-                NpMultiply => (false, 0x37),
-                NpDivide => (true, 0x35),
-                NpEnter => (true, 0x1c),
-                NpDecimal => (false, 0x53),
-                NpLock => (false, 0x45),
-
-                Menu => (true, 0x5d),
-
-                Custom { scan, extended } => {
-                    return Some(Scan::new(scan, extended));
-                }
-
-                _ => {
-                    return None;
-                }
-            };
-            Some(Scan::new(sc, ext))
-        }
-        #[cfg(not(windows))]
-        {
-            // No mapping for non-Windows yet.
-            match self {
-                Key::Custom { scan, extended } => Some(Scan::new(scan, extended)),
-                _ => None,
+        self.windows_scan()
+    }
+
+    /// Windows set-1 scancode + extended (E0) flag. Pure data, available on all platforms.
+    pub fn windows_scan(self) -> Option<Scan> {
+        use Key::*;
+        let (ext, sc) = match self {
+            // letters
+            A => (false, 0x1e),
+            B => (false, 0x30),
+            C => (false, 0x2e),
+            D => (false, 0x20),
+            E => (false, 0x12),
+            F => (false, 0x21),
+            G => (false, 0x22),
+            H => (false, 0x23),
+            I => (false, 0x17),
+            J => (false, 0x24),
+            K => (false, 0x25),
+            L => (false, 0x26),
+            M => (false, 0x32),
+            N => (false, 0x31),
+            O => (false, 0x18),
+            P => (false, 0x19),
+            Q => (false, 0x10),
+            R => (false, 0x13),
+            S => (false, 0x1f),
+            T => (false, 0x14),
+            U => (false, 0x16),
+            V => (false, 0x2f),
+            W => (false, 0x11),
+            X => (false, 0x2d),
+            Y => (false, 0x15),
+            Z => (false, 0x2c),
+
+            // number row
+            D1 => (false, 0x02),
+            D2 => (false, 0x03),
+            D3 => (false, 0x04),
+            D4 => (false, 0x05),
+            D5 => (false, 0x06),
+            D6 => (false, 0x07),
+            D7 => (false, 0x08),
+            D8 => (false, 0x09),
+            D9 => (false, 0x0a),
+            D0 => (false, 0x0b),
+
+            // function
+            F1 => (false, 0x3b),
+            F2 => (false, 0x3c),
+            F3 => (false, 0x3d),
+            F4 => (false, 0x3e),
+            F5 => (false, 0x3f),
+            F6 => (false, 0x40),
+            F7 => (false, 0x41),
+            F8 => (false, 0x42),
+            F9 => (false, 0x43),
+            F10 => (false, 0x44),
+            F11 => (false, 0x57),
+            F12 => (false, 0x58),
+            F13 => (false, 0x64),
+            F14 => (false, 0x65),
+            F15 => (false, 0x66),
+            F16 => (false, 0x67),
+            F17 => (false, 0x68),
+            F18 => (false, 0x69),
+            F19 => (false, 0x6a),
+            F20 => (false, 0x6b),
+            F21 => (false, 0x6c),
+            F22 => (false, 0x6d),
+            F23 => (false, 0x6e),
+            F24 => (false, 0x76),
+
+            // modifiers
+            LShift => (false, 0x2a),
+            RShift => (false, 0x36),
+            LCtrl => (false, 0x1d),
+            RCtrl => (true, 0x1d),
+            LAlt => (false, 0x38),
+            RAlt => (true, 0x38),
+            LWin => (true, 0x5b),
+            RWin => (true, 0x5c),
+
+            // misc
+            Space => (false, 0x39),
+            Tab => (false, 0x0f),
+            Enter => (false, 0x1c),
+            Escape => (false, 0x01),
+            Backspace => (false, 0x0e),
+            Minus => (false, 0x0c),
+            Equal => (false, 0x0d),
+            LBracket => (false, 0x1a),
+            RBracket => (false, 0x1b),
+            Semicolon => (false, 0x27),
+            Apostrophe => (false, 0x28),
+            Comma => (false, 0x33),
+            Period => (false, 0x34),
+            Slash => (false, 0x35),
+            Backslash => (false, 0x2b),
+            Grave => (false, 0x29),
+            IntlBackslash => (false, 0x56),
+            CapsLock => (false, 0x3a),
+            // Print => E0 2A E0 37
+            // Pause => E1 1D 45 E1 9D C5
+
+            // nav
+            Insert => (true, 0x52),
+            Delete => (true, 0x53),
+            Home => (true, 0x47),
+            End => (true, 0x4f),
+            PageUp => (true, 0x49),
+            PageDown => (true, 0x51),
+            ArrowUp => (true, 0x48),
+            ArrowDown => (true, 0x50),
+            ArrowLeft => (true, 0x4b),
+            ArrowRight => (true, 0x4d),
+
+            // numpad
+            Np0 => (false, 0x52),
+            Np1 => (false, 0x4f),
+            Np2 => (false, 0x50),
+            Np3 => (false, 0x51),
+            Np4 => (false, 0x4b),
+            Np5 => (false, 0x4c),
+            Np6 => (false, 0x4d),
+            Np7 => (false, 0x47),
+            Np8 => (false, 0x48),
+            Np9 => (false, 0x49),
+            NpAdd => (false, 0x4e),
+            NpSubtract => (false, 0x4a),
+            NpMultiply => (false, 0x37),
+            NpDivide => (true, 0x35),
+            NpEnter => (true, 0x1c),
+            NpDecimal => (false, 0x53),
+            NpLock => (false, 0x45),
+
+            Menu => (true, 0x5d),
+
+            // media / browser / launch
+            VolumeUp => (true, 0x30),
+            VolumeDown => (true, 0x2e),
+            VolumeMute => (true, 0x20),
+            MediaPlayPause => (true, 0x22),
+            MediaNext => (true, 0x19),
+            MediaPrev => (true, 0x10),
+            MediaStop => (true, 0x24),
+            BrowserBack => (true, 0x6a),
+            BrowserForward => (true, 0x69),
+            BrowserRefresh => (true, 0x67),
+            BrowserHome => (true, 0x32),
+            LaunchMail => (true, 0x6c),
+            LaunchCalculator => (true, 0x21),
+
+            Custom { scan, extended } => {
+                return Some(Scan::new(scan, extended));
             }
-        }
+
+            _ => {
+                return None;
+            }
+        };
+        Some(Scan::new(sc, ext))
+    }
+
+    /// Windows virtual-key code for media, browser and launch keys. Windows ignores
+    /// their scancodes in `SendInput`, so backends send these by VK instead.
+    pub fn windows_media_vk(self) -> Option<u16> {
+        use Key::*;
+        Some(match self {
+            VolumeUp => 0xaf,
+            VolumeDown => 0xae,
+            VolumeMute => 0xad,
+            MediaPlayPause => 0xb3,
+            MediaNext => 0xb0,
+            MediaPrev => 0xb1,
+            MediaStop => 0xb2,
+            BrowserBack => 0xa6,
+            BrowserForward => 0xa7,
+            BrowserRefresh => 0xa8,
+            BrowserHome => 0xac,
+            LaunchMail => 0xb4,
+            LaunchCalculator => 0xb7, // VK_LAUNCH_APP2
+            _ => return None,
+        })
+    }
+
+    /// Linux evdev keycode (`KEY_*`). Pure data, available on all platforms.
+    #[inline]
+    pub fn evdev_code(self) -> Option<u16> {
+        super::evdev::key_code(self)
+    }
+
+    /// macOS virtual keycode (`kVK_*`). Pure data, available on all platforms.
+    /// `None` for keys a Mac keyboard does not have (`Print`, `Pause`, `F21`-`F24`),
+    /// for media/browser/launch keys (system events on macOS) and for `Custom`.
+    pub fn mac_vk(self) -> Option<u16> {
+        use Key::*;
+        Some(match self {
+            // letters
+            A => 0x00,
+            B => 0x0b,
+            C => 0x08,
+            D => 0x02,
+            E => 0x0e,
+            F => 0x03,
+            G => 0x05,
+            H => 0x04,
+            I => 0x22,
+            J => 0x26,
+            K => 0x28,
+            L => 0x25,
+            M => 0x2e,
+            N => 0x2d,
+            O => 0x1f,
+            P => 0x23,
+            Q => 0x0c,
+            R => 0x0f,
+            S => 0x01,
+            T => 0x11,
+            U => 0x20,
+            V => 0x09,
+            W => 0x0d,
+            X => 0x07,
+            Y => 0x10,
+            Z => 0x06,
+
+            // number row
+            D1 => 0x12,
+            D2 => 0x13,
+            D3 => 0x14,
+            D4 => 0x15,
+            D5 => 0x17,
+            D6 => 0x16,
+            D7 => 0x1a,
+            D8 => 0x1c,
+            D9 => 0x19,
+            D0 => 0x1d,
+
+            // function
+            F1 => 0x7a,
+            F2 => 0x78,
+            F3 => 0x63,
+            F4 => 0x76,
+            F5 => 0x60,
+            F6 => 0x61,
+            F7 => 0x62,
+            F8 => 0x64,
+            F9 => 0x65,
+            F10 => 0x6d,
+            F11 => 0x67,
+            F12 => 0x6f,
+            F13 => 0x69,
+            F14 => 0x6b,
+            F15 => 0x71,
+            F16 => 0x6a,
+            F17 => 0x40,
+            F18 => 0x4f,
+            F19 => 0x50,
+            F20 => 0x5a,
+
+            // modifiers (Win = Command, Alt = Option)
+            LShift => 0x38,
+            RShift => 0x3c,
+            LCtrl => 0x3b,
+            RCtrl => 0x3e,
+            LAlt => 0x3a,
+            RAlt => 0x3d,
+            LWin => 0x37,
+            RWin => 0x36,
+
+            // misc
+            Space => 0x31,
+            Tab => 0x30,
+            Enter => 0x24,
+            Escape => 0x35,
+            Backspace => 0x33,
+            Minus => 0x1b,
+            Equal => 0x18,
+            LBracket => 0x21,
+            RBracket => 0x1e,
+            Semicolon => 0x29,
+            Apostrophe => 0x27,
+            Comma => 0x2b,
+            Period => 0x2f,
+            Slash => 0x2c,
+            Backslash => 0x2a,
+            Grave => 0x32,
+            IntlBackslash => 0x0a,
+            CapsLock => 0x39,
+
+            // nav (Insert = Help)
+            Insert => 0x72,
+            Delete => 0x75,
+            Home => 0x73,
+            End => 0x77,
+            PageUp => 0x74,
+            PageDown => 0x79,
+            ArrowUp => 0x7e,
+            ArrowDown => 0x7d,
+            ArrowLeft => 0x7b,
+            ArrowRight => 0x7c,
+
+            // numpad (NumLock = Clear)
+            Np0 => 0x52,
+            Np1 => 0x53,
+            Np2 => 0x54,
+            Np3 => 0x55,
+            Np4 => 0x56,
+            Np5 => 0x57,
+            Np6 => 0x58,
+            Np7 => 0x59,
+            Np8 => 0x5b,
+            Np9 => 0x5c,
+            NpAdd => 0x45,
+            NpSubtract => 0x4e,
+            NpMultiply => 0x43,
+            NpDivide => 0x4b,
+            NpEnter => 0x4c,
+            NpDecimal => 0x41,
+            NpLock => 0x47,
+
+            Menu => 0x6e,
+
+            VolumeUp => 0x48,
+            VolumeDown => 0x49,
+            VolumeMute => 0x4a,
+
+            // media / browser / launch keys are system events on macOS, not keycodes
+            Print
+            | Pause
+            | F21
+            | F22
+            | F23
+            | F24
+            | MediaPlayPause
+            | MediaNext
+            | MediaPrev
+            | MediaStop
+            | BrowserBack
+            | BrowserForward
+            | BrowserRefresh
+            | BrowserHome
+            | LaunchMail
+            | LaunchCalculator
+            | Custom { .. } => return None,
+        })
+    }
+
+    /// Reverse of [`Key::windows_scan`] for named keys.
+    pub fn from_windows_scan(scan: Scan) -> Option<Self> {
+        Self::iter().find(|k| k.windows_scan() == Some(scan))
+    }
+
+    /// Reverse of [`Key::evdev_code`] for named keys.
+    pub fn from_evdev_code(code: u16) -> Option<Self> {
+        Self::iter().find(|k| k.evdev_code() == Some(code))
+    }
+
+    /// Reverse of [`Key::mac_vk`] for named keys.
+    pub fn from_mac_vk(vk: u16) -> Option<Self> {
+        Self::iter().find(|k| k.mac_vk() == Some(vk))
     }
 
     /// Convenience: turn a Key into a press or release step.
@@ -539,6 +834,18 @@ impl Key {
             F10 => "f10",
             F11 => "f11",
             F12 => "f12",
+            F13 => "f13",
+            F14 => "f14",
+            F15 => "f15",
+            F16 => "f16",
+            F17 => "f17",
+            F18 => "f18",
+            F19 => "f19",
+            F20 => "f20",
+            F21 => "f21",
+            F22 => "f22",
+            F23 => "f23",
+            F24 => "f24",
 
             // modifiers
             LShift => "lshift",
@@ -567,6 +874,7 @@ impl Key {
             Slash => "slash",
             Backslash => "backslash",
             Grave => "grave",
+            IntlBackslash => "intl_backslash",
             CapsLock => "capslock",
             Print => "print",
             Pause => "pause",
@@ -604,6 +912,21 @@ impl Key {
 
             Menu => "menu",
 
+            // media / browser / launch
+            VolumeUp => "volume_up",
+            VolumeDown => "volume_down",
+            VolumeMute => "volume_mute",
+            MediaPlayPause => "media_play_pause",
+            MediaNext => "media_next",
+            MediaPrev => "media_prev",
+            MediaStop => "media_stop",
+            BrowserBack => "browser_back",
+            BrowserForward => "browser_forward",
+            BrowserRefresh => "browser_refresh",
+            BrowserHome => "browser_home",
+            LaunchMail => "launch_mail",
+            LaunchCalculator => "launch_calculator",
+
             // You generally shouldn't emit a token for `Custom`
             Custom { .. } => "custom",
         }
@@ -614,3 +937,100 @@ impl Key {
         Self::iter().map(|k| k.to_token())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Keys with no single set-1 scancode (Print and Pause send multi-byte sequences).
+    const NO_SCAN: &[Key] = &[Key::Print, Key::Pause];
+
+    /// Sent by virtual-key on Windows and as system events on macOS.
+    const MEDIA: &[Key] = &[
+        Key::VolumeUp,
+        Key::VolumeDown,
+        Key::VolumeMute,
+        Key::MediaPlayPause,
+        Key::MediaNext,
+        Key::MediaPrev,
+        Key::MediaStop,
+        Key::BrowserBack,
+        Key::BrowserForward,
+        Key::BrowserRefresh,
+        Key::BrowserHome,
+        Key::LaunchMail,
+        Key::LaunchCalculator,
+    ];
+
+    /// Keys a Mac keyboard has no `kVK_*` code for, besides the media keys.
+    const NO_MAC_VK: &[Key] = &[
+        Key::Print,
+        Key::Pause,
+        Key::F21,
+        Key::F22,
+        Key::F23,
+        Key::F24,
+    ];
+
+    #[test]
+    fn windows_scan_maps_both_ways() {
+        for key in Key::iter() {
+            match key.windows_scan() {
+                Some(scan) => assert_eq!(Key::from_windows_scan(scan), Some(key), "{key:?}"),
+                None => assert!(NO_SCAN.contains(&key), "{key:?} has no scancode"),
+            }
+        }
+    }
+
+    #[test]
+    fn evdev_code_maps_both_ways() {
+        for key in Key::iter() {
+            let code = key
+                .evdev_code()
+                .unwrap_or_else(|| panic!("{key:?} has no evdev code"));
+            assert_eq!(Key::from_evdev_code(code), Some(key), "{key:?}");
+        }
+    }
+
+    #[test]
+    fn mac_vk_maps_both_ways() {
+        for key in Key::iter() {
+            match key.mac_vk() {
+                Some(vk) => assert_eq!(Key::from_mac_vk(vk), Some(key), "{key:?}"),
+                None => assert!(
+                    NO_MAC_VK.contains(&key) || MEDIA.contains(&key),
+                    "{key:?} has no mac keycode"
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn media_keys_have_a_windows_vk() {
+        for key in Key::iter() {
+            assert_eq!(
+                key.windows_media_vk().is_some(),
+                MEDIA.contains(&key),
+                "{key:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn tokens_parse_back() {
+        for key in Key::iter() {
+            assert_eq!(Key::parse(key.to_token()), Some(key), "{key:?}");
+        }
+    }
+
+    #[test]
+    fn custom_keys_are_not_named() {
+        let custom = Key::Custom {
+            scan: 0x1e,
+            extended: false,
+        };
+        assert_eq!(custom.windows_scan(), Some(Scan::new(0x1e, false)));
+        assert_eq!(custom.mac_vk(), None);
+        assert_eq!(Key::from_windows_scan(Scan::new(0x1e, false)), Some(Key::A));
+    }
+}
@@ -0,0 +1,561 @@
+// src/input/layout.rs
+//! Keyboard layouts: which physical [`Key`] (plus Shift / AltGr) produces a
+//! character, localized key names, and display names that parse back.
+//!
+//! `Key` variants name physical positions after the US layout; `Key::Y` is the key
+//! that prints `Z` on a German keyboard. Steps always carry physical scancodes.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use super::key::Key;
+use super::types::InputStep;
+
+/// Supported layouts. Select one per plugin with `Plugin::keyboard_layout`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    #[default]
+    Us,
+    Uk,
+    De,
+    Fr,
+}
+
+/// A physical key and the modifiers needed to produce a character with it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LayoutKey {
+    pub key: Key,
+    pub shift: bool,
+    /// AltGr, sent as `RAlt`.
+    pub altgr: bool,
+}
+
+impl LayoutKey {
+    pub const fn plain(key: Key) -> Self {
+        Self {
+            key,
+            shift: false,
+            altgr: false,
+        }
+    }
+
+    /// Modifier keys to hold, outermost first.
+    pub fn modifiers(&self) -> Vec<Key> {
+        let mut v = Vec::with_capacity(2);
+        if self.altgr {
+            v.push(Key::RAlt);
+        }
+        if self.shift {
+            v.push(Key::LShift);
+        }
+        v
+    }
+
+    /// Tap the key with its modifiers held.
+    pub fn tap_steps(&self) -> Option<Vec<InputStep>> {
+        let mods = self
+            .modifiers()
+            .into_iter()
+            .map(Key::to_scan)
+            .collect::<Option<Vec<_>>>()?;
+        let scan = self.key.to_scan()?;
+        let mut v = Vec::with_capacity(2 + mods.len() * 2);
+        v.extend(mods.iter().map(|s| InputStep::KeyDown(*s)));
+        v.push(InputStep::KeyDown(scan));
+        v.push(InputStep::KeyUp(scan));
+        v.extend(mods.iter().rev().map(|s| InputStep::KeyUp(*s)));
+        Some(v)
+    }
+}
+
+/// Key, unshifted, shifted and AltGr characters; `'\0'` = none.
+type Row = (Key, char, char, char);
+
+const NONE: char = '\0';
+
+impl Layout {
+    /// Accepts codes and common names: "us", "en-gb", "uk", "de", "german", "fr", "azerty", ...
+    pub fn parse(name: &str) -> Option<Self> {
+        let s = name.trim().to_lowercase().replace('_', "-");
+        Some(match s.as_str() {
+            "us" | "en-us" | "english" | "qwerty" => Layout::Us,
+            "uk" | "gb" | "en-gb" | "british" => Layout::Uk,
+            "de" | "de-de" | "german" | "deutsch" | "qwertz" => Layout::De,
+            "fr" | "fr-fr" | "french" | "français" | "azerty" => Layout::Fr,
+            _ => return None,
+        })
+    }
+
+    /// Short code, accepted by [`Layout::parse`].
+    pub fn code(self) -> &'static str {
+        match self {
+            Layout::Us => "us",
+            Layout::Uk => "uk",
+            Layout::De => "de",
+            Layout::Fr => "fr",
+        }
+    }
+
+    fn table(self) -> &'static [Row] {
+        match self {
+            Layout::Us => US,
+            Layout::Uk => UK,
+            Layout::De => DE,
+            Layout::Fr => FR,
+        }
+    }
+
+    /// Localized names, first one per key is its display name.
+    fn names(self) -> &'static [(&'static str, Key)] {
+        match self {
+            Layout::Us | Layout::Uk => &[],
+            Layout::De => DE_NAMES,
+            Layout::Fr => FR_NAMES,
+        }
+    }
+
+    /// Characters that are dead keys: they only print when followed by a space.
+    fn dead(self) -> &'static [char] {
+        match self {
+            Layout::Us | Layout::Uk => &[],
+            Layout::De => &['^', '´', '`'],
+            Layout::Fr => &['^', '¨'],
+        }
+    }
+
+    /// Rows of the layout table, with letters not listed there mapping to themselves.
+    fn rows(self) -> impl Iterator<Item = Row> {
+        let table = self.table();
+        let letters = Key::iter()
+            .take_while(|k| *k <= Key::Z)
+            .filter(move |k| !table.iter().any(|r| r.0 == *k))
+            .map(|k| {
+                let c = k.to_token().chars().next().unwrap_or(NONE);
+                (k, c, c.to_ascii_uppercase(), NONE)
+            });
+        table.iter().copied().chain(letters)
+    }
+
+    fn row(self, key: Key) -> Option<Row> {
+        self.rows().find(|r| r.0 == key)
+    }
+
+    /// Key and modifiers that produce `c`.
+    pub fn char_key(self, c: char) -> Option<LayoutKey> {
+        match c {
+            ' ' => return Some(LayoutKey::plain(Key::Space)),
+            '\t' => return Some(LayoutKey::plain(Key::Tab)),
+            '\n' => return Some(LayoutKey::plain(Key::Enter)),
+            NONE => return None,
+            _ => {}
+        }
+        // prefer the plainest way to type it: unshifted, then Shift, then AltGr
+        let find = |pick: fn(&Row) -> char| self.rows().find(|r| pick(r) == c).map(|r| r.0);
+        if let Some(key) = find(|r| r.1) {
+            return Some(LayoutKey::plain(key));
+        }
+        if let Some(key) = find(|r| r.2) {
+            return Some(LayoutKey {
+                key,
+                shift: true,
+                altgr: false,
+            });
+        }
+        find(|r| r.3).map(|key| LayoutKey {
+            key,
+            shift: false,
+            altgr: true,
+        })
+    }
+
+    /// Character `key` produces with the given modifiers.
+    pub fn key_char(self, key: Key, shift: bool, altgr: bool) -> Option<char> {
+        let (_, base, shifted, alt) = self.row(key)?;
+        let c = match (shift, altgr) {
+            (false, false) => base,
+            (true, false) => shifted,
+            (false, true) => alt,
+            (true, true) => NONE,
+        };
+        (c != NONE).then_some(c)
+    }
+
+    /// Steps that type `c` (dead keys are followed by a space).
+    pub fn char_steps(self, c: char) -> Option<Vec<InputStep>> {
+        let mut v = self.char_key(c)?.tap_steps()?;
+        if self.dead().contains(&c) {
+            v.extend(LayoutKey::plain(Key::Space).tap_steps()?);
+        }
+        Some(v)
+    }
+
+    /// Replace `Unicode` steps with key presses for this layout; other steps pass through.
+    /// Fails on the first character the layout cannot type.
+    pub fn expand_unicode(self, steps: &[InputStep]) -> Result<Vec<InputStep>, char> {
+        let mut out = Vec::with_capacity(steps.len());
+        for &step in steps {
+            match step {
+                InputStep::Unicode(c) => out.extend(self.char_steps(c).ok_or(c)?),
+                other => out.push(other),
+            }
+        }
+        Ok(out)
+    }
+
+    /// Parse a key name: a character of this layout, a localized name, or any
+    /// [`Key::parse`] token. Case-insensitive; letters never imply Shift.
+    pub fn parse_key(self, name: &str) -> Option<LayoutKey> {
+        let trimmed = name.trim();
+        let mut chars = trimmed.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            let c = if c.is_alphabetic() {
+                single_lower(c)
+            } else {
+                c
+            };
+            if let Some(k) = self.char_key(c) {
+                return Some(k);
+            }
+        }
+
+        let norm = normalize(trimmed);
+        if let Some(&(_, key)) = self.names().iter().find(|(n, _)| *n == norm) {
+            return Some(LayoutKey::plain(key));
+        }
+        Key::parse(trimmed).map(LayoutKey::plain)
+    }
+
+    /// What the key is called on this layout: its printed character (upper-case
+    /// for letters), a localized name, or the English token. Parses back with
+    /// [`Layout::parse_key`].
+    pub fn display_name(self, key: Key) -> String {
+        if let Some(&(name, _)) = self.names().iter().find(|(_, k)| *k == key) {
+            return name.to_string();
+        }
+        if let Some(c) = self.key_char(key, false, false) {
+            let mut up = c.to_uppercase();
+            return match (up.next(), up.next()) {
+                (Some(u), None) => u.to_string(),
+                _ => c.to_string(),
+            };
+        }
+        key.to_token().to_string()
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+fn single_lower(c: char) -> char {
+    let mut low = c.to_lowercase();
+    match (low.next(), low.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.to_lowercase().replace([' ', '-'], "_")
+}
+
+// ---- tables ----
+
+const US: &[Row] = &[
+    (Key::D1, '1', '!', NONE),
+    (Key::D2, '2', '@', NONE),
+    (Key::D3, '3', '#', NONE),
+    (Key::D4, '4', '$', NONE),
+    (Key::D5, '5', '%', NONE),
+    (Key::D6, '6', '^', NONE),
+    (Key::D7, '7', '&', NONE),
+    (Key::D8, '8', '*', NONE),
+    (Key::D9, '9', '(', NONE),
+    (Key::D0, '0', ')', NONE),
+    (Key::Minus, '-', '_', NONE),
+    (Key::Equal, '=', '+', NONE),
+    (Key::LBracket, '[', '{', NONE),
+    (Key::RBracket, ']', '}', NONE),
+    (Key::Semicolon, ';', ':', NONE),
+    (Key::Apostrophe, '\'', '"', NONE),
+    (Key::Backslash, '\\', '|', NONE),
+    (Key::Comma, ',', '<', NONE),
+    (Key::Period, '.', '>', NONE),
+    (Key::Slash, '/', '?', NONE),
+    (Key::Grave, '`', '~', NONE),
+];
+
+const UK: &[Row] = &[
+    (Key::D1, '1', '!', NONE),
+    (Key::D2, '2', '"', NONE),
+    (Key::D3, '3', '£', NONE),
+    (Key::D4, '4', '$', '€'),
+    (Key::D5, '5', '%', NONE),
+    (Key::D6, '6', '^', NONE),
+    (Key::D7, '7', '&', NONE),
+    (Key::D8, '8', '*', NONE),
+    (Key::D9, '9', '(', NONE),
+    (Key::D0, '0', ')', NONE),
+    (Key::Minus, '-', '_', NONE),
+    (Key::Equal, '=', '+', NONE),
+    (Key::LBracket, '[', '{', NONE),
+    (Key::RBracket, ']', '}', NONE),
+    (Key::Semicolon, ';', ':', NONE),
+    (Key::Apostrophe, '\'', '@', NONE),
+    (Key::Backslash, '#', '~', NONE),
+    (Key::Comma, ',', '<', NONE),
+    (Key::Period, '.', '>', NONE),
+    (Key::Slash, '/', '?', NONE),
+    (Key::Grave, '`', '¬', '¦'),
+    (Key::IntlBackslash, '\\', '|', NONE),
+];
+
+const DE: &[Row] = &[
+    (Key::Y, 'z', 'Z', NONE),
+    (Key::Z, 'y', 'Y', NONE),
+    (Key::Q, 'q', 'Q', '@'),
+    (Key::E, 'e', 'E', '€'),
+    (Key::M, 'm', 'M', 'µ'),
+    (Key::D1, '1', '!', NONE),
+    (Key::D2, '2', '"', '²'),
+    (Key::D3, '3', '§', '³'),
+    (Key::D4, '4', '$', NONE),
+    (Key::D5, '5', '%', NONE),
+    (Key::D6, '6', '&', NONE),
+    (Key::D7, '7', '/', '{'),
+    (Key::D8, '8', '(', '['),
+    (Key::D9, '9', ')', ']'),
+    (Key::D0, '0', '=', '}'),
+    (Key::Minus, 'ß', '?', '\\'),
+    (Key::Equal, '´', '`', NONE),
+    (Key::LBracket, 'ü', 'Ü', NONE),
+    (Key::RBracket, '+', '*', '~'),
+    (Key::Semicolon, 'ö', 'Ö', NONE),
+    (Key::Apostrophe, 'ä', 'Ä', NONE),
+    (Key::Backslash, '#', '\'', NONE),
+    (Key::Comma, ',', ';', NONE),
+    (Key::Period, '.', ':', NONE),
+    (Key::Slash, '-', '_', NONE),
+    (Key::Grave, '^', '°', NONE),
+    (Key::IntlBackslash, '<', '>', '|'),
+];
+
+const FR: &[Row] = &[
+    (Key::Q, 'a', 'A', NONE),
+    (Key::W, 'z', 'Z', NONE),
+    (Key::A, 'q', 'Q', NONE),
+    (Key::Z, 'w', 'W', NONE),
+    (Key::E, 'e', 'E', '€'),
+    (Key::Semicolon, 'm', 'M', NONE),
+    (Key::M, ',', '?', NONE),
+    (Key::D1, '&', '1', NONE),
+    (Key::D2, 'é', '2', '~'),
+    (Key::D3, '"', '3', '#'),
+    (Key::D4, '\'', '4', '{'),
+    (Key::D5, '(', '5', '['),
+    (Key::D6, '-', '6', '|'),
+    (Key::D7, 'è', '7', '`'),
+    (Key::D8, '_', '8', '\\'),
+    (Key::D9, 'ç', '9', '^'),
+    (Key::D0, 'à', '0', '@'),
+    (Key::Minus, ')', '°', ']'),
+    (Key::Equal, '=', '+', '}'),
+    (Key::LBracket, '^', '¨', NONE),
+    (Key::RBracket, '$', '£', '¤'),
+    (Key::Apostrophe, 'ù', '%', NONE),
+    (Key::Backslash, '*', 'µ', NONE),
+    (Key::Comma, ';', '.', NONE),
+    (Key::Period, ':', '/', NONE),
+    (Key::Slash, '!', '§', NONE),
+    (Key::Grave, '²', NONE, NONE),
+    (Key::IntlBackslash, '<', '>', NONE),
+];
+
+const DE_NAMES: &[(&str, Key)] = &[
+    ("strg", Key::LCtrl),
+    ("strg_rechts", Key::RCtrl),
+    ("umschalt", Key::LShift),
+    ("umschalt_rechts", Key::RShift),
+    ("altgr", Key::RAlt),
+    ("leertaste", Key::Space),
+    ("eingabe", Key::Enter),
+    ("rücktaste", Key::Backspace),
+    ("feststell", Key::CapsLock),
+    ("entf", Key::Delete),
+    ("einfg", Key::Insert),
+    ("pos1", Key::Home),
+    ("ende", Key::End),
+    ("bild_auf", Key::PageUp),
+    ("bild_ab", Key::PageDown),
+    ("druck", Key::Print),
+    ("pfeil_hoch", Key::ArrowUp),
+    ("pfeil_runter", Key::ArrowDown),
+    ("pfeil_links", Key::ArrowLeft),
+    ("pfeil_rechts", Key::ArrowRight),
+];
+
+const FR_NAMES: &[(&str, Key)] = &[
+    ("ctrl_droit", Key::RCtrl),
+    ("maj", Key::LShift),
+    ("maj_droit", Key::RShift),
+    ("altgr", Key::RAlt),
+    ("espace", Key::Space),
+    ("entrée", Key::Enter),
+    ("retour_arrière", Key::Backspace),
+    ("verr_maj", Key::CapsLock),
+    ("échap", Key::Escape),
+    ("echap", Key::Escape),
+    ("suppr", Key::Delete),
+    ("inser", Key::Insert),
+    ("origine", Key::Home),
+    ("fin", Key::End),
+    ("page_préc", Key::PageUp),
+    ("page_suiv", Key::PageDown),
+    ("impr_écran", Key::Print),
+    ("haut", Key::ArrowUp),
+    ("bas", Key::ArrowDown),
+    ("gauche", Key::ArrowLeft),
+    ("droite", Key::ArrowRight),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shifted(key: Key) -> LayoutKey {
+        LayoutKey {
+            key,
+            shift: true,
+            altgr: false,
+        }
+    }
+
+    fn altgr(key: Key) -> LayoutKey {
+        LayoutKey {
+            key,
+            shift: false,
+            altgr: true,
+        }
+    }
+
+    #[test]
+    fn german_swaps_y_and_z_and_uses_altgr() {
+        let de = Layout::De;
+        assert_eq!(de.char_key('z'), Some(LayoutKey::plain(Key::Y)));
+        assert_eq!(de.char_key('Y'), Some(shifted(Key::Z)));
+        assert_eq!(de.char_key('@'), Some(altgr(Key::Q)));
+        assert_eq!(de.char_key('ß'), Some(LayoutKey::plain(Key::Minus)));
+        assert_eq!(de.char_key('"'), Some(shifted(Key::D2)));
+        assert_eq!(de.key_char(Key::Semicolon, true, false), Some('Ö'));
+        assert_eq!(de.char_key('é'), None);
+    }
+
+    #[test]
+    fn french_is_azerty_with_shifted_digits() {
+        let fr = Layout::Fr;
+        assert_eq!(fr.char_key('a'), Some(LayoutKey::plain(Key::Q)));
+        assert_eq!(fr.char_key('m'), Some(LayoutKey::plain(Key::Semicolon)));
+        assert_eq!(fr.char_key('1'), Some(shifted(Key::D1)));
+        assert_eq!(fr.char_key('é'), Some(LayoutKey::plain(Key::D2)));
+        assert_eq!(fr.char_key('@'), Some(altgr(Key::D0)));
+    }
+
+    #[test]
+    fn uk_differs_from_us_on_symbols() {
+        assert_eq!(Layout::Uk.char_key('"'), Some(shifted(Key::D2)));
+        assert_eq!(Layout::Uk.char_key('£'), Some(shifted(Key::D3)));
+        assert_eq!(Layout::Uk.char_key('@'), Some(shifted(Key::Apostrophe)));
+        assert_eq!(Layout::Us.char_key('@'), Some(shifted(Key::D2)));
+        assert_eq!(Layout::Us.char_key('£'), None);
+    }
+
+    #[test]
+    fn steps_hold_altgr_and_shift_around_the_tap() {
+        let scan = |k: Key| k.to_scan().unwrap();
+        assert_eq!(
+            Layout::De.char_steps('€').unwrap(),
+            vec![
+                InputStep::KeyDown(scan(Key::RAlt)),
+                InputStep::KeyDown(scan(Key::E)),
+                InputStep::KeyUp(scan(Key::E)),
+                InputStep::KeyUp(scan(Key::RAlt)),
+            ]
+        );
+        // dead keys only print when followed by a space
+        assert_eq!(
+            Layout::De.char_steps('^').unwrap(),
+            vec![
+                InputStep::KeyDown(scan(Key::Grave)),
+                InputStep::KeyUp(scan(Key::Grave)),
+                InputStep::KeyDown(scan(Key::Space)),
+                InputStep::KeyUp(scan(Key::Space)),
+            ]
+        );
+        assert_eq!(Layout::Us.char_steps('^').unwrap().len(), 4);
+    }
+
+    #[test]
+    fn expand_unicode_reports_the_first_untypeable_char() {
+        let text: Vec<_> = "Grüße €".chars().map(InputStep::Unicode).collect();
+        assert!(Layout::De.expand_unicode(&text).is_ok());
+        assert_eq!(Layout::Us.expand_unicode(&text), Err('ü'));
+        assert_eq!(Layout::Fr.expand_unicode(&text), Err('ü'));
+    }
+
+    #[test]
+    fn localized_names_parse() {
+        assert_eq!(
+            Layout::De.parse_key("Strg"),
+            Some(LayoutKey::plain(Key::LCtrl))
+        );
+        assert_eq!(
+            Layout::De.parse_key("Bild auf"),
+            Some(LayoutKey::plain(Key::PageUp))
+        );
+        assert_eq!(Layout::De.parse_key("Z"), Some(LayoutKey::plain(Key::Y)));
+        assert_eq!(
+            Layout::Fr.parse_key("échap"),
+            Some(LayoutKey::plain(Key::Escape))
+        );
+        assert_eq!(Layout::Fr.parse_key("A"), Some(LayoutKey::plain(Key::Q)));
+        // English tokens work on every layout
+        assert_eq!(
+            Layout::Fr.parse_key("ctrl"),
+            Some(LayoutKey::plain(Key::LCtrl))
+        );
+        assert_eq!(Layout::Us.parse_key("strg"), None);
+    }
+
+    #[test]
+    fn display_names_parse_back_on_every_layout() {
+        for layout in Layout::iter() {
+            for key in Key::iter() {
+                let name = layout.display_name(key);
+                assert_eq!(
+                    layout.parse_key(&name).map(|k| k.key),
+                    Some(key),
+                    "{layout}: {key:?} shown as {name:?}"
+                );
+            }
+        }
+        assert_eq!(Layout::De.display_name(Key::Y), "Z");
+        assert_eq!(Layout::De.display_name(Key::LCtrl), "strg");
+        assert_eq!(Layout::Fr.display_name(Key::Q), "A");
+    }
+
+    #[test]
+    fn layout_codes_parse_back() {
+        for layout in Layout::iter() {
+            assert_eq!(Layout::parse(layout.code()), Some(layout));
+        }
+        assert_eq!(Layout::parse("AZERTY"), Some(Layout::Fr));
+        assert_eq!(Layout::parse("en_GB"), Some(Layout::Uk));
+        assert_eq!(Layout::parse("dvorak"), None);
+    }
+}
@@ -0,0 +1,214 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::sync::Mutex;
+use std::thread;
+
+use super::InputSynth;
+use super::evdev::{
+    self, BTN_EXTRA, BTN_LEFT, EV_KEY, EV_REL, EV_SYN, KEY_MAX_USED, REL_HWHEEL, REL_WHEEL, REL_X,
+    REL_Y, RawEvent,
+};
+use super::layout::Layout;
+use super::types::InputStep;
+
+const UINPUT_PATH: &str = "/dev/uinput";
+const DEVICE_NAME: &str = "streamdeck-lib virtual input";
+
+// ioctl request codes (linux/uinput.h)
+const UI_SET_EVBIT: u64 = 0x4004_5564;
+const UI_SET_KEYBIT: u64 = 0x4004_5565;
+const UI_SET_RELBIT: u64 = 0x4004_5566;
+const UI_DEV_SETUP: u64 = 0x405c_5503;
+const UI_DEV_CREATE: u64 = 0x5501;
+const UI_DEV_DESTROY: u64 = 0x5502;
+
+const BUS_VIRTUAL: u16 = 0x06;
+
+/// Virtual keyboard + mouse through `/dev/uinput`.
+///
+/// Needs write access to `/dev/uinput` (usually the `input` group or a udev rule).
+/// The desktop may take a moment to pick up the new device after `new()`.
+/// Text is typed as key presses, so set the desktop's layout with
+/// [`LinuxSynth::with_layout`] (US by default).
+pub struct LinuxSynth {
+    dev: Mutex<File>,
+    layout: Layout,
+}
+
+impl LinuxSynth {
+    pub fn new() -> io::Result<Self> {
+        let dev = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(UINPUT_PATH)?;
+        let fd = dev.as_raw_fd();
+
+        ioctl_int(fd, UI_SET_EVBIT, EV_SYN)?;
+        ioctl_int(fd, UI_SET_EVBIT, EV_KEY)?;
+        ioctl_int(fd, UI_SET_EVBIT, EV_REL)?;
+        for code in 1..=KEY_MAX_USED {
+            ioctl_int(fd, UI_SET_KEYBIT, code)?;
+        }
+        for code in BTN_LEFT..=BTN_EXTRA {
+            ioctl_int(fd, UI_SET_KEYBIT, code)?;
+        }
+        // relative axes make the device show up as a mouse
+        ioctl_int(fd, UI_SET_RELBIT, REL_X)?;
+        ioctl_int(fd, UI_SET_RELBIT, REL_Y)?;
+        ioctl_int(fd, UI_SET_RELBIT, REL_WHEEL)?;
+        ioctl_int(fd, UI_SET_RELBIT, REL_HWHEEL)?;
+
+        // SAFETY: plain C struct, all-zero is a valid value
+        let mut setup: libc::uinput_setup = unsafe { std::mem::zeroed() };
+        setup.id.bustype = BUS_VIRTUAL;
+        setup.id.vendor = 0x1209;
+        setup.id.product = 0x5d01;
+        for (dst, src) in setup.name.iter_mut().zip(DEVICE_NAME.bytes()) {
+            *dst = src as libc::c_char;
+        }
+        // SAFETY: fd is open; UI_DEV_SETUP reads a uinput_setup
+        check(unsafe { libc::ioctl(fd, UI_DEV_SETUP as _, &setup) })?;
+        // SAFETY: fd is open
+        check(unsafe { libc::ioctl(fd, UI_DEV_CREATE as _) })?;
+
+        Ok(Self {
+            dev: Mutex::new(dev),
+            layout: Layout::default(),
+        })
+    }
+
+    /// Layout used to type `Unicode` steps (chainable), e.g. `cx.keyboard_layout()`.
+    pub fn with_layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Write raw events as one `write` call.
+    fn write_events(&self, events: &[RawEvent]) -> Result<(), String> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let buf = encode_events(events);
+        let mut dev = self.dev.lock().map_err(|_| "uinput mutex poisoned")?;
+        dev.write_all(&buf)
+            .map_err(|e| format!("uinput write failed: {e}"))
+    }
+}
+
+/// Serialize events as consecutive `struct input_event`s with a zero timestamp.
+fn encode_events(events: &[RawEvent]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(events.len() * size_of::<libc::input_event>());
+    for ev in events {
+        // SAFETY: plain C struct, all-zero is a valid value; kernel stamps the time
+        let mut raw: libc::input_event = unsafe { std::mem::zeroed() };
+        raw.type_ = ev.type_;
+        raw.code = ev.code;
+        raw.value = ev.value;
+        // SAFETY: input_event is POD; view it as bytes
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                (&raw as *const libc::input_event).cast::<u8>(),
+                size_of::<libc::input_event>(),
+            )
+        };
+        buf.extend_from_slice(bytes);
+    }
+    buf
+}
+
+impl InputSynth for LinuxSynth {
+    fn send_step(&self, step: &InputStep) -> Result<(), String> {
+        if let InputStep::Sleep(d) = *step {
+            thread::sleep(d);
+            return Ok(());
+        }
+        self.write_events(&evdev::encode_step_in(step, self.layout)?)
+    }
+
+    /// Batch events between sleeps into single writes.
+    fn send_steps<I>(&self, steps: I) -> Result<(), String>
+    where
+        I: IntoIterator<Item = InputStep>,
+    {
+        let mut buf: Vec<RawEvent> = Vec::with_capacity(16);
+        for step in steps {
+            if let InputStep::Sleep(d) = step {
+                self.write_events(&buf)?;
+                buf.clear();
+                thread::sleep(d);
+                continue;
+            }
+            buf.extend(evdev::encode_step_in(&step, self.layout)?);
+        }
+        self.write_events(&buf)
+    }
+}
+
+impl Drop for LinuxSynth {
+    fn drop(&mut self) {
+        if let Ok(dev) = self.dev.get_mut() {
+            // SAFETY: fd is open until `dev` drops after this
+            unsafe { libc::ioctl(dev.as_raw_fd(), UI_DEV_DESTROY as _) };
+        }
+    }
+}
+
+fn ioctl_int(fd: libc::c_int, req: u64, value: u16) -> io::Result<()> {
+    // SAFETY: fd is open; these requests take an int argument
+    check(unsafe { libc::ioctl(fd, req as _, libc::c_int::from(value)) })
+}
+
+fn check(rc: libc::c_int) -> io::Result<()> {
+    if rc < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::offset_of;
+
+    fn field<const N: usize>(buf: &[u8], at: usize) -> [u8; N] {
+        buf[at..at + N].try_into().unwrap()
+    }
+
+    #[test]
+    fn events_are_laid_out_as_input_event() {
+        let size = size_of::<libc::input_event>();
+        let events = [
+            RawEvent::new(EV_KEY, 30, 1),
+            RawEvent::new(EV_REL, REL_X, -2),
+        ];
+        let buf = encode_events(&events);
+        assert_eq!(buf.len(), 2 * size);
+
+        for (i, ev) in events.iter().enumerate() {
+            let rec = &buf[i * size..(i + 1) * size];
+            let type_ = u16::from_ne_bytes(field(rec, offset_of!(libc::input_event, type_)));
+            let code = u16::from_ne_bytes(field(rec, offset_of!(libc::input_event, code)));
+            let value = i32::from_ne_bytes(field(rec, offset_of!(libc::input_event, value)));
+            assert_eq!((type_, code, value), (ev.type_, ev.code, ev.value));
+
+            // timestamp left for the kernel
+            let time = offset_of!(libc::input_event, time);
+            let time_len = size_of::<libc::timeval>();
+            assert!(rec[time..time + time_len].iter().all(|&b| b == 0));
+        }
+    }
+
+    #[test]
+    fn syn_report_is_all_zero_fields() {
+        let buf = encode_events(&[RawEvent::syn()]);
+        assert!(buf.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn no_events_no_bytes() {
+        assert!(encode_events(&[]).is_empty());
+    }
+}
@@ -0,0 +1,510 @@
+// src/input/macros.rs
+//! Named macro documents: steps plus metadata, stored as JSON or TOML with a
+//! schema version.
+//!
+//! v1 (current, and the first): `steps` (serialized `InputStep`s), `repeat`,
+//! optional `timing`. Documents without a `version` are read as v1. A later format
+//! adds one step per version to `MIGRATIONS`; until then every other version is
+//! rejected.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use super::executor::Job;
+use super::timing::TimingProfile;
+use super::types::{InputStep, MouseButton, Scan};
+use crate::context::GlobalSettings;
+
+/// Schema version written by this build.
+pub const MACRO_SCHEMA_VERSION: u32 = 1;
+
+/// Default upper bound for [`Macro::validate`]: one minute of sleeps, repeats included.
+pub const MACRO_MAX_DURATION: Duration = Duration::from_secs(60);
+
+/// Largest accepted repeat count.
+pub const MACRO_MAX_REPEAT: u32 = 10_000;
+
+#[derive(Debug, Error)]
+pub enum MacroError {
+    #[error("invalid macro JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid macro TOML: {0}")]
+    TomlDe(#[from] toml::de::Error),
+    #[error("cannot write macro as TOML: {0}")]
+    TomlSer(#[from] toml::ser::Error),
+    #[error("macro document is not an object")]
+    NotAnObject,
+    #[error("macro schema version {0} is not supported (this build reads v{MACRO_SCHEMA_VERSION})")]
+    UnsupportedVersion(u32),
+    #[error("invalid macro schema version `{0}`")]
+    InvalidVersion(Value),
+    #[error("repeat count must be at least 1")]
+    ZeroRepeat,
+    #[error("repeat count {0} is over the limit of {MACRO_MAX_REPEAT}")]
+    TooManyRepeats(u32),
+    #[error("step {index}: {what} released without being pressed")]
+    UnmatchedRelease { index: usize, what: String },
+    #[error("{0} still pressed when the macro ends")]
+    Unreleased(String),
+    #[error("macro runs {actual:?}, longer than {max:?}")]
+    TooLong { actual: Duration, max: Duration },
+}
+
+/// A named macro with metadata, as stored on disk or in settings.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Macro {
+    /// Always [`MACRO_SCHEMA_VERSION`] once loaded.
+    pub version: u32,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    pub steps: Vec<InputStep>,
+    /// How many times the steps run back to back.
+    #[serde(default = "one")]
+    pub repeat: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<TimingProfile>,
+}
+
+fn one() -> u32 {
+    1
+}
+
+impl Macro {
+    pub fn new(name: impl Into<String>, steps: impl IntoIterator<Item = InputStep>) -> Self {
+        Self {
+            version: MACRO_SCHEMA_VERSION,
+            name: name.into(),
+            description: String::new(),
+            steps: steps.into_iter().collect(),
+            repeat: 1,
+            timing: None,
+        }
+    }
+
+    /// Description (chainable).
+    pub fn description(mut self, text: impl Into<String>) -> Self {
+        self.description = text.into();
+        self
+    }
+
+    /// Repeat count (chainable).
+    pub fn repeat(mut self, n: u32) -> Self {
+        self.repeat = n;
+        self
+    }
+
+    /// Timing profile (chainable).
+    pub fn timing(mut self, profile: TimingProfile) -> Self {
+        self.timing = Some(profile);
+        self
+    }
+
+    // ---- loading / saving ----
+
+    /// Load from any supported schema version; unknown versions are an error.
+    pub fn from_value(value: Value) -> Result<Self, MacroError> {
+        let Value::Object(mut doc) = value else {
+            return Err(MacroError::NotAnObject);
+        };
+        migrate(&mut doc)?;
+        Ok(serde_json::from_value(Value::Object(doc))?)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, MacroError> {
+        Self::from_value(serde_json::from_str(text)?)
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, MacroError> {
+        Self::from_value(toml::from_str(text)?)
+    }
+
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn to_toml(&self) -> Result<String, MacroError> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    // ---- settings helpers ----
+
+    /// Macro stored under `key` in an action's settings; `Ok(None)` if absent.
+    pub fn from_settings(
+        settings: &Map<String, Value>,
+        key: &str,
+    ) -> Result<Option<Self>, MacroError> {
+        settings.get(key).cloned().map(Self::from_value).transpose()
+    }
+
+    /// Store under `key` in an action's settings (send them with `SdClient::set_settings`).
+    pub fn store_in(&self, settings: &mut Map<String, Value>, key: impl Into<String>) {
+        settings.insert(key.into(), self.to_value());
+    }
+
+    /// Macro stored under `key` in the global settings; `Ok(None)` if absent.
+    pub fn load_global(globals: &GlobalSettings, key: &str) -> Result<Option<Self>, MacroError> {
+        globals.get(key).map(Self::from_value).transpose()
+    }
+
+    /// Store under `key` in the global settings.
+    pub fn save_global(&self, globals: &GlobalSettings, key: impl Into<String>) {
+        globals.set(key, self.to_value());
+    }
+
+    // ---- running ----
+
+    /// Steps with repeats unrolled. Fails on a repeat count [`Macro::to_job`] would reject.
+    pub fn expanded_steps(&self) -> Result<Vec<InputStep>, MacroError> {
+        self.check_repeat()?;
+        let n = self.repeat as usize;
+        let cap = self.steps.len().checked_mul(n).unwrap_or(0);
+        let mut out = Vec::with_capacity(cap);
+        for _ in 0..n {
+            out.extend_from_slice(&self.steps);
+        }
+        Ok(out)
+    }
+
+    /// A job running this macro with its timing profile; the executor runs the repeats.
+    ///
+    /// Fails on a zero or absurd repeat count or a run longer than [`MACRO_MAX_DURATION`].
+    /// Press balance is not checked, so press-only macros for `HoldWhilePressed` work;
+    /// use [`Macro::validate`] for that.
+    pub fn to_job(&self) -> Result<Job, MacroError> {
+        self.check_limits(MACRO_MAX_DURATION)?;
+        let job = Job::new(self.steps.clone()).repeat(self.repeat);
+        Ok(match self.timing {
+            Some(t) => job.timing(t),
+            None => job,
+        })
+    }
+
+    /// Total sleep time, repeats included (before any timing profile).
+    /// Saturates at `Duration::MAX`, so absurd documents fail validation instead of panicking.
+    pub fn duration(&self) -> Duration {
+        let once = self
+            .steps
+            .iter()
+            .filter_map(|s| match s {
+                InputStep::Sleep(d) => Some(*d),
+                _ => None,
+            })
+            .fold(Duration::ZERO, Duration::saturating_add);
+        once.saturating_mul(self.repeat)
+    }
+
+    // ---- validation ----
+
+    /// [`Macro::validate_with`] using [`MACRO_MAX_DURATION`].
+    pub fn validate(&self) -> Result<(), MacroError> {
+        self.validate_with(MACRO_MAX_DURATION)
+    }
+
+    /// Check that every press is released, nothing is released unpressed, the repeat
+    /// count is in range and the macro runs at most `max`. Press-only macros for
+    /// `MacroMode::HoldWhilePressed` fail the balance check by design.
+    pub fn validate_with(&self, max: Duration) -> Result<(), MacroError> {
+        self.check_repeat()?;
+
+        let mut keys: Vec<Scan> = Vec::new();
+        let mut buttons: Vec<MouseButton> = Vec::new();
+        for (index, step) in self.steps.iter().enumerate() {
+            match *step {
+                InputStep::KeyDown(s) if !keys.contains(&s) => keys.push(s),
+                InputStep::MouseDown(b) if !buttons.contains(&b) => buttons.push(b),
+                InputStep::KeyUp(s) => {
+                    let before = keys.len();
+                    keys.retain(|k| *k != s);
+                    if keys.len() == before {
+                        let what = format!("key ({s})");
+                        return Err(MacroError::UnmatchedRelease { index, what });
+                    }
+                }
+                InputStep::MouseUp(b) => {
+                    let before = buttons.len();
+                    buttons.retain(|x| *x != b);
+                    if buttons.len() == before {
+                        let what = format!("mouse button {b:?}");
+                        return Err(MacroError::UnmatchedRelease { index, what });
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(s) = keys.first() {
+            return Err(MacroError::Unreleased(format!("key ({s})")));
+        }
+        if let Some(b) = buttons.first() {
+            return Err(MacroError::Unreleased(format!("mouse button {b:?}")));
+        }
+
+        self.check_limits(max)
+    }
+
+    fn check_repeat(&self) -> Result<(), MacroError> {
+        match self.repeat {
+            0 => Err(MacroError::ZeroRepeat),
+            n if n > MACRO_MAX_REPEAT => Err(MacroError::TooManyRepeats(n)),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_limits(&self, max: Duration) -> Result<(), MacroError> {
+        self.check_repeat()?;
+        let actual = self.duration();
+        if actual > max {
+            return Err(MacroError::TooLong { actual, max });
+        }
+        Ok(())
+    }
+}
+
+// ---- migrations ----
+
+/// Upgrades a document by one version.
+type Migration = fn(&mut Map<String, Value>);
+
+/// Migration from version `from` to `from + 1`, one per version below
+/// [`MACRO_SCHEMA_VERSION`]. Empty while v1 is the only format.
+const MIGRATIONS: &[(u32, Migration)] = &[];
+
+/// Bring `doc` up to [`MACRO_SCHEMA_VERSION`]. A missing version means the current one.
+fn migrate(doc: &mut Map<String, Value>) -> Result<(), MacroError> {
+    migrate_with(doc, MACRO_SCHEMA_VERSION, MIGRATIONS)
+}
+
+fn migrate_with(
+    doc: &mut Map<String, Value>,
+    current: u32,
+    migrations: &[(u32, Migration)],
+) -> Result<(), MacroError> {
+    let stored = match doc.get("version") {
+        None => current,
+        Some(v) => v
+            .as_u64()
+            .and_then(|n| u32::try_from(n).ok())
+            .ok_or_else(|| MacroError::InvalidVersion(v.clone()))?,
+    };
+    if stored > current {
+        return Err(MacroError::UnsupportedVersion(stored));
+    }
+    // check the whole chain first so a gap leaves `doc` untouched
+    let steps = (stored..current)
+        .map(|from| migrations.iter().find(|(v, _)| *v == from).map(|(_, f)| *f))
+        .collect::<Option<Vec<Migration>>>()
+        .ok_or(MacroError::UnsupportedVersion(stored))?;
+    for step in steps {
+        step(doc);
+    }
+    doc.insert("version".into(), Value::from(current));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::input::Key;
+
+    fn tap(k: Key) -> [InputStep; 2] {
+        [k.to_step_down().unwrap(), k.to_step_up().unwrap()]
+    }
+
+    fn sample() -> Macro {
+        let mut steps = tap(Key::A).to_vec();
+        steps.push(InputStep::Sleep(Duration::from_millis(25)));
+        steps.extend(tap(Key::B));
+        Macro::new("ab", steps)
+            .description("types a, then b")
+            .repeat(2)
+            .timing(TimingProfile::natural().seed(3))
+    }
+
+    #[test]
+    fn json_and_toml_round_trip() {
+        let m = sample();
+        assert_eq!(Macro::from_json(&m.to_json()).unwrap(), m);
+        assert_eq!(Macro::from_toml(&m.to_toml().unwrap()).unwrap(), m);
+    }
+
+    #[test]
+    fn missing_version_reads_as_current() {
+        let mut doc = sample().to_value();
+        doc.as_object_mut().unwrap().remove("version");
+        let m = Macro::from_value(doc).unwrap();
+        assert_eq!(m.version, MACRO_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn unknown_versions_are_errors() {
+        for v in [0u64, 2, 99] {
+            let doc = json!({ "version": v, "name": "x", "steps": [] });
+            assert!(
+                matches!(Macro::from_value(doc), Err(MacroError::UnsupportedVersion(n)) if n as u64 == v),
+                "version {v}"
+            );
+        }
+    }
+
+    #[test]
+    fn versions_outside_u32_are_rejected_not_truncated() {
+        // 2^32 + 1 would truncate to the supported v1
+        for v in [json!(4_294_967_297u64), json!(-1), json!(1.5), json!("1")] {
+            let doc = json!({ "version": v.clone(), "name": "x", "steps": [] });
+            assert!(
+                matches!(Macro::from_value(doc), Err(MacroError::InvalidVersion(ref got)) if *got == v),
+                "version {v}"
+            );
+        }
+    }
+
+    #[test]
+    fn every_older_version_has_a_migration() {
+        for from in (1..).take_while(|v| *v < MACRO_SCHEMA_VERSION) {
+            assert!(
+                MIGRATIONS.iter().any(|(v, _)| *v == from),
+                "no migration from v{from}"
+            );
+        }
+    }
+
+    // a synthetic history: v1 had `loops`, v2 renamed it to `repeat`, v3 added `name`
+    fn v1_to_v2(doc: &mut Map<String, Value>) {
+        if let Some(n) = doc.remove("loops") {
+            doc.insert("repeat".into(), n);
+        }
+    }
+
+    fn v2_to_v3(doc: &mut Map<String, Value>) {
+        doc.entry("name").or_insert(json!("unnamed"));
+    }
+
+    const HISTORY: &[(u32, Migration)] = &[(2, v2_to_v3), (1, v1_to_v2)];
+
+    fn object(v: Value) -> Map<String, Value> {
+        match v {
+            Value::Object(m) => m,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn older_documents_run_every_step_in_order() {
+        let mut doc = object(json!({ "version": 1, "loops": 3, "steps": [] }));
+        migrate_with(&mut doc, 3, HISTORY).unwrap();
+        assert_eq!(
+            doc,
+            object(json!({ "version": 3, "repeat": 3, "name": "unnamed", "steps": [] }))
+        );
+
+        let mut doc = object(json!({ "version": 2, "name": "x", "repeat": 2, "steps": [] }));
+        migrate_with(&mut doc, 3, HISTORY).unwrap();
+        assert_eq!(doc["version"], json!(3));
+        assert_eq!(doc["name"], json!("x"));
+    }
+
+    #[test]
+    fn a_gap_in_the_chain_rejects_and_leaves_the_document_alone() {
+        let mut doc = object(json!({ "version": 1, "loops": 3 }));
+        let before = doc.clone();
+        let gap: &[(u32, Migration)] = &[(2, v2_to_v3)];
+        assert!(matches!(
+            migrate_with(&mut doc, 3, gap),
+            Err(MacroError::UnsupportedVersion(1))
+        ));
+        assert_eq!(doc, before);
+    }
+
+    #[test]
+    fn non_objects_are_rejected() {
+        assert!(matches!(
+            Macro::from_value(json!([1, 2])),
+            Err(MacroError::NotAnObject)
+        ));
+    }
+
+    #[test]
+    fn duration_counts_repeats() {
+        assert_eq!(sample().duration(), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn huge_sleeps_saturate_and_fail_validation() {
+        let m = Macro::new(
+            "forever",
+            [
+                InputStep::Sleep(Duration::MAX),
+                InputStep::Sleep(Duration::MAX),
+            ],
+        )
+        .repeat(MACRO_MAX_REPEAT);
+        assert_eq!(m.duration(), Duration::MAX);
+        assert!(matches!(m.validate(), Err(MacroError::TooLong { .. })));
+    }
+
+    #[test]
+    fn absurd_repeat_counts_are_rejected_before_unrolling() {
+        let doc = json!({ "name": "x", "steps": [], "repeat": 4_294_967_295u64 });
+        let m = Macro::from_value(doc).unwrap();
+        assert!(matches!(
+            m.validate(),
+            Err(MacroError::TooManyRepeats(u32::MAX))
+        ));
+        assert!(matches!(
+            m.expanded_steps(),
+            Err(MacroError::TooManyRepeats(_))
+        ));
+        assert!(matches!(m.to_job(), Err(MacroError::TooManyRepeats(_))));
+        assert!(matches!(
+            sample().repeat(0).to_job(),
+            Err(MacroError::ZeroRepeat)
+        ));
+    }
+
+    #[test]
+    fn to_job_runs_repeats_and_allows_press_only_macros() {
+        use crate::input::{Executor, JobStatus, RecordingSynth};
+        use std::sync::Arc;
+
+        let synth = Arc::new(RecordingSynth::new());
+        let exec = Executor::new(Arc::clone(&synth));
+        let job = exec.submit(sample().to_job().unwrap());
+        assert_eq!(
+            job.wait_timeout(Duration::from_secs(5)),
+            Some(JobStatus::Completed)
+        );
+        assert_eq!(synth.presses(Key::A), 2);
+        assert_eq!(synth.presses(Key::B), 2);
+
+        let hold = Macro::new("hold", [Key::W.to_step_down().unwrap()]);
+        assert!(hold.validate().is_err());
+        assert!(hold.to_job().is_ok());
+    }
+
+    #[test]
+    fn validation_checks_balance_and_repeat() {
+        assert!(sample().validate().is_ok());
+        assert!(matches!(
+            sample().repeat(0).validate(),
+            Err(MacroError::ZeroRepeat)
+        ));
+        let [down, up] = tap(Key::A);
+        assert!(matches!(
+            Macro::new("up", [up]).validate(),
+            Err(MacroError::UnmatchedRelease { index: 0, .. })
+        ));
+        assert!(matches!(
+            Macro::new("down", [down]).validate(),
+            Err(MacroError::Unreleased(_))
+        ));
+    }
+}
@@ -6,96 +6,70 @@ pub use key::Key;
 
 pub mod dsl;
 
+mod recording;
+pub use recording::{RecordedStep, RecordingSynth};
+
+pub mod text;
+
+pub mod layout;
+pub use layout::{Layout, LayoutKey};
+
+pub mod script;
+pub use script::{ScriptError, ScriptErrorKind, parse_script, to_script};
+
 #[cfg(windows)]
 mod windows;
 #[cfg(windows)]
 pub use windows::WinSynth;
 
-use std::sync::Arc;
+pub mod evdev;
 
-/// Platform-agnostic interface. Implemented by OS backends.
-pub trait InputSynth: Send + Sync + 'static {
-    fn send_step(&self, step: &InputStep) -> Result<(), String>;
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "linux")]
+pub use linux::LinuxSynth;
 
-    fn send_steps<I>(&self, steps: I) -> Result<(), String>
-    where
-        I: IntoIterator<Item = InputStep>,
-    {
-        for s in steps {
-            self.send_step(&s)?;
-        }
-        Ok(())
-    }
-}
+mod executor;
+pub use executor::{Executor, Job, JobHandle, JobId, JobPriority, JobStatus, StatusFn};
 
-/// Optional worker to serialize steps.
-pub struct Executor<S: InputSynth + ?Sized> {
-    tx: crossbeam_channel::Sender<InputStep>,
-    join: Option<std::thread::JoinHandle<()>>,
-    synth: Arc<S>,
-}
+mod modes;
+pub use modes::{MacroController, MacroMode};
 
-impl<S: InputSynth + ?Sized> Executor<S> {
-    pub fn new(synth: Arc<S>) -> Self {
-        Self::new_inner(synth, crossbeam_channel::unbounded())
-    }
+mod timing;
+pub use timing::TimingProfile;
 
-    /// Bounded queue with backpressure (optional).
-    pub fn new_bounded(synth: Arc<S>, cap: usize) -> Self {
-        Self::new_inner(synth, crossbeam_channel::bounded(cap))
-    }
+mod bindings;
+pub use bindings::{Binding, BindingConflict, BindingError, BindingMap};
 
-    fn new_inner(
-        synth: Arc<S>,
-        (tx, rx): (
-            crossbeam_channel::Sender<InputStep>,
-            crossbeam_channel::Receiver<InputStep>,
-        ),
-    ) -> Self {
-        let s2 = Arc::clone(&synth);
-        let join = std::thread::spawn(move || {
-            for step in rx.iter() {
-                let _ = s2.send_step(&step);
-            }
-        });
-        Self {
-            tx,
-            join: Some(join),
-            synth,
-        }
-    }
+mod macros;
+pub use macros::{MACRO_MAX_DURATION, MACRO_MAX_REPEAT, MACRO_SCHEMA_VERSION, Macro, MacroError};
 
-    /// Queue a single step (fire-and-forget).
-    pub fn enqueue(&self, step: InputStep) {
-        let _ = self.tx.send(step);
-    }
+mod safety;
+pub use safety::{install_panic_hook, release_all};
 
-    /// Queue a single step; surface send error.
-    pub fn try_enqueue(
-        &self,
-        step: InputStep,
-    ) -> Result<(), crossbeam_channel::SendError<InputStep>> {
-        self.tx.send(step)
-    }
+/// Platform-agnostic interface. Implemented by OS backends.
+pub trait InputSynth: Send + Sync + 'static {
+    fn send_step(&self, step: &InputStep) -> Result<(), String>;
 
-    pub fn enqueue_all<I: IntoIterator<Item = InputStep>>(&self, steps: I) {
-        for s in steps {
-            let _ = self.tx.send(s);
-        }
+    /// Whether `Sleep` blocks in real time. `Executor` then waits itself (so the wait
+    /// can be cancelled); backends with a virtual clock return false and get the step.
+    fn blocks_on_sleep(&self) -> bool {
+        true
     }
 
-    pub fn synth(&self) -> &Arc<S> {
-        &self.synth
+    /// Modifier keys currently down as the OS sees them (physical or synthesized),
+    /// or `None` if the backend cannot tell.
+    fn modifier_state(&self) -> Option<Vec<Key>> {
+        None
     }
-}
 
-impl<S: InputSynth + ?Sized> Drop for Executor<S> {
-    fn drop(&mut self) {
-        // Closing the sender ends the worker loop.
-        // Take the join handle so we only join once.
-        if let Some(j) = self.join.take() {
-            drop(self.tx.clone()); // close
-            let _ = j.join();
+    fn send_steps<I>(&self, steps: I) -> Result<(), String>
+    where
+        I: IntoIterator<Item = InputStep>,
+    {
+        for s in steps {
+            self.send_step(&s)?;
         }
+        Ok(())
     }
 }
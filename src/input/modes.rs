@@ -0,0 +1,459 @@
+// src/input/modes.rs
+//! Macro modes tied to an action context: one-shot, hold while the Stream Deck
+//! key is pressed, repeat while held, or toggle.
+//!
+//! Call [`MacroController::key_down`] / [`MacroController::key_up`] from the action,
+//! or feed events through [`MacroController::on_event`] (e.g. from a hook) so a key
+//! release or the instance disappearing ends holds and repeats.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crossbeam_channel::{RecvTimeoutError, Sender};
+
+use super::InputSynth;
+use super::executor::{Executor, Job, JobHandle, JobPriority};
+use super::safety::Held;
+use super::types::InputStep;
+use crate::sd_protocol::StreamDeckEvent;
+
+/// How a macro follows the Stream Deck key.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MacroMode {
+    /// Run once on key down. Pressing again while it runs queues another run;
+    /// the first one still completes.
+    #[default]
+    OneShot,
+    /// Run on key down; keys it leaves pressed are released on key up.
+    /// Use press-only steps (`down(Key::W)`), not taps.
+    HoldWhilePressed,
+    /// Run on key down, then again every `interval` after it finishes, until key up.
+    RepeatWhileHeld { interval: Duration },
+    /// First key down runs like `HoldWhilePressed`; the next one releases.
+    Toggle,
+}
+
+/// State shared with a repeat thread.
+#[derive(Default)]
+struct RepeatState {
+    stopped: bool,
+    current: Option<JobHandle>,
+}
+
+enum Active {
+    /// One-shot runs still queued or running; all of them end together.
+    Jobs(Vec<JobHandle>),
+    Hold {
+        job: JobHandle,
+        release: Vec<InputStep>,
+    },
+    Repeat {
+        state: Arc<Mutex<RepeatState>>,
+        wake: Sender<()>,
+    },
+}
+
+/// Runs macros per action context according to their [`MacroMode`].
+pub struct MacroController<S: InputSynth + ?Sized> {
+    exec: Arc<Executor<S>>,
+    active: Mutex<HashMap<String, (MacroMode, Active)>>,
+}
+
+impl<S: InputSynth + ?Sized> MacroController<S> {
+    pub fn new(exec: Arc<Executor<S>>) -> Self {
+        Self {
+            exec,
+            active: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn executor(&self) -> &Arc<Executor<S>> {
+        &self.exec
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, (MacroMode, Active)>> {
+        self.active.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Stream Deck key pressed on `ctx`.
+    pub fn key_down(&self, ctx: &str, mode: MacroMode, steps: Vec<InputStep>) {
+        let mut active = self.lock();
+        let running = active.get(ctx).is_some_and(|(_, a)| a.is_live());
+
+        match mode {
+            MacroMode::Toggle if running => {
+                if let Some((_, a)) = active.remove(ctx) {
+                    self.end(a);
+                }
+                return;
+            }
+            // a second key down while holding/repeating changes nothing
+            MacroMode::HoldWhilePressed | MacroMode::RepeatWhileHeld { .. } | MacroMode::Toggle
+                if running =>
+            {
+                return;
+            }
+            _ => {}
+        }
+
+        if mode == MacroMode::OneShot
+            && let Some((MacroMode::OneShot, Active::Jobs(jobs))) = active.get_mut(ctx)
+        {
+            // the earlier runs complete on their own; the new one queues behind them
+            jobs.retain(JobHandle::is_running);
+            jobs.push(self.exec.submit(steps));
+            return;
+        }
+
+        let entry = match mode {
+            MacroMode::OneShot => Active::Jobs(vec![self.exec.submit(steps)]),
+            MacroMode::HoldWhilePressed | MacroMode::Toggle => Active::Hold {
+                release: release_steps(&steps),
+                job: self.exec.submit(steps),
+            },
+            MacroMode::RepeatWhileHeld { interval } => self.start_repeat(steps, interval),
+        };
+        // left over from a different mode; make sure nothing lingers
+        if let Some((_, old)) = active.insert(ctx.to_string(), (mode, entry)) {
+            self.end(old);
+        }
+    }
+
+    /// Stream Deck key released on `ctx`. Ends holds and repeats; toggles and one-shots keep going.
+    pub fn key_up(&self, ctx: &str) {
+        let mut active = self.lock();
+        let ends = matches!(
+            active.get(ctx),
+            Some((
+                MacroMode::HoldWhilePressed | MacroMode::RepeatWhileHeld { .. },
+                _
+            ))
+        );
+        if ends && let Some((_, a)) = active.remove(ctx) {
+            self.end(a);
+        }
+    }
+
+    /// Stop whatever runs for `ctx` and release what it holds (e.g. on `willDisappear`).
+    pub fn stop(&self, ctx: &str) {
+        if let Some((_, a)) = self.lock().remove(ctx) {
+            self.end(a);
+        }
+    }
+
+    /// Stop everything (e.g. on plugin exit).
+    pub fn stop_all(&self) {
+        for (_, (_, a)) in self.lock().drain() {
+            self.end(a);
+        }
+    }
+
+    /// True while a macro for `ctx` is running, held or repeating.
+    pub fn is_active(&self, ctx: &str) -> bool {
+        self.lock().get(ctx).is_some_and(|(_, a)| a.is_live())
+    }
+
+    /// React to key releases and disappearing instances.
+    pub fn on_event(&self, ev: &StreamDeckEvent) {
+        match ev {
+            StreamDeckEvent::KeyUp { context, .. } => self.key_up(context),
+            StreamDeckEvent::WillDisappear { context, .. } => self.stop(context),
+            _ => {}
+        }
+    }
+
+    fn start_repeat(&self, steps: Vec<InputStep>, interval: Duration) -> Active {
+        let state: Arc<Mutex<RepeatState>> = Arc::default();
+        let (wake, wake_rx) = crossbeam_channel::bounded::<()>(1);
+        let exec = Arc::clone(&self.exec);
+        let st = Arc::clone(&state);
+
+        std::thread::spawn(move || {
+            loop {
+                let job = {
+                    let mut s = lock_repeat(&st);
+                    if s.stopped {
+                        break;
+                    }
+                    let job = exec.submit(steps.clone());
+                    s.current = Some(job.clone());
+                    job
+                };
+                job.wait();
+                match wake_rx.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    _ => break,
+                }
+            }
+        });
+
+        Active::Repeat { state, wake }
+    }
+
+    fn end(&self, active: Active) {
+        match active {
+            Active::Jobs(jobs) => jobs.iter().for_each(JobHandle::cancel),
+            Active::Hold { job, release } => {
+                // cancel releases what it pressed so far; if it already finished,
+                // release what the macro leaves held (extra key-ups are harmless)
+                job.cancel();
+                if !release.is_empty() {
+                    self.exec
+                        .submit(Job::new(release).priority(JobPriority::High));
+                }
+            }
+            Active::Repeat { state, wake } => {
+                let mut s = lock_repeat(&state);
+                s.stopped = true;
+                if let Some(job) = s.current.take() {
+                    job.cancel();
+                }
+                let _ = wake.try_send(());
+            }
+        }
+    }
+}
+
+impl<S: InputSynth + ?Sized> Drop for MacroController<S> {
+    // everything the controller started ends with it; nothing stays pressed
+    fn drop(&mut self) {
+        self.stop_all();
+    }
+}
+
+impl Active {
+    fn is_live(&self) -> bool {
+        match self {
+            Active::Jobs(jobs) => jobs.iter().any(JobHandle::is_running),
+            // holds stay live until released, even after the press job finished
+            Active::Hold { .. } | Active::Repeat { .. } => true,
+        }
+    }
+}
+
+fn lock_repeat(state: &Mutex<RepeatState>) -> MutexGuard<'_, RepeatState> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Releases for whatever `steps` leave pressed, in reverse press order.
+fn release_steps(steps: &[InputStep]) -> Vec<InputStep> {
+    let mut held = Held::default();
+    for step in steps {
+        held.track(step);
+    }
+    held.take_releases()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{JobStatus, Key, RecordingSynth};
+
+    const WAIT: Duration = Duration::from_secs(5);
+
+    fn down(k: Key) -> InputStep {
+        k.to_step_down().unwrap()
+    }
+
+    fn up(k: Key) -> InputStep {
+        k.to_step_up().unwrap()
+    }
+
+    fn setup() -> (Arc<RecordingSynth>, Arc<Executor<RecordingSynth>>) {
+        let synth = Arc::new(RecordingSynth::new());
+        let exec = Arc::new(Executor::new(Arc::clone(&synth)));
+        (synth, exec)
+    }
+
+    /// Wait until everything submitted so far has run.
+    fn settle<S: InputSynth + ?Sized>(exec: &Executor<S>) {
+        let last = exec.submit(Job::new([]).priority(JobPriority::Low));
+        assert_eq!(last.wait_timeout(WAIT), Some(JobStatus::Completed));
+    }
+
+    #[test]
+    fn second_one_shot_press_lets_the_first_complete() {
+        let (synth, exec) = setup();
+        let ctrl = MacroController::new(Arc::clone(&exec));
+        // park the worker so both runs are queued when the second press lands
+        let (go, gate) = crossbeam_channel::bounded::<()>(0);
+        exec.submit(Job::new([]).on_status(move |_, _| {
+            let _ = gate.recv_timeout(WAIT);
+        }));
+
+        ctrl.key_down("ctx", MacroMode::OneShot, vec![down(Key::A), up(Key::A)]);
+        ctrl.key_down("ctx", MacroMode::OneShot, vec![down(Key::A), up(Key::A)]);
+        go.send(()).unwrap();
+        settle(&exec);
+
+        assert_eq!(synth.presses(Key::A), 2);
+        synth.assert_nothing_held();
+    }
+
+    #[test]
+    fn hold_is_released_on_key_up() {
+        let (synth, exec) = setup();
+        let ctrl = MacroController::new(Arc::clone(&exec));
+        ctrl.key_down("ctx", MacroMode::HoldWhilePressed, vec![down(Key::W)]);
+        settle(&exec);
+        assert!(synth.is_held(Key::W));
+
+        ctrl.key_up("ctx");
+        settle(&exec);
+        synth.assert_nothing_held();
+        assert!(!ctrl.is_active("ctx"));
+    }
+
+    #[test]
+    fn dropping_the_controller_releases_holds_and_toggles() {
+        let (synth, exec) = setup();
+        let ctrl = MacroController::new(Arc::clone(&exec));
+        ctrl.key_down("a", MacroMode::HoldWhilePressed, vec![down(Key::LShift)]);
+        ctrl.key_down("b", MacroMode::Toggle, vec![down(Key::W)]);
+        settle(&exec);
+        assert!(synth.is_held(Key::LShift) && synth.is_held(Key::W));
+
+        drop(ctrl);
+        settle(&exec);
+        synth.assert_nothing_held();
+    }
+
+    #[test]
+    fn stop_cancels_every_queued_one_shot() {
+        let (synth, exec) = setup();
+        let ctrl = MacroController::new(Arc::clone(&exec));
+        let (go, gate) = crossbeam_channel::bounded::<()>(0);
+        exec.submit(Job::new([]).on_status(move |_, _| {
+            let _ = gate.recv_timeout(WAIT);
+        }));
+
+        for _ in 0..3 {
+            ctrl.key_down("ctx", MacroMode::OneShot, vec![down(Key::A), up(Key::A)]);
+        }
+        ctrl.stop("ctx");
+        go.send(()).unwrap();
+        settle(&exec);
+
+        assert_eq!(synth.presses(Key::A), 0);
+        assert!(!ctrl.is_active("ctx"));
+    }
+
+    #[test]
+    fn repeat_runs_until_key_up() {
+        let (synth, exec) = setup();
+        let ctrl = MacroController::new(Arc::clone(&exec));
+        let mode = MacroMode::RepeatWhileHeld {
+            interval: Duration::from_millis(5),
+        };
+        ctrl.key_down("ctx", mode, vec![down(Key::F), up(Key::F)]);
+        let deadline = std::time::Instant::now() + WAIT;
+        while synth.presses(Key::F) < 3 {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "repeat never ran 3 times"
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        ctrl.key_up("ctx");
+        settle(&exec);
+        let runs = synth.presses(Key::F);
+        std::thread::sleep(Duration::from_millis(50));
+        settle(&exec);
+        assert_eq!(
+            synth.presses(Key::F),
+            runs,
+            "repeat kept going after key up"
+        );
+        synth.assert_nothing_held();
+        assert!(!ctrl.is_active("ctx"));
+    }
+
+    #[test]
+    fn repeat_stopped_while_its_run_is_queued_never_presses() {
+        let (synth, exec) = setup();
+        let ctrl = MacroController::new(Arc::clone(&exec));
+        let (go, gate) = crossbeam_channel::bounded::<()>(0);
+        exec.submit(Job::new([]).on_status(move |_, _| {
+            let _ = gate.recv_timeout(WAIT);
+        }));
+
+        let mode = MacroMode::RepeatWhileHeld {
+            interval: Duration::from_secs(3600),
+        };
+        ctrl.key_down("ctx", mode, vec![down(Key::W)]);
+        // whether the repeat thread submitted yet or not, the stop wins
+        ctrl.key_up("ctx");
+        go.send(()).unwrap();
+        settle(&exec);
+
+        assert_eq!(synth.presses(Key::W), 0);
+        synth.assert_nothing_held();
+    }
+
+    #[test]
+    fn repeat_waiting_out_its_interval_is_woken_by_stop() {
+        let (synth, exec) = setup();
+        let ctrl = MacroController::new(Arc::clone(&exec));
+        let mode = MacroMode::RepeatWhileHeld {
+            interval: Duration::from_secs(3600),
+        };
+        ctrl.key_down("ctx", mode, vec![down(Key::F), up(Key::F)]);
+        let deadline = std::time::Instant::now() + WAIT;
+        while synth.presses(Key::F) < 1 {
+            assert!(std::time::Instant::now() < deadline, "repeat never ran");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        ctrl.key_up("ctx");
+        settle(&exec);
+        assert_eq!(synth.presses(Key::F), 1);
+        assert!(!ctrl.is_active("ctx"));
+    }
+
+    #[test]
+    fn toggle_releases_on_the_second_press_and_ignores_key_up() {
+        let (synth, exec) = setup();
+        let ctrl = MacroController::new(Arc::clone(&exec));
+        ctrl.key_down("ctx", MacroMode::Toggle, vec![down(Key::W)]);
+        ctrl.key_up("ctx");
+        settle(&exec);
+        assert!(synth.is_held(Key::W));
+        assert!(ctrl.is_active("ctx"));
+
+        ctrl.key_down("ctx", MacroMode::Toggle, vec![down(Key::W)]);
+        ctrl.key_up("ctx");
+        settle(&exec);
+        synth.assert_nothing_held();
+        assert_eq!(synth.presses(Key::W), 1);
+        assert!(!ctrl.is_active("ctx"));
+    }
+
+    #[test]
+    fn will_disappear_releases_the_context() {
+        let (synth, exec) = setup();
+        let ctrl = MacroController::new(Arc::clone(&exec));
+        ctrl.key_down("ctx", MacroMode::Toggle, vec![down(Key::W)]);
+        ctrl.key_down("other", MacroMode::HoldWhilePressed, vec![down(Key::S)]);
+        settle(&exec);
+
+        let ev = crate::sd_protocol::parse_incoming(
+            &serde_json::json!({
+                "event": "willDisappear",
+                "action": "com.example.macro",
+                "context": "ctx",
+                "device": "dev",
+                "payload": { "controller": "Keypad", "settings": {} },
+            })
+            .to_string(),
+        )
+        .unwrap();
+        ctrl.on_event(&ev);
+        settle(&exec);
+
+        assert!(!synth.is_held(Key::W));
+        assert!(!ctrl.is_active("ctx"));
+        assert!(synth.is_held(Key::S), "other contexts keep going");
+    }
+}
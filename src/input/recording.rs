@@ -0,0 +1,370 @@
+// src/input/recording.rs
+//! `InputSynth` that sends nothing: it records steps on a virtual clock and
+//! tracks what is held, so macros can be checked in tests.
+
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use super::InputSynth;
+use super::key::Key;
+use super::types::{InputStep, MouseButton, Scan};
+
+/// A step and the virtual time it was sent at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RecordedStep {
+    pub at: Duration,
+    pub step: InputStep,
+}
+
+#[derive(Default)]
+struct State {
+    now: Duration,
+    steps: Vec<RecordedStep>,
+    keys: Vec<Scan>,
+    buttons: Vec<MouseButton>,
+    /// User presses (`true`) and releases, keyed by the number of steps recorded before them.
+    physical: Vec<(usize, Scan, bool)>,
+}
+
+/// Records every step; `Sleep` advances the virtual clock instead of blocking.
+#[derive(Default)]
+pub struct RecordingSynth {
+    state: Mutex<State>,
+}
+
+impl RecordingSynth {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // a panicking assertion must not make the recording unreadable
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Everything sent so far, with timestamps.
+    pub fn recorded(&self) -> Vec<RecordedStep> {
+        self.state().steps.clone()
+    }
+
+    /// Everything sent so far, without timestamps.
+    pub fn steps(&self) -> Vec<InputStep> {
+        self.state().steps.iter().map(|r| r.step).collect()
+    }
+
+    /// Text typed through `Unicode` steps.
+    pub fn typed_text(&self) -> String {
+        self.state()
+            .steps
+            .iter()
+            .filter_map(|r| match r.step {
+                InputStep::Unicode(c) => Some(c),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Virtual time spent in `Sleep` steps.
+    pub fn elapsed(&self) -> Duration {
+        self.state().now
+    }
+
+    /// Keys currently pressed, in press order.
+    pub fn held_keys(&self) -> Vec<Scan> {
+        self.state().keys.clone()
+    }
+
+    /// Mouse buttons currently pressed, in press order.
+    pub fn held_buttons(&self) -> Vec<MouseButton> {
+        self.state().buttons.clone()
+    }
+
+    pub fn is_held(&self, key: Key) -> bool {
+        key.to_scan()
+            .is_some_and(|s| self.state().keys.contains(&s))
+    }
+
+    pub fn no_keys_held(&self) -> bool {
+        self.state().keys.is_empty()
+    }
+
+    /// No key and no mouse button held.
+    pub fn nothing_held(&self) -> bool {
+        let st = self.state();
+        st.keys.is_empty() && st.buttons.is_empty()
+    }
+
+    /// True if `main` went down at some point while all `mods` were held
+    /// (by steps or by [`RecordingSynth::hold_physically`]).
+    pub fn chord_issued(&self, mods: &[Key], main: Key) -> bool {
+        let (Some(main), Some(mods)) = (
+            main.to_scan(),
+            mods.iter().map(|m| m.to_scan()).collect::<Option<Vec<_>>>(),
+        ) else {
+            return false;
+        };
+
+        let st = self.state();
+        let mut held: Vec<Scan> = Vec::new();
+        let mut physical = st.physical.iter().peekable();
+        for (i, r) in st.steps.iter().enumerate() {
+            while let Some((_, s, down)) = physical.next_if(|(at, _, _)| *at <= i) {
+                if !*down {
+                    held.retain(|h| h != s);
+                } else if !held.contains(s) {
+                    held.push(*s);
+                }
+            }
+            match r.step {
+                InputStep::KeyDown(s) => {
+                    if s == main && mods.iter().all(|m| held.contains(m)) {
+                        return true;
+                    }
+                    if !held.contains(&s) {
+                        held.push(s);
+                    }
+                }
+                InputStep::KeyUp(s) => held.retain(|h| *h != s),
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// Number of times `key` went down.
+    pub fn presses(&self, key: Key) -> usize {
+        let Some(scan) = key.to_scan() else {
+            return 0;
+        };
+        self.state()
+            .steps
+            .iter()
+            .filter(|r| r.step == InputStep::KeyDown(scan))
+            .count()
+    }
+
+    /// Panics if any key or mouse button is still held.
+    #[track_caller]
+    pub fn assert_nothing_held(&self) {
+        let st = self.state();
+        assert!(
+            st.keys.is_empty() && st.buttons.is_empty(),
+            "input left pressed: keys {:?}, buttons {:?}",
+            st.keys,
+            st.buttons
+        );
+    }
+
+    /// Panics unless `mods + main` was issued (see [`RecordingSynth::chord_issued`]).
+    #[track_caller]
+    pub fn assert_chord(&self, mods: &[Key], main: Key) {
+        assert!(
+            self.chord_issued(mods, main),
+            "chord {mods:?}+{main:?} not issued; steps: {:?}",
+            self.steps()
+        );
+    }
+
+    /// Mark `key` as held by the user: no step is recorded, but it counts as held
+    /// (and shows up in `modifier_state`) until a step or `release_physically` lifts it.
+    pub fn hold_physically(&self, key: Key) {
+        if let Some(s) = key.to_scan() {
+            let mut st = self.state();
+            let at = st.steps.len();
+            st.physical.push((at, s, true));
+            if !st.keys.contains(&s) {
+                st.keys.push(s);
+            }
+        }
+    }
+
+    /// The user lets go of `key`; no step is recorded.
+    pub fn release_physically(&self, key: Key) {
+        if let Some(s) = key.to_scan() {
+            let mut st = self.state();
+            let at = st.steps.len();
+            st.physical.push((at, s, false));
+            st.keys.retain(|k| *k != s);
+        }
+    }
+
+    /// Forget all recorded steps and held state; resets the clock.
+    pub fn clear(&self) {
+        *self.state() = State::default();
+    }
+}
+
+impl InputSynth for RecordingSynth {
+    fn blocks_on_sleep(&self) -> bool {
+        false
+    }
+
+    fn modifier_state(&self) -> Option<Vec<Key>> {
+        let st = self.state();
+        let held = st
+            .keys
+            .iter()
+            .filter_map(|s| Key::from_windows_scan(*s))
+            .filter(|k| k.is_modifier())
+            .collect();
+        Some(held)
+    }
+
+    fn send_step(&self, step: &InputStep) -> Result<(), String> {
+        let mut st = self.state();
+        let at = st.now;
+        st.steps.push(RecordedStep { at, step: *step });
+        match *step {
+            InputStep::KeyDown(s) => {
+                if !st.keys.contains(&s) {
+                    st.keys.push(s);
+                }
+            }
+            InputStep::KeyUp(s) => st.keys.retain(|k| *k != s),
+            InputStep::MouseDown(b) => {
+                if !st.buttons.contains(&b) {
+                    st.buttons.push(b);
+                }
+            }
+            InputStep::MouseUp(b) => st.buttons.retain(|x| *x != b),
+            InputStep::Sleep(d) => st.now += d,
+            InputStep::Unicode(_)
+            | InputStep::MouseMove { .. }
+            | InputStep::MouseMoveTo { .. }
+            | InputStep::Scroll { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn down(k: Key) -> InputStep {
+        k.to_step_down().unwrap()
+    }
+
+    fn up(k: Key) -> InputStep {
+        k.to_step_up().unwrap()
+    }
+
+    fn send(synth: &RecordingSynth, steps: &[InputStep]) {
+        for step in steps {
+            synth.send_step(step).unwrap();
+        }
+    }
+
+    #[test]
+    fn sleeps_advance_the_virtual_clock() {
+        let synth = RecordingSynth::new();
+        let wall = std::time::Instant::now();
+        send(
+            &synth,
+            &[
+                down(Key::A),
+                InputStep::Sleep(Duration::from_secs(60)),
+                up(Key::A),
+                InputStep::Sleep(Duration::from_millis(5)),
+            ],
+        );
+        assert!(wall.elapsed() < Duration::from_secs(60));
+        assert!(!synth.blocks_on_sleep());
+        assert_eq!(synth.elapsed(), Duration::from_millis(60_005));
+
+        let at: Vec<Duration> = synth.recorded().iter().map(|r| r.at).collect();
+        assert_eq!(
+            at,
+            [0, 0, 60_000, 60_000].map(Duration::from_millis).to_vec()
+        );
+
+        synth.clear();
+        assert_eq!(synth.elapsed(), Duration::ZERO);
+        assert!(synth.steps().is_empty());
+    }
+
+    #[test]
+    fn held_state_follows_the_steps() {
+        let synth = RecordingSynth::new();
+        send(
+            &synth,
+            &[
+                down(Key::LShift),
+                down(Key::A),
+                InputStep::MouseDown(MouseButton::Left),
+            ],
+        );
+        assert_eq!(
+            synth.held_keys(),
+            vec![Key::LShift.to_scan().unwrap(), Key::A.to_scan().unwrap()]
+        );
+        assert_eq!(synth.held_buttons(), vec![MouseButton::Left]);
+        assert_eq!(synth.modifier_state(), Some(vec![Key::LShift]));
+
+        send(
+            &synth,
+            &[
+                up(Key::A),
+                up(Key::LShift),
+                InputStep::MouseUp(MouseButton::Left),
+            ],
+        );
+        assert!(synth.nothing_held());
+        synth.assert_nothing_held();
+    }
+
+    #[test]
+    fn chord_needs_every_modifier_down_before_the_main_key() {
+        let synth = RecordingSynth::new();
+        send(
+            &synth,
+            &[
+                down(Key::LCtrl),
+                down(Key::LShift),
+                down(Key::S),
+                up(Key::S),
+            ],
+        );
+        assert!(synth.chord_issued(&[Key::LCtrl, Key::LShift], Key::S));
+        assert!(synth.chord_issued(&[Key::LCtrl], Key::S));
+        assert!(!synth.chord_issued(&[Key::LAlt], Key::S));
+        synth.assert_chord(&[Key::LShift, Key::LCtrl], Key::S);
+
+        // pressed too late, or released too early
+        synth.clear();
+        send(&synth, &[down(Key::S), down(Key::LCtrl), up(Key::S)]);
+        assert!(!synth.chord_issued(&[Key::LCtrl], Key::S));
+        synth.clear();
+        send(&synth, &[down(Key::LCtrl), up(Key::LCtrl), down(Key::S)]);
+        assert!(!synth.chord_issued(&[Key::LCtrl], Key::S));
+    }
+
+    #[test]
+    fn physical_holds_count_toward_chords_from_when_they_happen() {
+        let synth = RecordingSynth::new();
+        send(&synth, &[down(Key::C), up(Key::C)]);
+        synth.hold_physically(Key::LCtrl);
+        assert!(synth.is_held(Key::LCtrl));
+        assert_eq!(synth.modifier_state(), Some(vec![Key::LCtrl]));
+        // nothing is recorded for the user's hand
+        assert_eq!(synth.steps().len(), 2);
+
+        send(&synth, &[down(Key::V), up(Key::V)]);
+        synth.release_physically(Key::LCtrl);
+        send(&synth, &[down(Key::X), up(Key::X)]);
+
+        assert!(!synth.chord_issued(&[Key::LCtrl], Key::C));
+        assert!(synth.chord_issued(&[Key::LCtrl], Key::V));
+        assert!(!synth.chord_issued(&[Key::LCtrl], Key::X));
+        assert!(synth.nothing_held());
+    }
+
+    #[test]
+    fn a_step_lifts_a_physical_hold() {
+        let synth = RecordingSynth::new();
+        synth.hold_physically(Key::LShift);
+        send(&synth, &[up(Key::LShift), down(Key::A)]);
+        assert!(!synth.is_held(Key::LShift));
+        assert!(!synth.chord_issued(&[Key::LShift], Key::A));
+    }
+}
@@ -0,0 +1,176 @@
+// src/input/safety.rs
+//! Stuck-key safety net: every `Executor` tracks what its synth holds pressed,
+//! so it can be released on drop, on panic, on runtime shutdown, or on demand.
+
+use std::sync::{Arc, Mutex, MutexGuard, Once, Weak};
+
+use tracing::warn;
+
+use super::InputSynth;
+use super::types::{InputStep, MouseButton, Scan};
+
+/// Keys and buttons pressed and not released yet, in press order.
+#[derive(Default, Debug)]
+pub(crate) struct Held {
+    keys: Vec<Scan>,
+    buttons: Vec<MouseButton>,
+}
+
+impl Held {
+    pub(crate) fn track(&mut self, step: &InputStep) {
+        match *step {
+            InputStep::KeyDown(s) if !self.keys.contains(&s) => self.keys.push(s),
+            InputStep::KeyUp(s) => self.keys.retain(|k| *k != s),
+            InputStep::MouseDown(b) if !self.buttons.contains(&b) => self.buttons.push(b),
+            InputStep::MouseUp(b) => self.buttons.retain(|x| *x != b),
+            _ => {}
+        }
+    }
+
+    /// Release steps for everything held (reverse press order); clears the set.
+    pub(crate) fn take_releases(&mut self) -> Vec<InputStep> {
+        let mut out: Vec<InputStep> = self
+            .buttons
+            .drain(..)
+            .rev()
+            .map(InputStep::MouseUp)
+            .collect();
+        out.extend(self.keys.drain(..).rev().map(InputStep::KeyUp));
+        out
+    }
+}
+
+/// A synth plus what has been pressed through it.
+pub(crate) struct Tracked<S: InputSynth + ?Sized> {
+    pub(crate) synth: Arc<S>,
+    held: Mutex<Held>,
+}
+
+impl<S: InputSynth + ?Sized> Tracked<S> {
+    pub(crate) fn new(synth: Arc<S>) -> Self {
+        Self {
+            synth,
+            held: Mutex::new(Held::default()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Held> {
+        self.held.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Send a step and record its effect on what is held.
+    pub(crate) fn send(&self, step: &InputStep) -> Result<(), String> {
+        self.synth.send_step(step)?;
+        self.lock().track(step);
+        Ok(())
+    }
+
+    /// Release what `held` (a subset, e.g. one job's presses) still holds; best effort.
+    pub(crate) fn release(&self, held: &mut Held) {
+        for step in held.take_releases() {
+            let _ = self.send(&step);
+        }
+    }
+
+    fn release_steps(&self, steps: Vec<InputStep>) {
+        for step in steps {
+            // send directly: the tracked set was already cleared
+            if let Err(e) = self.synth.send_step(&step) {
+                warn!("⚠️ failed to release {:?}: {}", step, e);
+            }
+        }
+    }
+}
+
+/// Type-erased view of a `Tracked` for the global registry.
+pub(crate) trait ReleaseAll: Send + Sync {
+    fn release_all(&self);
+    /// Non-blocking variant for the panic hook (skips if the state is locked).
+    fn try_release_all(&self);
+}
+
+impl<S: InputSynth + ?Sized> ReleaseAll for Tracked<S> {
+    fn release_all(&self) {
+        let steps = self.lock().take_releases();
+        self.release_steps(steps);
+    }
+
+    fn try_release_all(&self) {
+        let steps = match self.held.try_lock() {
+            Ok(mut held) => held.take_releases(),
+            Err(std::sync::TryLockError::Poisoned(p)) => p.into_inner().take_releases(),
+            Err(std::sync::TryLockError::WouldBlock) => return,
+        };
+        self.release_steps(steps);
+    }
+}
+
+/// Cancels an executor's queued and running jobs.
+pub(crate) type CancelFn = Box<dyn Fn() + Send + Sync>;
+
+/// One executor in the registry.
+struct Entry {
+    tracked: Weak<dyn ReleaseAll>,
+    cancel: Arc<CancelFn>,
+}
+
+static LIVE: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+
+fn live() -> MutexGuard<'static, Vec<Entry>> {
+    LIVE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Make `t` reachable from [`release_all`] and the panic hook while it lives;
+/// `cancel` stops its jobs before the release.
+pub(crate) fn register(t: Weak<dyn ReleaseAll>, cancel: CancelFn) {
+    let mut live = live();
+    live.retain(|e| e.tracked.strong_count() > 0);
+    live.push(Entry {
+        tracked: t,
+        cancel: Arc::new(cancel),
+    });
+}
+
+pub(crate) type LiveEntry = (Arc<dyn ReleaseAll>, Arc<CancelFn>);
+
+pub(crate) fn live_entries() -> Vec<LiveEntry> {
+    live()
+        .iter()
+        .filter_map(|e| Some((e.tracked.upgrade()?, Arc::clone(&e.cancel))))
+        .collect()
+}
+
+/// Cancel the jobs of every live `Executor`, then release every key and mouse
+/// button held through them, so nothing queued presses again afterwards.
+pub fn release_all() {
+    release(&live_entries());
+}
+
+pub(crate) fn release(entries: &[LiveEntry]) {
+    for (_, cancel) in entries {
+        cancel();
+    }
+    for (t, _) in entries {
+        t.release_all();
+    }
+}
+
+/// Chain a panic hook that releases held input before the previous hook runs.
+/// Idempotent; the runtime installs it on start.
+pub fn install_panic_hook() {
+    static ONCE: Once = Once::new();
+    ONCE.call_once(|| {
+        let prev = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            // release only: cancelling runs status callbacks, too much for a panic hook
+            let trackers: Vec<Arc<dyn ReleaseAll>> = match LIVE.try_lock() {
+                Ok(live) => live.iter().filter_map(|e| e.tracked.upgrade()).collect(),
+                Err(_) => Vec::new(),
+            };
+            for t in trackers {
+                t.try_release_all();
+            }
+            prev(info);
+        }));
+    });
+}
@@ -0,0 +1,775 @@
+// src/input/script.rs
+//! Text syntax for macros, so a property inspector can pass them as strings.
+//!
+//! Statements are separated by `,`, `;` or newlines:
+//!
+//! ```text
+//! ctrl+shift+s, wait 50ms, hold space 200ms, click left x2
+//! ```
+//!
+//! - `a`, `ctrl+c`, `ctrl+c x3`: tap a key or chord (optionally repeated)
+//! - `tap up x2`: same, spelled out; needed when the key name is also a statement
+//!   (`up x2` would release a key called `x2`)
+//! - `hold ctrl+a 200ms`: press, wait, release
+//! - `down lshift`, `up lshift`: press or release only
+//! - `wait 50ms` / `sleep 1.5s` (`ns`, `us`, `ms`, `s`; bare numbers are ms)
+//! - `type "Hello, world!\n"`: type text (escapes: `\"`, `\\`, `\n`, `\t`)
+//! - `click left`, `click right x2`, `mousedown x1`, `mouseup x1`
+//! - `move 10 -5`, `moveto 800 600`, `moveto 32768 32768 normalized`
+//! - `scroll -3` (vertical), `scroll 0 2` (vertical, horizontal); positive is up / right
+//! - `sc:0x1e`, `sc:0xe01d`: raw scancode (E0 prefix = extended)
+//!
+//! Key names go through [`Key::parse`]; use `comma` for the `,` key.
+
+use std::ops::Range;
+use std::time::Duration;
+
+use thiserror::Error;
+
+use super::key::Key;
+use super::types::{InputStep, MouseButton, Scan};
+
+/// What went wrong while parsing a macro script.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ScriptErrorKind {
+    #[error("unknown key `{0}`")]
+    UnknownKey(String),
+    #[error("key `{0}` has no scancode")]
+    NoScancode(String),
+    #[error("unknown mouse button `{0}`")]
+    UnknownButton(String),
+    #[error("invalid duration `{0}`")]
+    BadDuration(String),
+    #[error("invalid number `{0}`")]
+    BadNumber(String),
+    #[error("invalid repeat count `{0}` (expected e.g. `x2`)")]
+    BadCount(String),
+    #[error("expected {0}")]
+    Expected(&'static str),
+    #[error("unexpected `{0}`")]
+    Unexpected(String),
+}
+
+/// Parse error with the byte range of the offending text.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{kind} at {}..{}", span.start, span.end)]
+pub struct ScriptError {
+    pub span: Range<usize>,
+    pub kind: ScriptErrorKind,
+}
+
+impl ScriptError {
+    fn new(span: Range<usize>, kind: ScriptErrorKind) -> Self {
+        Self { span, kind }
+    }
+}
+
+/// A word of the input and where it starts.
+#[derive(Clone, Copy)]
+struct Word<'a> {
+    text: &'a str,
+    at: usize,
+}
+
+impl Word<'_> {
+    fn span(&self) -> Range<usize> {
+        self.at..self.at + self.text.len()
+    }
+}
+
+/// Parse a macro script into steps.
+pub fn parse_script(src: &str) -> Result<Vec<InputStep>, ScriptError> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut quote = Quote::default();
+    for (i, c) in src.char_indices() {
+        if quote.feed(c) {
+            continue;
+        }
+        if matches!(c, ',' | ';' | '\n') {
+            parse_words(&src[start..i], start, &mut out)?;
+            start = i + 1;
+        }
+    }
+    if quote.open {
+        let end = src.len();
+        return Err(ScriptError::new(
+            end..end,
+            ScriptErrorKind::Expected("closing `\"`"),
+        ));
+    }
+    parse_words(&src[start..], start, &mut out)?;
+    Ok(out)
+}
+
+fn parse_words(stmt: &str, offset: usize, out: &mut Vec<InputStep>) -> Result<(), ScriptError> {
+    let words = split_words(stmt, offset);
+    if words.is_empty() {
+        return Ok(());
+    }
+    parse_statement(&words, out)
+}
+
+/// Tracks `"..."` strings (with `\` escapes) so separators inside them are text.
+#[derive(Default)]
+struct Quote {
+    open: bool,
+    escaped: bool,
+}
+
+impl Quote {
+    /// Feed one char; returns true if it belongs to a quoted string.
+    fn feed(&mut self, c: char) -> bool {
+        if !self.open {
+            self.open = c == '"';
+            return self.open;
+        }
+        if self.escaped {
+            self.escaped = false;
+        } else if c == '\\' {
+            self.escaped = true;
+        } else if c == '"' {
+            self.open = false;
+        }
+        true
+    }
+}
+
+fn split_words(stmt: &str, offset: usize) -> Vec<Word<'_>> {
+    let mut words = Vec::new();
+    let mut begin = None;
+    let mut quote = Quote::default();
+    for (i, c) in stmt.char_indices().chain([(stmt.len(), ' ')]) {
+        let quoted = i < stmt.len() && quote.feed(c);
+        match (c.is_whitespace() && !quoted, begin) {
+            (true, Some(b)) => {
+                words.push(Word {
+                    text: &stmt[b..i],
+                    at: offset + b,
+                });
+                begin = None;
+            }
+            (false, None) => begin = Some(i),
+            _ => {}
+        }
+    }
+    words
+}
+
+fn parse_statement(words: &[Word<'_>], out: &mut Vec<InputStep>) -> Result<(), ScriptError> {
+    let head = words[0];
+    let rest = &words[1..];
+    // error position for a missing argument: right after the last word
+    let end = words[words.len() - 1].span().end;
+    let missing = |what| ScriptError::new(end..end, ScriptErrorKind::Expected(what));
+
+    match head.text.to_lowercase().as_str() {
+        "wait" | "sleep" => {
+            let [d] = rest else {
+                return Err(arity(rest, 1, missing("a duration")));
+            };
+            out.push(InputStep::Sleep(parse_duration(*d)?));
+        }
+        "type" => {
+            let [text] = rest else {
+                return Err(arity(rest, 1, missing("a quoted string")));
+            };
+            out.extend(parse_quoted(*text)?.chars().map(InputStep::Unicode));
+        }
+        "hold" => {
+            let [keys, d] = rest else {
+                return Err(arity(rest, 2, missing("keys and a duration")));
+            };
+            let keys = parse_chord(*keys)?;
+            let d = parse_duration(*d)?;
+            out.extend(keys.iter().map(|&s| InputStep::KeyDown(s)));
+            out.push(InputStep::Sleep(d));
+            out.extend(keys.iter().rev().map(|&s| InputStep::KeyUp(s)));
+        }
+        "tap" => {
+            let Some((keys, rest)) = rest.split_first() else {
+                return Err(missing("a key"));
+            };
+            tap(*keys, rest, out, missing)?;
+        }
+        // a lone `up`/`down` is the arrow key
+        "down" | "up" if !rest.is_empty() => {
+            let [keys] = rest else {
+                return Err(arity(rest, 1, missing("keys")));
+            };
+            let keys = parse_chord(*keys)?;
+            if head.text.eq_ignore_ascii_case("down") {
+                out.extend(keys.into_iter().map(InputStep::KeyDown));
+            } else {
+                out.extend(keys.into_iter().map(InputStep::KeyUp));
+            }
+        }
+        "click" => {
+            let (btn, count) = match rest {
+                [b] => (*b, 1),
+                [b, n] => (*b, parse_count(*n)?),
+                _ => return Err(arity(rest, 2, missing("a mouse button"))),
+            };
+            let btn = parse_button(btn)?;
+            for _ in 0..count {
+                out.push(InputStep::MouseDown(btn));
+                out.push(InputStep::MouseUp(btn));
+            }
+        }
+        "move" => {
+            let [dx, dy] = rest else {
+                return Err(arity(rest, 2, missing("`dx dy`")));
+            };
+            out.push(InputStep::MouseMove {
+                dx: parse_int(*dx)?,
+                dy: parse_int(*dy)?,
+            });
+        }
+        "moveto" => {
+            let (x, y, normalized) = match rest {
+                [x, y] => (*x, *y, false),
+                [x, y, n] if n.text.eq_ignore_ascii_case("normalized") => (*x, *y, true),
+                _ => return Err(arity(rest, 2, missing("`x y`"))),
+            };
+            out.push(InputStep::MouseMoveTo {
+                x: parse_int(x)?,
+                y: parse_int(y)?,
+                normalized,
+            });
+        }
+        "scroll" => {
+            let (v, h) = match rest {
+                [v] => (parse_int(*v)?, 0),
+                [v, h] => (parse_int(*v)?, parse_int(*h)?),
+                _ => return Err(arity(rest, 2, missing("wheel notches"))),
+            };
+            out.push(InputStep::Scroll {
+                vertical: v,
+                horizontal: h,
+            });
+        }
+        "mousedown" | "mouseup" => {
+            let [b] = rest else {
+                return Err(arity(rest, 1, missing("a mouse button")));
+            };
+            let btn = parse_button(*b)?;
+            out.push(if head.text.eq_ignore_ascii_case("mousedown") {
+                InputStep::MouseDown(btn)
+            } else {
+                InputStep::MouseUp(btn)
+            });
+        }
+        _ => tap(head, rest, out, missing)?,
+    }
+    Ok(())
+}
+
+/// Statement words; a chord spelled like one of these needs an explicit `tap`.
+const KEYWORDS: &[&str] = &[
+    "wait",
+    "sleep",
+    "type",
+    "hold",
+    "tap",
+    "down",
+    "up",
+    "click",
+    "move",
+    "moveto",
+    "scroll",
+    "mousedown",
+    "mouseup",
+];
+
+/// `keys [xN]`: press and release the chord, `N` times.
+fn tap(
+    keys: Word<'_>,
+    rest: &[Word<'_>],
+    out: &mut Vec<InputStep>,
+    missing: impl Fn(&'static str) -> ScriptError,
+) -> Result<(), ScriptError> {
+    let count = match rest {
+        [] => 1,
+        [n] => parse_count(*n)?,
+        _ => return Err(arity(rest, 1, missing("a key"))),
+    };
+    let keys = parse_chord(keys)?;
+    for _ in 0..count {
+        out.extend(keys.iter().map(|&s| InputStep::KeyDown(s)));
+        out.extend(keys.iter().rev().map(|&s| InputStep::KeyUp(s)));
+    }
+    Ok(())
+}
+
+/// Too few arguments → `missing`; too many → point at the first extra word.
+fn arity(rest: &[Word<'_>], max: usize, missing: ScriptError) -> ScriptError {
+    match rest.get(max) {
+        Some(extra) => ScriptError::new(
+            extra.span(),
+            ScriptErrorKind::Unexpected(extra.text.to_string()),
+        ),
+        None => missing,
+    }
+}
+
+/// `"a \"b\"\n"` → `a "b"` + newline.
+fn parse_quoted(word: Word<'_>) -> Result<String, ScriptError> {
+    let inner = word
+        .text
+        .strip_prefix('"')
+        .and_then(|t| t.strip_suffix('"'))
+        .filter(|_| word.text.len() >= 2)
+        .ok_or_else(|| {
+            ScriptError::new(word.span(), ScriptErrorKind::Expected("a quoted string"))
+        })?;
+
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        out.push(match chars.next() {
+            Some((_, 'n')) => '\n',
+            Some((_, 't')) => '\t',
+            Some((_, e @ ('"' | '\\'))) => e,
+            other => {
+                // +1 for the opening quote
+                let at = word.at + 1 + i;
+                let len = 1 + other.map_or(0, |(_, e)| e.len_utf8());
+                return Err(ScriptError::new(
+                    at..at + len,
+                    ScriptErrorKind::Unexpected(inner[i..i + len].to_string()),
+                ));
+            }
+        });
+    }
+    Ok(out)
+}
+
+/// `ctrl+shift+s` → scancodes in order.
+fn parse_chord(word: Word<'_>) -> Result<Vec<Scan>, ScriptError> {
+    let mut keys = Vec::new();
+    let mut at = word.at;
+    for part in word.text.split('+') {
+        let span = at..at + part.len();
+        at += part.len() + 1;
+        if part.is_empty() {
+            return Err(ScriptError::new(span, ScriptErrorKind::Expected("a key")));
+        }
+        keys.push(parse_key(part, span)?);
+    }
+    Ok(keys)
+}
+
+fn parse_key(text: &str, span: Range<usize>) -> Result<Scan, ScriptError> {
+    if let Some(raw) = text.strip_prefix("sc:") {
+        return parse_raw_scan(raw)
+            .ok_or_else(|| ScriptError::new(span, ScriptErrorKind::UnknownKey(text.to_string())));
+    }
+    let key = Key::parse(text)
+        .ok_or_else(|| ScriptError::new(span.clone(), ScriptErrorKind::UnknownKey(text.into())))?;
+    key.to_scan()
+        .ok_or_else(|| ScriptError::new(span, ScriptErrorKind::NoScancode(text.to_string())))
+}
+
+/// `0x1e` or `0xe01d` (E0 prefix marks an extended key).
+fn parse_raw_scan(raw: &str) -> Option<Scan> {
+    let hex = raw.strip_prefix("0x").or_else(|| raw.strip_prefix("0X"))?;
+    let v = u16::from_str_radix(hex, 16).ok()?;
+    match v {
+        0..=0xff => Some(Scan::new(v, false)),
+        0xe000..=0xe0ff => Some(Scan::new(v & 0xff, true)),
+        _ => None,
+    }
+}
+
+fn parse_button(word: Word<'_>) -> Result<MouseButton, ScriptError> {
+    Ok(match word.text.to_lowercase().as_str() {
+        "left" | "l" => MouseButton::Left,
+        "right" | "r" => MouseButton::Right,
+        "middle" | "m" => MouseButton::Middle,
+        "x1" => MouseButton::X1,
+        "x2" => MouseButton::X2,
+        _ => {
+            return Err(ScriptError::new(
+                word.span(),
+                ScriptErrorKind::UnknownButton(word.text.to_string()),
+            ));
+        }
+    })
+}
+
+fn parse_int(word: Word<'_>) -> Result<i32, ScriptError> {
+    word.text.parse::<i32>().map_err(|_| {
+        ScriptError::new(
+            word.span(),
+            ScriptErrorKind::BadNumber(word.text.to_string()),
+        )
+    })
+}
+
+/// `x2` → 2
+fn parse_count(word: Word<'_>) -> Result<usize, ScriptError> {
+    word.text
+        .strip_prefix(['x', 'X'])
+        .and_then(|n| n.parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .ok_or_else(|| {
+            ScriptError::new(
+                word.span(),
+                ScriptErrorKind::BadCount(word.text.to_string()),
+            )
+        })
+}
+
+/// `50ms`, `1.5s`, `250us`, `1500ns`, `50` (ms).
+fn parse_duration(word: Word<'_>) -> Result<Duration, ScriptError> {
+    let t = word.text.to_lowercase();
+    let (num, unit_ns) = if let Some(n) = t.strip_suffix("ms") {
+        (n, 1_000_000)
+    } else if let Some(n) = t.strip_suffix("us") {
+        (n, 1_000)
+    } else if let Some(n) = t.strip_suffix("ns") {
+        (n, 1)
+    } else if let Some(n) = t.strip_suffix('s') {
+        (n, 1_000_000_000)
+    } else {
+        (t.as_str(), 1_000_000)
+    };
+    let bad = || {
+        ScriptError::new(
+            word.span(),
+            ScriptErrorKind::BadDuration(word.text.to_string()),
+        )
+    };
+    // whole numbers are exact; fractions go through f64
+    if let Ok(v) = num.parse::<u64>() {
+        let nanos = u128::from(v) * unit_ns as u128;
+        let secs = u64::try_from(nanos / 1_000_000_000).map_err(|_| bad())?;
+        return Ok(Duration::new(secs, (nanos % 1_000_000_000) as u32));
+    }
+    num.parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite() && *v >= 0.0)
+        .and_then(|v| Duration::try_from_secs_f64(v * unit_ns as f64 * 1e-9).ok())
+        .ok_or_else(bad)
+}
+
+// ---- pretty-printer ----
+
+/// Render steps in script syntax; `parse_script(&to_script(s)) == s`.
+pub fn to_script(steps: &[InputStep]) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < steps.len() {
+        let (text, used) = render_one(&steps[i..]);
+        parts.push(text);
+        i += used;
+    }
+    parts.join(", ")
+}
+
+/// Render the statement at the start of `steps`; returns the text and steps consumed.
+fn render_one(steps: &[InputStep]) -> (String, usize) {
+    match steps[0] {
+        InputStep::Sleep(d) => (format!("wait {}", fmt_duration(d)), 1),
+        InputStep::Unicode(_) => {
+            let text: String = steps
+                .iter()
+                .map_while(|s| match *s {
+                    InputStep::Unicode(c) => Some(c),
+                    _ => None,
+                })
+                .collect();
+            let used = text.chars().count();
+            (format!("type {}", fmt_quoted(&text)), used)
+        }
+        InputStep::MouseDown(b) => {
+            let click = [InputStep::MouseDown(b), InputStep::MouseUp(b)];
+            let n = repeats(steps, &click);
+            if n == 0 {
+                (format!("mousedown {}", fmt_button(b)), 1)
+            } else {
+                (format!("click {}{}", fmt_button(b), fmt_count(n)), n * 2)
+            }
+        }
+        InputStep::MouseUp(b) => (format!("mouseup {}", fmt_button(b)), 1),
+        InputStep::MouseMove { dx, dy } => (format!("move {dx} {dy}"), 1),
+        InputStep::MouseMoveTo { x, y, normalized } => {
+            let norm = if normalized { " normalized" } else { "" };
+            (format!("moveto {x} {y}{norm}"), 1)
+        }
+        InputStep::Scroll {
+            vertical,
+            horizontal: 0,
+        } => (format!("scroll {vertical}"), 1),
+        InputStep::Scroll {
+            vertical,
+            horizontal,
+        } => (format!("scroll {vertical} {horizontal}"), 1),
+        InputStep::KeyUp(_) => {
+            let ups = run(steps, |s| matches!(s, InputStep::KeyUp(_)));
+            (format!("up {}", fmt_chord(&ups)), ups.len())
+        }
+        InputStep::KeyDown(_) => {
+            let downs = run(steps, |s| matches!(s, InputStep::KeyDown(_)));
+            let n = downs.len();
+            let releases: Vec<InputStep> =
+                downs.iter().rev().map(|&s| InputStep::KeyUp(s)).collect();
+
+            // chord / tap, possibly repeated
+            let mut unit: Vec<InputStep> = downs.iter().map(|&s| InputStep::KeyDown(s)).collect();
+            unit.extend(&releases);
+            let reps = repeats(steps, &unit);
+            if reps > 0 {
+                let chord = fmt_chord(&downs);
+                let tap = if KEYWORDS.contains(&chord.as_str()) {
+                    "tap "
+                } else {
+                    ""
+                };
+                return (
+                    format!("{tap}{chord}{}", fmt_count(reps)),
+                    reps * unit.len(),
+                );
+            }
+
+            // hold: downs, sleep, ups in reverse
+            if let Some(&InputStep::Sleep(d)) = steps.get(n)
+                && steps.get(n + 1..n + 1 + n) == Some(&releases[..])
+            {
+                return (
+                    format!("hold {} {}", fmt_chord(&downs), fmt_duration(d)),
+                    2 * n + 1,
+                );
+            }
+
+            (format!("down {}", fmt_chord(&downs)), n)
+        }
+    }
+}
+
+/// Scancodes of the leading run of key steps matching `pred`.
+fn run(steps: &[InputStep], pred: impl Fn(&InputStep) -> bool) -> Vec<Scan> {
+    steps
+        .iter()
+        .take_while(|s| pred(s))
+        .filter_map(|s| match *s {
+            InputStep::KeyDown(sc) | InputStep::KeyUp(sc) => Some(sc),
+            _ => None,
+        })
+        .collect()
+}
+
+/// How many times `unit` repeats back-to-back at the start of `steps`.
+fn repeats(steps: &[InputStep], unit: &[InputStep]) -> usize {
+    steps.chunks(unit.len()).take_while(|c| *c == unit).count()
+}
+
+fn fmt_chord(keys: &[Scan]) -> String {
+    let mut s = String::new();
+    for (i, &sc) in keys.iter().enumerate() {
+        if i > 0 {
+            s.push('+');
+        }
+        s.push_str(&fmt_key(sc));
+    }
+    s
+}
+
+fn fmt_key(sc: Scan) -> String {
+    match Key::from_windows_scan(sc) {
+        Some(k) => k.to_token().to_string(),
+        None if sc.extended => format!("sc:0xe0{:02x}", sc.code),
+        None => format!("sc:0x{:02x}", sc.code),
+    }
+}
+
+fn fmt_quoted(text: &str) -> String {
+    let mut s = String::with_capacity(text.len() + 2);
+    s.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            '\t' => s.push_str("\\t"),
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+    s
+}
+
+fn fmt_button(b: MouseButton) -> String {
+    match b {
+        MouseButton::Left => "left".into(),
+        MouseButton::Right => "right".into(),
+        MouseButton::Middle => "middle".into(),
+        MouseButton::X(n) => format!("x{n}"),
+    }
+}
+
+fn fmt_count(n: usize) -> String {
+    if n > 1 {
+        format!(" x{n}")
+    } else {
+        String::new()
+    }
+}
+
+fn fmt_duration(d: Duration) -> String {
+    if d.subsec_nanos() == 0 && d.as_secs() > 0 {
+        format!("{}s", d.as_secs())
+    } else if d.as_nanos().is_multiple_of(1_000_000) {
+        format!("{}ms", d.as_millis())
+    } else if d.as_nanos().is_multiple_of(1_000) {
+        format!("{}us", d.as_micros())
+    } else {
+        format!("{}ns", d.as_nanos())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn key(k: Key) -> Scan {
+        k.to_scan().unwrap()
+    }
+
+    fn taps(k: Key, n: usize) -> Vec<InputStep> {
+        (0..n)
+            .flat_map(|_| [InputStep::KeyDown(key(k)), InputStep::KeyUp(key(k))])
+            .collect()
+    }
+
+    fn round_trip(steps: &[InputStep]) -> String {
+        let text = to_script(steps);
+        let back = parse_script(&text).unwrap_or_else(|e| panic!("`{text}`: {e}"));
+        assert_eq!(back, steps, "`{text}`");
+        text
+    }
+
+    #[test]
+    fn repeated_arrow_taps_round_trip() {
+        assert_eq!(round_trip(&taps(Key::ArrowUp, 2)), "tap up x2");
+        assert_eq!(round_trip(&taps(Key::ArrowDown, 3)), "tap down x3");
+        assert_eq!(round_trip(&taps(Key::ArrowUp, 1)), "tap up");
+        assert_eq!(round_trip(&taps(Key::A, 2)), "a x2");
+    }
+
+    #[test]
+    fn lone_up_and_down_are_still_arrow_keys() {
+        assert_eq!(parse_script("up").unwrap(), taps(Key::ArrowUp, 1));
+        // without `tap`, the keyword wins
+        let err = parse_script("down x2").unwrap_err();
+        assert_eq!(err.kind, ScriptErrorKind::UnknownKey("x2".into()));
+        assert_eq!(
+            parse_script("down up, up up").unwrap(),
+            vec![
+                InputStep::KeyDown(key(Key::ArrowUp)),
+                InputStep::KeyUp(key(Key::ArrowUp))
+            ]
+        );
+    }
+
+    #[test]
+    fn sub_microsecond_sleeps_round_trip() {
+        for nanos in [1, 999, 1_500, 1_000_001, 2_000_000_500] {
+            round_trip(&[InputStep::Sleep(Duration::from_nanos(nanos))]);
+        }
+        assert_eq!(
+            round_trip(&[InputStep::Sleep(Duration::from_nanos(1_500))]),
+            "wait 1500ns"
+        );
+        assert_eq!(
+            round_trip(&[InputStep::Sleep(Duration::from_micros(1_500))]),
+            "wait 1500us"
+        );
+    }
+
+    #[test]
+    fn durations_parse_exactly() {
+        let d = |s: &str| parse_script(&format!("wait {s}")).unwrap();
+        assert_eq!(d("1500ns"), [InputStep::Sleep(Duration::from_nanos(1_500))]);
+        assert_eq!(d("3us"), [InputStep::Sleep(Duration::from_micros(3))]);
+        assert_eq!(d("50"), [InputStep::Sleep(Duration::from_millis(50))]);
+        assert_eq!(d("1.5s"), [InputStep::Sleep(Duration::from_millis(1_500))]);
+        assert!(parse_script("wait -1ms").is_err());
+    }
+
+    #[test]
+    fn mixed_script_round_trips() {
+        let steps = parse_script(
+            "ctrl+shift+s, wait 50ms, hold space 200ms, click left x2, \
+             type \"a, \\\"b\\\"\\n\", move 10 -5, moveto 1 2 normalized, \
+             scroll -3, scroll 0 2, down lshift, up lshift, sc:0xe05e",
+        )
+        .unwrap();
+        round_trip(&steps);
+    }
+
+    fn any_key() -> impl Strategy<Value = Scan> {
+        prop_oneof![
+            4 => prop::sample::select(
+                <Key as strum::IntoEnumIterator>::iter()
+                    .filter_map(Key::to_scan)
+                    .collect::<Vec<_>>()
+            ),
+            1 => (0u16..=0xff, any::<bool>()).prop_map(|(c, e)| Scan::new(c, e)),
+        ]
+    }
+
+    fn any_button() -> impl Strategy<Value = MouseButton> {
+        prop_oneof![
+            Just(MouseButton::Left),
+            Just(MouseButton::Right),
+            Just(MouseButton::Middle),
+            Just(MouseButton::X1),
+            Just(MouseButton::X2),
+        ]
+    }
+
+    fn any_step() -> impl Strategy<Value = InputStep> {
+        prop_oneof![
+            any_key().prop_map(InputStep::KeyDown),
+            any_key().prop_map(InputStep::KeyUp),
+            any_button().prop_map(InputStep::MouseDown),
+            any_button().prop_map(InputStep::MouseUp),
+            (0u64..5_000_000_000).prop_map(|n| InputStep::Sleep(Duration::from_nanos(n))),
+            any::<char>().prop_map(InputStep::Unicode),
+            (any::<i32>(), any::<i32>()).prop_map(|(dx, dy)| InputStep::MouseMove { dx, dy }),
+            (any::<i32>(), any::<i32>(), any::<bool>())
+                .prop_map(|(x, y, normalized)| InputStep::MouseMoveTo { x, y, normalized }),
+            (any::<i32>(), any::<i32>()).prop_map(|(vertical, horizontal)| {
+                InputStep::Scroll {
+                    vertical,
+                    horizontal,
+                }
+            }),
+        ]
+    }
+
+    /// Mostly taps and chords, which exercise the repeat/hold folding.
+    fn steps() -> impl Strategy<Value = Vec<InputStep>> {
+        let tap = (prop::collection::vec(any_key(), 1..3), 1usize..4).prop_map(|(keys, n)| {
+            let mut unit: Vec<_> = keys.iter().map(|&k| InputStep::KeyDown(k)).collect();
+            unit.extend(keys.iter().rev().map(|&k| InputStep::KeyUp(k)));
+            unit.repeat(n)
+        });
+        let chunk = prop_oneof![tap, any_step().prop_map(|s| vec![s])];
+        prop::collection::vec(chunk, 0..12).prop_map(|c| c.concat())
+    }
+
+    proptest! {
+        #[test]
+        fn parse_inverts_to_script(steps in steps()) {
+            let text = to_script(&steps);
+            prop_assert_eq!(parse_script(&text).ok(), Some(steps), "{}", text);
+        }
+    }
+}
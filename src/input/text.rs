@@ -0,0 +1,117 @@
+// src/input/text.rs
+//! Text → key presses for backends without native Unicode input.
+//! US-layout shorthands for [`Layout::Us`]; characters it cannot express are
+//! reported, not guessed.
+
+use super::key::Key;
+use super::layout::Layout;
+use super::types::InputStep;
+
+/// Key and shift state that produce `c` on a US keyboard.
+pub fn us_char_key(c: char) -> Option<(Key, bool)> {
+    // US has no AltGr level
+    Layout::Us.char_key(c).map(|k| (k.key, k.shift))
+}
+
+/// Key steps that type `c` on a US layout (shift wrapped around the tap if needed).
+pub fn us_char_steps(c: char) -> Option<Vec<InputStep>> {
+    Layout::Us.char_steps(c)
+}
+
+/// Replace `Unicode` steps with US-layout key presses; other steps pass through.
+/// Fails on the first character the table cannot type.
+pub fn expand_unicode_us(steps: &[InputStep]) -> Result<Vec<InputStep>, char> {
+    Layout::Us.expand_unicode(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::input::dsl::type_text;
+    use crate::input::{InputSynth, RecordingSynth, Scan};
+
+    fn scan(k: Key) -> Scan {
+        k.to_scan().unwrap()
+    }
+
+    #[test]
+    fn printable_ascii_is_covered_once() {
+        let mut seen: HashMap<(Key, bool), char> = HashMap::new();
+        for c in (' '..='~').chain(['\t', '\n']) {
+            let ks = us_char_key(c).unwrap_or_else(|| panic!("{c:?} not in the table"));
+            if let Some(other) = seen.insert(ks, c) {
+                panic!("{c:?} and {other:?} both map to {ks:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn table_spot_checks() {
+        assert_eq!(us_char_key('a'), Some((Key::A, false)));
+        assert_eq!(us_char_key('Z'), Some((Key::Z, true)));
+        assert_eq!(us_char_key('0'), Some((Key::D0, false)));
+        assert_eq!(us_char_key(')'), Some((Key::D0, true)));
+        assert_eq!(us_char_key('@'), Some((Key::D2, true)));
+        assert_eq!(us_char_key('"'), Some((Key::Apostrophe, true)));
+        assert_eq!(us_char_key('~'), Some((Key::Grave, true)));
+        assert_eq!(us_char_key('\n'), Some((Key::Enter, false)));
+    }
+
+    #[test]
+    fn characters_outside_us_ascii_are_rejected() {
+        for c in ['é', 'ß', '€', '£', 'Ж', '中', '😀', '\r', '\u{7f}'] {
+            assert_eq!(us_char_key(c), None, "{c:?}");
+            assert_eq!(us_char_steps(c), None, "{c:?}");
+        }
+    }
+
+    #[test]
+    fn shift_wraps_the_tap() {
+        assert_eq!(
+            us_char_steps('a').unwrap(),
+            vec![
+                InputStep::KeyDown(scan(Key::A)),
+                InputStep::KeyUp(scan(Key::A))
+            ]
+        );
+        assert_eq!(
+            us_char_steps('?').unwrap(),
+            vec![
+                InputStep::KeyDown(scan(Key::LShift)),
+                InputStep::KeyDown(scan(Key::Slash)),
+                InputStep::KeyUp(scan(Key::Slash)),
+                InputStep::KeyUp(scan(Key::LShift)),
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_replaces_only_unicode_steps() {
+        let sleep = InputStep::Sleep(std::time::Duration::from_millis(5));
+        let mut steps = type_text("Hi");
+        steps.insert(1, sleep);
+        let out = expand_unicode_us(&steps).unwrap();
+
+        let mut expected = us_char_steps('H').unwrap();
+        expected.push(sleep);
+        expected.extend(us_char_steps('i').unwrap());
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn expand_reports_the_first_untypeable_char() {
+        assert_eq!(expand_unicode_us(&type_text("ok €1 £2")), Err('€'));
+    }
+
+    #[test]
+    fn expanded_text_leaves_nothing_held() {
+        let synth = RecordingSynth::new();
+        let steps = expand_unicode_us(&type_text("Hello, World! ~_~\n")).unwrap();
+        synth.send_steps(steps).unwrap();
+        synth.assert_nothing_held();
+        assert_eq!(synth.presses(Key::LShift), 6);
+        assert!(synth.chord_issued(&[Key::LShift], Key::H));
+    }
+}
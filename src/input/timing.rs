@@ -0,0 +1,289 @@
+// src/input/timing.rs
+//! Humanized timing: the same steps, run with jittered sleeps, a minimum press
+//! duration and a delay between back-to-back events.
+//!
+//! Applied by `Executor` when a job starts (see `Job::timing`); the steps
+//! themselves stay unchanged. Give a seed for reproducible output.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::types::{InputStep, Scan};
+
+/// How to loosen up a macro's timing. All fields default to zero (no change).
+///
+/// Serialized with durations in whole milliseconds (`jitterMs`, `minPressMs`, `interKeyMs`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimingProfile {
+    /// Each sleep (explicit or inserted) moves by up to ± this much.
+    #[serde(rename = "jitterMs", with = "ms")]
+    pub jitter: Duration,
+    /// Keys stay down at least this long; key-ups wait if needed.
+    #[serde(rename = "minPressMs", with = "ms")]
+    pub min_press: Duration,
+    /// Pause inserted between two events that have no sleep between them.
+    #[serde(rename = "interKeyMs", with = "ms")]
+    pub inter_key: Duration,
+    /// Fixed seed: every run produces the same timing. `None` draws a fresh one per run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl TimingProfile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A reasonable default for games: ±15 ms, 30 ms presses, 20 ms between events.
+    pub fn natural() -> Self {
+        Self::new()
+            .jitter(Duration::from_millis(15))
+            .min_press(Duration::from_millis(30))
+            .inter_key(Duration::from_millis(20))
+    }
+
+    /// Sleep jitter (chainable).
+    pub fn jitter(mut self, d: Duration) -> Self {
+        self.jitter = d;
+        self
+    }
+
+    /// Minimum press duration (chainable).
+    pub fn min_press(mut self, d: Duration) -> Self {
+        self.min_press = d;
+        self
+    }
+
+    /// Inter-event delay (chainable).
+    pub fn inter_key(mut self, d: Duration) -> Self {
+        self.inter_key = d;
+        self
+    }
+
+    /// Fixed seed (chainable).
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// True if applying this profile changes nothing.
+    pub fn is_identity(&self) -> bool {
+        self.jitter.is_zero() && self.min_press.is_zero() && self.inter_key.is_zero()
+    }
+
+    /// Steps with this profile's timing applied. Deterministic when seeded.
+    pub fn apply(&self, steps: &[InputStep]) -> Vec<InputStep> {
+        if self.is_identity() {
+            return steps.to_vec();
+        }
+        let mut rng = SplitMix64::new(self.seed.unwrap_or_else(fresh_seed));
+        let mut out = Vec::with_capacity(steps.len() * 2);
+        // time since each held key went down, counted in sleeps
+        let mut pressed: Vec<(Scan, Duration)> = Vec::new();
+        // whether the last thing emitted was an event (so a pause may go before the next)
+        let mut after_event = false;
+
+        for step in steps {
+            if let InputStep::Sleep(d) = *step {
+                let d = self.jittered(d, &mut rng);
+                advance(&mut pressed, d);
+                out.push(InputStep::Sleep(d));
+                after_event = false;
+                continue;
+            }
+
+            let mut wait = Duration::ZERO;
+            if after_event && !self.inter_key.is_zero() {
+                wait = self.jittered(self.inter_key, &mut rng);
+            }
+            if let InputStep::KeyUp(s) = *step
+                && let Some(&(_, down_for)) = pressed.iter().find(|(k, _)| *k == s)
+                && down_for + wait < self.min_press
+            {
+                // top up to the minimum, then jitter only upwards so it stays a minimum
+                wait = self.min_press - down_for + self.upward(&mut rng);
+            }
+            if !wait.is_zero() {
+                advance(&mut pressed, wait);
+                out.push(InputStep::Sleep(wait));
+            }
+
+            match *step {
+                InputStep::KeyDown(s) if !pressed.iter().any(|(k, _)| *k == s) => {
+                    pressed.push((s, Duration::ZERO))
+                }
+                InputStep::KeyUp(s) => pressed.retain(|(k, _)| *k != s),
+                _ => {}
+            }
+            out.push(*step);
+            after_event = true;
+        }
+        out
+    }
+
+    /// `d` moved by up to ± `jitter`, never below zero.
+    fn jittered(&self, d: Duration, rng: &mut SplitMix64) -> Duration {
+        let j = self.jitter.as_nanos() as u64;
+        if j == 0 {
+            return d;
+        }
+        let offset = rng.next() % (2 * j + 1);
+        let nanos = (d.as_nanos() as u64 + offset).saturating_sub(j);
+        Duration::from_nanos(nanos)
+    }
+
+    /// Between zero and `jitter`.
+    fn upward(&self, rng: &mut SplitMix64) -> Duration {
+        let j = self.jitter.as_nanos() as u64;
+        if j == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_nanos(rng.next() % (j + 1))
+    }
+}
+
+mod ms {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(d: &Duration, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_u64(d.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+        u64::deserialize(d).map(Duration::from_millis)
+    }
+}
+
+fn advance(pressed: &mut [(Scan, Duration)], d: Duration) {
+    for (_, t) in pressed.iter_mut() {
+        *t += d;
+    }
+}
+
+fn fresh_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// Small, fast PRNG; plenty for timing noise.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::input::{Executor, Job, JobStatus, Key, RecordingSynth};
+
+    fn tap(k: Key) -> [InputStep; 2] {
+        [k.to_step_down().unwrap(), k.to_step_up().unwrap()]
+    }
+
+    fn sleeps(steps: &[InputStep]) -> Vec<Duration> {
+        steps
+            .iter()
+            .filter_map(|s| match *s {
+                InputStep::Sleep(d) => Some(d),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identity_profile_changes_nothing() {
+        let steps = tap(Key::A).to_vec();
+        assert_eq!(TimingProfile::new().seed(1).apply(&steps), steps);
+    }
+
+    #[test]
+    fn seeded_jitter_is_reproducible_and_bounded() {
+        let jitter = Duration::from_millis(10);
+        let base = Duration::from_millis(100);
+        let steps: Vec<_> = (0..50).map(|_| InputStep::Sleep(base)).collect();
+        let profile = TimingProfile::new().jitter(jitter).seed(42);
+
+        let a = profile.apply(&steps);
+        assert_eq!(a, profile.apply(&steps));
+        assert_ne!(a, profile.seed(43).apply(&steps));
+        for d in sleeps(&a) {
+            assert!(d >= base - jitter && d <= base + jitter, "{d:?}");
+        }
+        // noise, not a constant offset
+        assert!(sleeps(&a).windows(2).any(|w| w[0] != w[1]));
+    }
+
+    #[test]
+    fn min_press_and_inter_key_are_enforced() {
+        let min = Duration::from_millis(30);
+        let gap = Duration::from_millis(20);
+        let profile = TimingProfile::new().min_press(min).inter_key(gap).seed(7);
+        let mut steps = tap(Key::A).to_vec();
+        steps.extend(tap(Key::B));
+
+        let out = profile.apply(&steps);
+        let a = tap(Key::A);
+        let b = tap(Key::B);
+        assert_eq!(
+            out,
+            vec![
+                a[0],
+                InputStep::Sleep(min),
+                a[1],
+                InputStep::Sleep(gap),
+                b[0],
+                InputStep::Sleep(min),
+                b[1],
+            ]
+        );
+    }
+
+    #[test]
+    fn executor_applies_seeded_timing() {
+        let steps: Vec<_> = tap(Key::A)
+            .into_iter()
+            .chain([InputStep::Sleep(Duration::from_millis(40))])
+            .chain(tap(Key::B))
+            .collect();
+        let profile = TimingProfile::natural().seed(1234);
+
+        let synth = Arc::new(RecordingSynth::new());
+        let exec = Executor::new(Arc::clone(&synth));
+        let job = exec.submit(Job::new(steps.clone()).timing(profile));
+        assert_eq!(
+            job.wait_timeout(Duration::from_secs(5)),
+            Some(JobStatus::Completed)
+        );
+
+        let expected = profile.apply(&steps);
+        assert_eq!(synth.steps(), expected);
+        assert_eq!(synth.elapsed(), sleeps(&expected).iter().sum::<Duration>());
+        // keys stayed down long enough despite the jitter
+        let recorded = synth.recorded();
+        for key in [Key::A, Key::B] {
+            let (down, up) = (key.to_step_down().unwrap(), key.to_step_up().unwrap());
+            let at = |s| recorded.iter().find(|r| r.step == s).unwrap().at;
+            assert!(at(up) - at(down) >= profile.min_press, "{key:?}");
+        }
+        synth.assert_nothing_held();
+    }
+}
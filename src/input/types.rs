@@ -51,4 +51,24 @@ pub enum InputStep {
     MouseDown(MouseButton),
     MouseUp(MouseButton),
     Sleep(Duration),
+    /// Type one character independent of the keyboard layout.
+    /// Backends without native Unicode input fall back to a US key table.
+    Unicode(char),
+    /// Move the cursor relative to where it is (pixels, subject to pointer acceleration).
+    MouseMove {
+        dx: i32,
+        dy: i32,
+    },
+    /// Move the cursor to an absolute position: primary-screen pixels, or
+    /// `0..=65535` across the whole desktop when `normalized`.
+    MouseMoveTo {
+        x: i32,
+        y: i32,
+        normalized: bool,
+    },
+    /// Scroll by wheel notches; positive is up / right.
+    Scroll {
+        vertical: i32,
+        horizontal: i32,
+    },
 }
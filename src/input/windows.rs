@@ -2,8 +2,11 @@ use std::mem::size_of;
 use std::thread;
 
 use windows::Win32::UI::Input::KeyboardAndMouse::*;
+use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN};
 
 use super::InputSynth;
+use super::key::Key;
+use super::text;
 use super::types::{InputStep, MouseButton, Scan};
 
 pub struct WinSynth;
@@ -50,6 +53,15 @@ impl WinSynth {
                     flush(&mut buf)?;
                     thread::sleep(dur);
                 }
+                InputStep::Unicode(c) => buf.extend(build_unicode(c)),
+                InputStep::MouseMove { dx, dy } => buf.push(build_move(dx, dy)),
+                InputStep::MouseMoveTo { x, y, normalized } => {
+                    buf.push(build_move_to(x, y, normalized))
+                }
+                InputStep::Scroll {
+                    vertical,
+                    horizontal,
+                } => buf.extend(build_scroll(vertical, horizontal)),
             }
         }
 
@@ -69,9 +81,38 @@ impl InputSynth for WinSynth {
                 thread::sleep(d);
                 Ok(())
             }
+            InputStep::Unicode(c) => send_many(&build_unicode(c)),
+            InputStep::MouseMove { dx, dy } => send_one(build_move(dx, dy)),
+            InputStep::MouseMoveTo { x, y, normalized } => {
+                send_one(build_move_to(x, y, normalized))
+            }
+            InputStep::Scroll {
+                vertical,
+                horizontal,
+            } => send_many(&build_scroll(vertical, horizontal)),
         }
     }
 
+    fn modifier_state(&self) -> Option<Vec<Key>> {
+        const MODS: [(VIRTUAL_KEY, Key); 8] = [
+            (VK_LSHIFT, Key::LShift),
+            (VK_RSHIFT, Key::RShift),
+            (VK_LCONTROL, Key::LCtrl),
+            (VK_RCONTROL, Key::RCtrl),
+            (VK_LMENU, Key::LAlt),
+            (VK_RMENU, Key::RAlt),
+            (VK_LWIN, Key::LWin),
+            (VK_RWIN, Key::RWin),
+        ];
+        // high bit = down right now
+        let held = MODS
+            .iter()
+            .filter(|(vk, _)| unsafe { GetAsyncKeyState(vk.0 as i32) } < 0)
+            .map(|(_, k)| *k)
+            .collect();
+        Some(held)
+    }
+
     /// Override the default to batch efficiently.
     fn send_steps<I>(&self, steps: I) -> Result<(), String>
     where
@@ -111,9 +152,15 @@ fn mouse_data(btn: MouseButton) -> u32 {
     }
 }
 
-#[inline]
+/// Keys go by scancode, except media/browser/launch keys: Windows only acts on
+/// those by virtual-key code.
 fn build_key(s: Scan, down: bool) -> INPUT {
-    let mut flags = KEYEVENTF_SCANCODE;
+    let vk = media_vk(s);
+    let mut flags = if vk.is_some() {
+        KEYBD_EVENT_FLAGS(0)
+    } else {
+        KEYEVENTF_SCANCODE
+    };
     if s.extended {
         flags |= KEYEVENTF_EXTENDEDKEY;
     }
@@ -125,7 +172,7 @@ fn build_key(s: Scan, down: bool) -> INPUT {
         r#type: INPUT_KEYBOARD,
         Anonymous: INPUT_0 {
             ki: KEYBDINPUT {
-                wVk: VIRTUAL_KEY(0),
+                wVk: VIRTUAL_KEY(vk.unwrap_or(0)),
                 wScan: s.code,
                 dwFlags: flags,
                 time: 0,
@@ -135,14 +182,114 @@ fn build_key(s: Scan, down: bool) -> INPUT {
     }
 }
 
+#[inline]
+fn media_vk(s: Scan) -> Option<u16> {
+    if !s.extended {
+        return None;
+    }
+    Key::from_windows_scan(s).and_then(Key::windows_media_vk)
+}
+
+/// Down/up events for `c` via KEYEVENTF_UNICODE (UTF-16, so two units outside the BMP).
+/// Control characters go through real keys; apps ignore them as Unicode input.
+fn build_unicode(c: char) -> Vec<INPUT> {
+    if c.is_control()
+        && let Some(steps) = text::us_char_steps(c)
+    {
+        return steps
+            .into_iter()
+            .filter_map(|s| match s {
+                InputStep::KeyDown(sc) => Some(build_key(sc, true)),
+                InputStep::KeyUp(sc) => Some(build_key(sc, false)),
+                _ => None,
+            })
+            .collect();
+    }
+
+    let mut units = [0u16; 2];
+    let units = c.encode_utf16(&mut units);
+    let mut v = Vec::with_capacity(units.len() * 2);
+    for &u in units.iter() {
+        v.push(build_unicode_unit(u, true));
+    }
+    for &u in units.iter() {
+        v.push(build_unicode_unit(u, false));
+    }
+    v
+}
+
+#[inline]
+fn build_unicode_unit(unit: u16, down: bool) -> INPUT {
+    let mut flags = KEYEVENTF_UNICODE;
+    if !down {
+        flags |= KEYEVENTF_KEYUP;
+    }
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: VIRTUAL_KEY(0),
+                wScan: unit,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    }
+}
+
 #[inline]
 fn build_mouse(flags: MOUSE_EVENT_FLAGS, data: u32) -> INPUT {
+    build_mouse_at(flags, 0, 0, data)
+}
+
+#[inline]
+fn build_move(dx: i32, dy: i32) -> INPUT {
+    build_mouse_at(MOUSEEVENTF_MOVE, dx, dy, 0)
+}
+
+/// Absolute moves use 0..=65535 coordinates; pixels are scaled to the primary screen.
+fn build_move_to(x: i32, y: i32, normalized: bool) -> INPUT {
+    if normalized {
+        let flags = MOUSEEVENTF_MOVE | MOUSEEVENTF_ABSOLUTE | MOUSEEVENTF_VIRTUALDESK;
+        return build_mouse_at(flags, x, y, 0);
+    }
+    let (w, h) = unsafe { (GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN)) };
+    let scale = |v: i32, size: i32| -> i32 {
+        let max = (size - 1).max(1) as i64;
+        ((v.clamp(0, max as i32) as i64 * 65535) / max) as i32
+    };
+    build_mouse_at(
+        MOUSEEVENTF_MOVE | MOUSEEVENTF_ABSOLUTE,
+        scale(x, w),
+        scale(y, h),
+        0,
+    )
+}
+
+/// One wheel event per axis; notches are multiples of WHEEL_DELTA (120).
+fn build_scroll(vertical: i32, horizontal: i32) -> Vec<INPUT> {
+    const WHEEL_DELTA: i32 = 120;
+    let mut v = Vec::with_capacity(2);
+    if vertical != 0 {
+        let data = vertical.saturating_mul(WHEEL_DELTA) as u32;
+        v.push(build_mouse(MOUSEEVENTF_WHEEL, data));
+    }
+    if horizontal != 0 {
+        let data = horizontal.saturating_mul(WHEEL_DELTA) as u32;
+        v.push(build_mouse(MOUSEEVENTF_HWHEEL, data));
+    }
+    v
+}
+
+#[inline]
+fn build_mouse_at(flags: MOUSE_EVENT_FLAGS, dx: i32, dy: i32, data: u32) -> INPUT {
     INPUT {
         r#type: INPUT_MOUSE,
         Anonymous: INPUT_0 {
             mi: MOUSEINPUT {
-                dx: 0,
-                dy: 0,
+                dx,
+                dy,
                 mouseData: data,
                 dwFlags: flags,
                 time: 0,
@@ -152,6 +299,18 @@ fn build_mouse(flags: MOUSE_EVENT_FLAGS, data: u32) -> INPUT {
     }
 }
 
+fn send_many(inputs: &[INPUT]) -> Result<(), String> {
+    if inputs.is_empty() {
+        return Ok(());
+    }
+    let n = unsafe { SendInput(inputs, size_of::<INPUT>() as i32) };
+    if n as usize != inputs.len() {
+        Err("SendInput failed".into())
+    } else {
+        Ok(())
+    }
+}
+
 #[inline]
 fn send_one(input: INPUT) -> Result<(), String> {
     let n = unsafe { SendInput(&[input], size_of::<INPUT>() as i32) };
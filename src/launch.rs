@@ -90,8 +90,12 @@ pub fn ws_url(port: u16) -> String {
 ///
 /// Usage in your `main`:
 /// ```no_run
-/// let _guard = your_crate::telemetry::init("your_plugin_id");
-/// your_crate::launch::run_plugin(build_plugin())?;
+/// # fn build_plugin() -> streamdeck_lib::Plugin { streamdeck_lib::Plugin::new() }
+/// # fn main() -> anyhow::Result<()> {
+/// let _guard = streamdeck_lib::init("your_plugin_id");
+/// streamdeck_lib::run_plugin(build_plugin())?;
+/// # Ok(())
+/// # }
 /// ```
 pub fn run_plugin(plugin: crate::plugin::Plugin) -> anyhow::Result<()> {
     let args = parse_launch_args()?;
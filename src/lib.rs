@@ -11,9 +11,14 @@ mod hooks;
 pub mod input;
 mod launch;
 mod logger;
+mod pi;
 mod plugin;
+mod recorder;
+mod replay;
 mod runtime;
 mod sd_protocol; // maybe this one stays public if it has submodules users need
+mod title;
+mod typed_globals;
 
 // Public surface (root-level re-exports)
 pub use crate::actions::{Action, ActionFactory, ActionId, ActionStatic};
@@ -21,24 +26,46 @@ pub use crate::adapters::{
     Adapter, AdapterError, AdapterHandle, AdapterResult, AdapterStatic, StartPolicy,
 };
 pub use crate::bus::{Bus, BusTyped};
-pub use crate::context::{Context, Extensions, GlobalSettings};
+pub use crate::context::{
+    Context, Extensions, GlobalSettings, GlobalsChange, GlobalsConflict, GlobalsDiff, WatchId,
+    WriteBehind,
+};
 pub use crate::events::{ActionTarget, AdapterControl, AdapterTarget, ErasedTopic, TopicId};
 pub use crate::hooks::{AppHooks, HookEvent, HookFn};
+#[cfg(target_os = "linux")]
+pub use crate::input::LinuxSynth;
 pub use crate::input::dsl::{
-    chord, click, click_n, down, hold, sleep, sleep_ms, tap, tap_with_delay, up,
+    chord, chord_for, chord_from_state, click, click_n, down, hold, move_by, move_to,
+    move_to_normalized, scroll, sleep, sleep_ms, tap, tap_with_delay, type_text, up,
 };
 pub use crate::input::key::Key;
+pub use crate::input::layout::{Layout, LayoutKey};
+pub use crate::input::script::{ScriptError, ScriptErrorKind, parse_script, to_script};
 pub use crate::input::types::{InputStep, MouseButton, Scan};
-pub use crate::input::{Executor, InputSynth};
+pub use crate::input::{
+    Binding, BindingConflict, BindingError, BindingMap, Executor, InputSynth, Job, JobHandle,
+    JobId, JobPriority, JobStatus, Macro, MacroController, MacroError, MacroMode, RecordedStep,
+    RecordingSynth, TimingProfile,
+};
 pub use crate::launch::run_plugin;
 pub use crate::launch::{LaunchArgError, LaunchArgs, parse_from, parse_launch_args};
 pub use crate::logger::{init, init_with};
+pub use crate::pi::{
+    ERR_DECODE, PI_VISIBILITY, PiAction, PiError, PiInfo, PiQueuePolicy, PiReply, PiVisibility,
+    decode_from_pi, encode_error_for_pi, encode_for_pi,
+};
 pub use crate::plugin::Plugin;
+pub use crate::recorder::{RECORD_ENV, REDACTED, RecordEntry, RecordRedactor, read_recording};
+pub use crate::replay::{ReplayMismatch, ReplayReport, replay, replay_file};
 pub use crate::runtime::run_with_defaults;
 pub use crate::sd_protocol::{
-    Coordinates, DeviceInfo, SdClient, SdState, SetImagePayload, SetTitlePayload, Size,
-    StreamDeckEvent, Target, TitleParameters, TriggerPayload,
+    Coordinates, DeviceInfo, FontStyle, Outgoing, ParseColorError, Rgb, SdClient, SdState,
+    SetImagePayload, SetTitlePayload, Size, StreamDeckEvent, SwitchToProfilePayload, Target,
+    TitleAlignment, TitleColor, TitleParameters, TriggerPayload, parse_incoming,
+    parse_incoming_owned, parse_outgoing, serialize_incoming, serialize_outgoing,
 };
+pub use crate::title::{KEY_PX_1X, KEY_PX_2X, TitleBox, TitleOverflow, fit_title, fit_title_in};
+pub use crate::typed_globals::{GlobalsSchema, MigrationFn, TypedGlobals, VersionedSettings};
 
 pub mod prelude {
     pub use crate::actions::{Action, ActionFactory, ActionStatic};
@@ -46,20 +73,25 @@ pub mod prelude {
         Adapter, AdapterError, AdapterHandle, AdapterResult, AdapterStatic, StartPolicy,
     };
     pub use crate::bus::{Bus, BusTyped};
-    pub use crate::context::{Context, Extensions, GlobalSettings};
+    pub use crate::context::{Context, Extensions, GlobalSettings, GlobalsChange, GlobalsDiff};
     pub use crate::events::{ErasedTopic, TopicId};
     pub use crate::hooks::{AppHooks, HookEvent};
     pub use crate::input::InputSynth;
     pub use crate::input::dsl::{
-        chord, click, click_n, down, hold, sleep, sleep_ms, tap, tap_with_delay, up,
+        chord, chord_for, chord_from_state, click, click_n, down, hold, move_by, move_to,
+        move_to_normalized, scroll, sleep, sleep_ms, tap, tap_with_delay, type_text, up,
     };
     pub use crate::input::key::Key;
+    pub use crate::input::layout::Layout;
     pub use crate::input::types::{InputStep, MouseButton, Scan};
     pub use crate::launch::run_plugin;
     pub use crate::launch::{LaunchArgError, parse_launch_args};
     pub use crate::logger::{init, init_with};
+    pub use crate::pi::{PI_VISIBILITY, PiAction, PiInfo, PiQueuePolicy, PiReply};
     pub use crate::plugin::Plugin;
     pub use crate::runtime::run_with_defaults;
     pub use crate::sd_protocol::{SdClient, SdState, StreamDeckEvent, Target, views::*};
     pub use crate::simple_action_factory;
+    pub use crate::title::TitleOverflow;
+    pub use crate::typed_globals::{GlobalsSchema, VersionedSettings};
 }
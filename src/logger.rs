@@ -1,7 +1,11 @@
 use chrono::TimeZone;
 // src/telemetry.rs
 use directories::BaseDirs;
-use std::{fs, io, path::PathBuf};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
 use tracing_appender::{non_blocking, non_blocking::WorkerGuard};
 use tracing_subscriber::fmt::format::Writer;
 use tracing_subscriber::fmt::time::FormatTime;
@@ -9,14 +13,27 @@ use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberI
 
 const DEFAULT_KEEP_RUNS: usize = 4;
 
-fn logs_dir(plugin_id: &str) -> io::Result<PathBuf> {
+/// Directory the first `init` call logs to.
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+pub(crate) fn logs_dir(plugin_id: &str) -> io::Result<PathBuf> {
     let base = BaseDirs::new().ok_or_else(|| io::Error::other("no home dir"))?;
     let dir = base.data_dir().join(plugin_id);
     fs::create_dir_all(&dir)?;
     Ok(dir)
 }
 
-fn run_log_path(dir: &PathBuf, prefix: &str) -> PathBuf {
+/// Where `init` put this process's logs; `None` before it ran.
+pub(crate) fn current_logs_dir() -> Option<&'static Path> {
+    LOG_DIR.get().map(PathBuf::as_path)
+}
+
+fn run_log_path(dir: &Path, prefix: &str) -> PathBuf {
+    run_file_path(dir, prefix, "log")
+}
+
+/// `<dir>/<prefix>-YYYYMMDD-HHMMSS-PID.<ext>`
+pub(crate) fn run_file_path(dir: &Path, prefix: &str, ext: &str) -> PathBuf {
     // Use local time; keep it simple and avoid extra deps for the filename.
     // YYYYMMDD-HHMMSS-PID
     let now = std::time::SystemTime::now()
@@ -29,26 +46,29 @@ fn run_log_path(dir: &PathBuf, prefix: &str) -> PathBuf {
         .map(|dt| dt.format("%Y%m%d-%H%M%S").to_string())
         .unwrap_or_else(|| now.to_string());
     let pid = std::process::id();
-    dir.join(format!("{prefix}-{stamp}-{pid}.log"))
+    dir.join(format!("{prefix}-{stamp}-{pid}.{ext}"))
+}
+
+fn cleanup_old_runs(dir: &Path, prefix: &str, keep: usize) {
+    cleanup_old_files(dir, prefix, "log", keep);
 }
 
-fn cleanup_old_runs(dir: &PathBuf, prefix: &str, keep: usize) {
-    // Delete oldest files matching "<prefix>-*.log", keep newest `keep`
+/// Delete the oldest files matching `<prefix>-*.<ext>` in `dir`, keeping the newest `keep`.
+pub(crate) fn cleanup_old_files(dir: &Path, prefix: &str, ext: &str, keep: usize) {
     let mut entries: Vec<(std::time::SystemTime, PathBuf)> = Vec::new();
     if let Ok(read) = fs::read_dir(dir) {
         for e in read.flatten() {
             let p = e.path();
             if let (Some(name), true) = (
                 p.file_name().and_then(|s| s.to_str()),
-                p.extension().map(|e| e == "log").unwrap_or(false),
-            ) {
-                if name.starts_with(prefix) {
-                    let mtime = e
-                        .metadata()
-                        .and_then(|m| m.modified())
-                        .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
-                    entries.push((mtime, p));
-                }
+                p.extension().map(|e| e == ext).unwrap_or(false),
+            ) && name.starts_with(prefix)
+            {
+                let mtime = e
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+                entries.push((mtime, p));
             }
         }
     }
@@ -73,6 +93,7 @@ pub fn init(plugin_id: &str) -> WorkerGuard {
 pub fn init_with(plugin_id: &str, file_prefix: &str, keep_runs: usize) -> WorkerGuard {
     let dir = logs_dir(plugin_id).expect("failed to create logs dir");
     cleanup_old_runs(&dir, file_prefix, keep_runs);
+    let _ = LOG_DIR.set(dir.clone());
 
     let file = run_log_path(&dir, file_prefix);
     let file = fs::OpenOptions::new()
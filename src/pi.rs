@@ -0,0 +1,795 @@
+// pi.rs
+//! Typed property inspector (PI) channel.
+//!
+//! Actions that implement [`PiAction`] declare a message enum for each direction.
+//! The runtime decodes `sendToPlugin` payloads into `PiAction::In` and hands them
+//! to `on_pi_message` together with a [`PiReply`] used to answer.
+//!
+//! Wire shape (both directions) is the serialized message object plus an optional
+//! `requestId`. The PI sets `requestId` when it expects an answer; `PiReply::reply`
+//! echoes it back. Decode failures are answered with
+//! `{ "requestId": .., "error": { "code": "decode", "message": .. } }`.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    marker::PhantomData,
+    sync::{Arc, RwLock},
+};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+use tracing::{debug, error, warn};
+
+use crate::{
+    actions::Action,
+    context::Context,
+    events::{ErasedTopic, TopicId},
+    sd_protocol::{StreamDeckEvent, views::*},
+};
+
+/// Key carrying the request id in both directions.
+pub const REQUEST_ID_KEY: &str = "requestId";
+/// Key carrying a structured error sent to the PI.
+pub const ERROR_KEY: &str = "error";
+/// Key used to wrap non-object messages.
+pub const PAYLOAD_KEY: &str = "payload";
+
+/// Error code used when a PI message can't be decoded into `PiAction::In`.
+pub const ERR_DECODE: &str = "decode";
+
+/// Structured error reported back to the PI.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PiError {
+    pub code: String,
+    pub message: String,
+}
+
+impl PiError {
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Encode a plugin → PI message, attaching `request_id` when present.
+///
+/// Objects get `requestId` merged in; anything else is wrapped as `{ "payload": .. }`.
+pub fn encode_for_pi<T: Serialize>(
+    msg: &T,
+    request_id: Option<&Value>,
+) -> serde_json::Result<Value> {
+    let mut obj = match serde_json::to_value(msg)? {
+        Value::Object(m) => m,
+        other => {
+            let mut m = Map::new();
+            m.insert(PAYLOAD_KEY.to_string(), other);
+            m
+        }
+    };
+    if let Some(id) = request_id {
+        obj.insert(REQUEST_ID_KEY.to_string(), id.clone());
+    }
+    Ok(Value::Object(obj))
+}
+
+/// Encode a structured error for the PI.
+pub fn encode_error_for_pi(err: &PiError, request_id: Option<&Value>) -> Value {
+    let mut obj = Map::new();
+    if let Some(id) = request_id {
+        obj.insert(REQUEST_ID_KEY.to_string(), id.clone());
+    }
+    obj.insert(
+        ERROR_KEY.to_string(),
+        serde_json::to_value(err).unwrap_or(Value::Null),
+    );
+    Value::Object(obj)
+}
+
+/// Split a PI → plugin payload into its request id and the decoded message.
+pub fn decode_from_pi<T: DeserializeOwned>(
+    payload: &Map<String, Value>,
+) -> (Option<Value>, serde_json::Result<T>) {
+    let mut body = payload.clone();
+    let request_id = body.remove(REQUEST_ID_KEY);
+    (request_id, serde_json::from_value(Value::Object(body)))
+}
+
+/// Answer handle for a single PI message.
+pub struct PiReply<'a, Out> {
+    cx: &'a Context,
+    context: &'a str,
+    request_id: Option<Value>,
+    _pd: PhantomData<fn(&Out)>,
+}
+
+impl<'a, Out: Serialize> PiReply<'a, Out> {
+    pub(crate) fn new(cx: &'a Context, context: &'a str, request_id: Option<Value>) -> Self {
+        Self {
+            cx,
+            context,
+            request_id,
+            _pd: PhantomData,
+        }
+    }
+
+    /// Action context (instance id) of the PI that sent the message.
+    pub fn context(&self) -> &str {
+        self.context
+    }
+
+    /// Request id set by the PI, if it expects an answer.
+    pub fn request_id(&self) -> Option<&Value> {
+        self.request_id.as_ref()
+    }
+
+    /// Answer the request (echoes `requestId`).
+    pub fn reply(&self, msg: &Out) {
+        self.send_encoded(encode_for_pi(msg, self.request_id.as_ref()));
+    }
+
+    /// Push an unsolicited message (no `requestId`).
+    pub fn push(&self, msg: &Out) {
+        self.send_encoded(encode_for_pi(msg, None));
+    }
+
+    /// Answer the request with a structured error.
+    pub fn error(&self, code: impl Into<String>, message: impl Into<String>) {
+        let err = PiError::new(code, message);
+        self.cx.sd().send_to_property_inspector(
+            self.context,
+            encode_error_for_pi(&err, self.request_id.as_ref()),
+        );
+    }
+
+    fn send_encoded(&self, v: serde_json::Result<Value>) {
+        match v {
+            Ok(v) => self.cx.sd().send_to_property_inspector(self.context, v),
+            Err(e) => warn!("PI: failed to encode message for {}: {}", self.context, e),
+        }
+    }
+}
+
+/// Typed PI messaging for an action. Register with `ActionFactory::pi_*`.
+pub trait PiAction: Action {
+    /// PI → plugin messages (typically a `#[serde(tag = "type")]` enum).
+    type In: DeserializeOwned;
+    /// Plugin → PI messages.
+    type Out: Serialize;
+
+    fn on_pi_message(&mut self, cx: &Context, pi: &PiReply<'_, Self::Out>, msg: Self::In);
+
+    /// Called after a decode failure was reported to the PI.
+    fn on_pi_decode_error(
+        &mut self,
+        _cx: &Context,
+        _ev: &DidReceivePropertyInspectorMessage,
+        _err: &serde_json::Error,
+    ) {
+    }
+}
+
+/// Wraps a `PiAction` so the runtime routes decoded PI messages to it.
+/// Everything else is forwarded untouched.
+pub(crate) struct PiRouted<A>(pub(crate) A);
+
+impl<A: PiAction> Action for PiRouted<A> {
+    fn id(&self) -> &str {
+        self.0.id()
+    }
+    fn topics(&self) -> &'static [&'static str] {
+        self.0.topics()
+    }
+    fn init(&mut self, cx: &Context, ctx_id: &str) {
+        self.0.init(cx, ctx_id)
+    }
+    fn teardown(&mut self, cx: &Context, ctx_id: &str) {
+        self.0.teardown(cx, ctx_id)
+    }
+    fn will_appear(&mut self, cx: &Context, ev: &WillAppear) {
+        self.0.will_appear(cx, ev)
+    }
+    fn will_disappear(&mut self, cx: &Context, ev: &WillDisappear) {
+        self.0.will_disappear(cx, ev)
+    }
+    fn key_down(&mut self, cx: &Context, ev: &KeyDown) {
+        self.0.key_down(cx, ev)
+    }
+    fn key_up(&mut self, cx: &Context, ev: &KeyUp) {
+        self.0.key_up(cx, ev)
+    }
+    fn dial_down(&mut self, cx: &Context, ev: &DialDown) {
+        self.0.dial_down(cx, ev)
+    }
+    fn dial_up(&mut self, cx: &Context, ev: &DialUp) {
+        self.0.dial_up(cx, ev)
+    }
+    fn dial_rotate(&mut self, cx: &Context, ev: &DialRotate) {
+        self.0.dial_rotate(cx, ev)
+    }
+    fn touch_tap(&mut self, cx: &Context, ev: &TouchTap) {
+        self.0.touch_tap(cx, ev)
+    }
+    fn title_parameters_did_change(&mut self, cx: &Context, ev: &TitleParametersDidChange) {
+        self.0.title_parameters_did_change(cx, ev)
+    }
+    fn property_inspector_did_appear(&mut self, cx: &Context, ev: &PropertyInspectorDidAppear) {
+        self.0.property_inspector_did_appear(cx, ev)
+    }
+    fn property_inspector_did_disappear(
+        &mut self,
+        cx: &Context,
+        ev: &PropertyInspectorDidDisappear,
+    ) {
+        self.0.property_inspector_did_disappear(cx, ev)
+    }
+    fn did_receive_settings(&mut self, cx: &Context, ev: &DidReceiveSettings) {
+        self.0.did_receive_settings(cx, ev)
+    }
+    fn did_receive_resources(&mut self, cx: &Context, ev: &DidReceiveResources) {
+        self.0.did_receive_resources(cx, ev)
+    }
+
+    fn did_receive_property_inspector_message(
+        &mut self,
+        cx: &Context,
+        ev: &DidReceivePropertyInspectorMessage,
+    ) {
+        let (request_id, decoded) = decode_from_pi::<A::In>(ev.payload);
+        let pi = PiReply::<A::Out>::new(cx, ev.context, request_id);
+        match decoded {
+            Ok(msg) => self.0.on_pi_message(cx, &pi, msg),
+            Err(e) => {
+                warn!(
+                    "PI: undecodable message for {} ({}): {}",
+                    ev.action, ev.context, e
+                );
+                pi.error(ERR_DECODE, e.to_string());
+                self.0.on_pi_decode_error(cx, ev, &e);
+            }
+        }
+    }
+
+    fn on_global_event(&mut self, cx: &Context, ev: &StreamDeckEvent) {
+        self.0.on_global_event(cx, ev)
+    }
+    fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
+        self.0.on_notify(cx, ctx_id, event)
+    }
+}
+
+// ======================
+// PI visibility
+// ======================
+
+/// Identifies an open property inspector.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PiInfo {
+    pub action: String,
+    pub context: String,
+    pub device: String,
+}
+
+/// Published on [`PI_VISIBILITY`] whenever a PI opens or closes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiVisibility {
+    pub info: PiInfo,
+    pub open: bool,
+}
+
+/// Built-in topic: property inspector opened/closed.
+pub const PI_VISIBILITY: TopicId<PiVisibility> = TopicId::new("streamdeck.pi.visibility");
+
+/// What to do with `sendToPropertyInspector` while that context's PI is closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PiQueuePolicy {
+    /// Send anyway (Stream Deck discards it if nothing listens).
+    #[default]
+    Send,
+    /// Drop the message.
+    Drop,
+    /// Keep up to `max` messages per context and send them once the PI opens.
+    /// Oldest messages are dropped first.
+    ReplayOnOpen { max: usize },
+}
+
+/// Shared view of which property inspectors are open. Written by the runtime only.
+#[derive(Clone, Default)]
+pub(crate) struct PiTracker {
+    // in open order; the last one is the "current" PI
+    open: Arc<RwLock<Vec<PiInfo>>>,
+}
+
+impl PiTracker {
+    pub(crate) fn opened(&self, info: PiInfo) {
+        match self.open.write() {
+            Ok(mut w) => {
+                w.retain(|p| p.context != info.context);
+                w.push(info);
+            }
+            Err(_) => error!("PiTracker: write lock poisoned; PI state not updated"),
+        }
+    }
+
+    /// Returns the closed PI, if it was tracked as open.
+    pub(crate) fn closed(&self, context: &str) -> Option<PiInfo> {
+        match self.open.write() {
+            Ok(mut w) => {
+                let idx = w.iter().position(|p| p.context == context)?;
+                Some(w.remove(idx))
+            }
+            Err(_) => {
+                error!("PiTracker: write lock poisoned; PI state not updated");
+                None
+            }
+        }
+    }
+
+    pub(crate) fn is_open(&self, context: &str) -> bool {
+        self.open
+            .read()
+            .map(|r| r.iter().any(|p| p.context == context))
+            .unwrap_or(false)
+    }
+
+    pub(crate) fn current(&self) -> Option<PiInfo> {
+        self.open.read().ok().and_then(|r| r.last().cloned())
+    }
+
+    pub(crate) fn all(&self) -> Vec<PiInfo> {
+        self.open.read().map(|r| r.clone()).unwrap_or_default()
+    }
+}
+
+/// Runtime-side buffer for PI messages held back by [`PiQueuePolicy`].
+#[derive(Default)]
+pub(crate) struct PiOutbox {
+    held: HashMap<String, VecDeque<Value>>,
+}
+
+impl PiOutbox {
+    /// Apply `policy` to a message for a closed PI.
+    /// Returns the message back if it should be sent right away.
+    pub(crate) fn hold(
+        &mut self,
+        policy: PiQueuePolicy,
+        context: &str,
+        payload: Value,
+    ) -> Option<Value> {
+        match policy {
+            PiQueuePolicy::Send => Some(payload),
+            PiQueuePolicy::Drop => {
+                debug!("PI closed for {}; dropping message", context);
+                None
+            }
+            PiQueuePolicy::ReplayOnOpen { max } => {
+                if max == 0 {
+                    return None;
+                }
+                let q = self.held.entry(context.to_string()).or_default();
+                while q.len() >= max {
+                    q.pop_front();
+                }
+                q.push_back(payload);
+                None
+            }
+        }
+    }
+
+    /// Take everything held for `context` (in send order).
+    pub(crate) fn take(&mut self, context: &str) -> VecDeque<Value> {
+        self.held.remove(context).unwrap_or_default()
+    }
+
+    /// Discard everything held for `context` (instance went away).
+    pub(crate) fn forget(&mut self, context: &str) {
+        self.held.remove(context);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::actions::ActionFactory;
+    use crate::plugin::Plugin;
+    use crate::recorder::RecordEntry;
+    use crate::replay::replay;
+    use crate::sd_protocol::{Outgoing, parse_incoming};
+
+    fn info(context: &str) -> PiInfo {
+        PiInfo {
+            action: "com.example.pi".into(),
+            context: context.into(),
+            device: "dev".into(),
+        }
+    }
+
+    #[test]
+    fn tracker_follows_open_and_close() {
+        let pis = PiTracker::default();
+        assert_eq!(pis.current(), None);
+
+        pis.opened(info("a"));
+        pis.opened(info("b"));
+        assert!(pis.is_open("a") && pis.is_open("b"));
+        assert_eq!(pis.current(), Some(info("b")));
+        assert_eq!(pis.all(), vec![info("a"), info("b")]);
+
+        // reopening moves it to the end instead of duplicating it
+        pis.opened(info("a"));
+        assert_eq!(pis.all(), vec![info("b"), info("a")]);
+
+        assert_eq!(pis.closed("a"), Some(info("a")));
+        assert_eq!(pis.closed("a"), None);
+        assert!(!pis.is_open("a"));
+        assert_eq!(pis.current(), Some(info("b")));
+    }
+
+    #[test]
+    fn outbox_send_and_drop_hold_nothing() {
+        let mut out = PiOutbox::default();
+        assert_eq!(out.hold(PiQueuePolicy::Send, "a", json!(1)), Some(json!(1)));
+        assert_eq!(out.hold(PiQueuePolicy::Drop, "a", json!(2)), None);
+        assert!(out.take("a").is_empty());
+    }
+
+    #[test]
+    fn outbox_replay_keeps_the_newest_max_in_order() {
+        let mut out = PiOutbox::default();
+        let policy = PiQueuePolicy::ReplayOnOpen { max: 2 };
+        for n in 1..=3 {
+            assert_eq!(out.hold(policy, "a", json!(n)), None);
+        }
+        out.hold(policy, "b", json!("other"));
+        assert_eq!(Vec::from(out.take("a")), vec![json!(2), json!(3)]);
+        assert!(out.take("a").is_empty());
+
+        out.forget("b");
+        assert!(out.take("b").is_empty());
+        // max 0 keeps nothing
+        out.hold(PiQueuePolicy::ReplayOnOpen { max: 0 }, "c", json!(1));
+        assert!(out.take("c").is_empty());
+    }
+
+    // ---- typed messages ----
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(tag = "type", rename_all = "camelCase")]
+    enum ToPlugin {
+        ListBindings,
+        Rename { name: String },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(tag = "type", rename_all = "camelCase")]
+    enum ToPi {
+        Bindings { names: Vec<String> },
+        Renamed,
+    }
+
+    fn object(v: Value) -> Map<String, Value> {
+        match v {
+            Value::Object(m) => m,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn encode_echoes_the_request_id_and_decode_splits_it_off() {
+        let msg = ToPlugin::Rename { name: "x".into() };
+        let wire = encode_for_pi(&msg, Some(&json!(7))).unwrap();
+        assert_eq!(
+            wire,
+            json!({ "type": "rename", "name": "x", "requestId": 7 })
+        );
+
+        let (id, back) = decode_from_pi::<ToPlugin>(&object(wire));
+        assert_eq!(id, Some(json!(7)));
+        assert_eq!(back.unwrap(), msg);
+    }
+
+    #[test]
+    fn messages_without_a_request_id_round_trip() {
+        let wire = encode_for_pi(&ToPlugin::ListBindings, None).unwrap();
+        assert_eq!(wire, json!({ "type": "listBindings" }));
+        let (id, back) = decode_from_pi::<ToPlugin>(&object(wire));
+        assert_eq!(id, None);
+        assert_eq!(back.unwrap(), ToPlugin::ListBindings);
+    }
+
+    #[test]
+    fn non_objects_are_wrapped_in_payload() {
+        assert_eq!(
+            encode_for_pi(&[1, 2], Some(&json!("r1"))).unwrap(),
+            json!({ "payload": [1, 2], "requestId": "r1" })
+        );
+    }
+
+    #[test]
+    fn errors_carry_code_message_and_request_id() {
+        let err = PiError::new(ERR_DECODE, "bad");
+        assert_eq!(
+            encode_error_for_pi(&err, Some(&json!(3))),
+            json!({ "requestId": 3, "error": { "code": "decode", "message": "bad" } })
+        );
+        assert_eq!(
+            encode_error_for_pi(&err, None),
+            json!({ "error": { "code": "decode", "message": "bad" } })
+        );
+    }
+
+    /// Answers PI requests; counts decode failures.
+    #[derive(Default)]
+    struct Rpc {
+        decode_errors: u32,
+    }
+
+    impl Action for Rpc {
+        fn id(&self) -> &str {
+            "com.example.pi"
+        }
+    }
+
+    impl PiAction for Rpc {
+        type In = ToPlugin;
+        type Out = ToPi;
+
+        fn on_pi_message(&mut self, _cx: &Context, pi: &PiReply<'_, ToPi>, msg: ToPlugin) {
+            match msg {
+                ToPlugin::ListBindings => pi.reply(&ToPi::Bindings {
+                    names: vec!["fire".into()],
+                }),
+                ToPlugin::Rename { .. } => pi.push(&ToPi::Renamed),
+            }
+        }
+
+        fn on_pi_decode_error(
+            &mut self,
+            cx: &Context,
+            ev: &DidReceivePropertyInspectorMessage,
+            _err: &serde_json::Error,
+        ) {
+            self.decode_errors += 1;
+            cx.sd().log_message(format!(
+                "{} decode errors={}",
+                ev.context, self.decode_errors
+            ));
+        }
+    }
+
+    fn send_to_plugin(payload: Value) -> RecordEntry {
+        let text = json!({
+            "event": "sendToPlugin",
+            "action": "com.example.pi",
+            "context": "ctx",
+            "payload": payload,
+        });
+        RecordEntry::Incoming {
+            t_ms: 0,
+            event: parse_incoming(&text.to_string()).unwrap(),
+        }
+    }
+
+    #[test]
+    fn routed_actions_get_typed_messages_and_answer_them() {
+        let plugin =
+            Plugin::new().add_action(ActionFactory::new_pi("com.example.pi", Rpc::default));
+        let entries = [
+            incoming("willAppear"),
+            send_to_plugin(json!({ "type": "listBindings", "requestId": "r1" })),
+            send_to_plugin(json!({ "type": "rename", "name": "x" })),
+            send_to_plugin(json!({ "type": "explode", "requestId": 9 })),
+        ];
+        let produced = replay(&plugin, &entries).produced;
+        let to_pi: Vec<Value> = produced
+            .iter()
+            .filter_map(|m| match m {
+                Outgoing::SendToPropertyInspector { context, payload } if context == "ctx" => {
+                    Some(payload.clone())
+                }
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(to_pi.len(), 3);
+        assert_eq!(
+            to_pi[0],
+            json!({ "type": "bindings", "names": ["fire"], "requestId": "r1" })
+        );
+        assert_eq!(to_pi[1], json!({ "type": "renamed" }));
+        assert_eq!(to_pi[2]["requestId"], json!(9));
+        assert_eq!(to_pi[2]["error"]["code"], json!(ERR_DECODE));
+        assert!(
+            to_pi[2]["error"]["message"]
+                .as_str()
+                .unwrap()
+                .contains("explode")
+        );
+        assert!(produced.contains(&Outgoing::LogMessage {
+            message: "ctx decode errors=1".into()
+        }));
+    }
+
+    // ---- runtime ----
+
+    /// Sends its press count to its PI on every key down.
+    #[derive(Default)]
+    struct Chatty {
+        presses: u32,
+    }
+
+    impl Action for Chatty {
+        fn id(&self) -> &str {
+            "com.example.pi"
+        }
+
+        fn key_down(&mut self, cx: &Context, ev: &KeyDown) {
+            self.presses += 1;
+            cx.sd()
+                .send_to_property_inspector(ev.context, json!(self.presses));
+        }
+    }
+
+    fn incoming(event: &str) -> RecordEntry {
+        let text = json!({
+            "event": event,
+            "action": "com.example.pi",
+            "context": "ctx",
+            "device": "dev",
+            "payload": { "controller": "Keypad", "settings": {} },
+        });
+        RecordEntry::Incoming {
+            t_ms: 0,
+            event: parse_incoming(&text.to_string()).unwrap(),
+        }
+    }
+
+    /// PI messages the plugin sent while replaying `events`.
+    fn pi_messages(policy: PiQueuePolicy, events: &[&str]) -> Vec<Value> {
+        let plugin = Plugin::new()
+            .add_action(ActionFactory::new("com.example.pi", Chatty::default))
+            .set_pi_queue_policy(policy);
+        let mut entries = vec![incoming("willAppear")];
+        entries.extend(events.iter().map(|e| incoming(e)));
+        replay(&plugin, &entries)
+            .produced
+            .into_iter()
+            .filter_map(|m| match m {
+                Outgoing::SendToPropertyInspector { payload, .. } => Some(payload),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Logs what `cx` reports about open PIs on key down and on visibility changes.
+    #[derive(Default)]
+    struct Watcher;
+
+    impl Action for Watcher {
+        fn id(&self) -> &str {
+            "com.example.pi"
+        }
+
+        fn topics(&self) -> &'static [&'static str] {
+            &[PI_VISIBILITY.name]
+        }
+
+        fn key_down(&mut self, cx: &Context, ev: &KeyDown) {
+            let current = cx.current_pi().map(|p| p.context);
+            let open = cx.open_pis().len();
+            let here = cx.pi_open_for(ev.context);
+            cx.sd().log_message(format!("{current:?} {open} {here}"));
+        }
+
+        fn on_notify(&mut self, cx: &Context, _ctx_id: &str, event: &ErasedTopic) {
+            if let Some(v) = event.downcast(PI_VISIBILITY) {
+                cx.sd()
+                    .log_message(format!("{} open={}", v.info.context, v.open));
+            }
+        }
+    }
+
+    #[test]
+    fn runtime_tracks_pis_and_publishes_visibility() {
+        let plugin =
+            Plugin::new().add_action(ActionFactory::new("com.example.pi", Watcher::default));
+        let entries: Vec<_> = [
+            "willAppear",
+            "keyDown",
+            "propertyInspectorDidAppear",
+            "keyDown",
+            "propertyInspectorDidDisappear",
+            "keyDown",
+        ]
+        .into_iter()
+        .map(incoming)
+        .collect();
+        let logs: Vec<String> = replay(&plugin, &entries)
+            .produced
+            .into_iter()
+            .filter_map(|m| match m {
+                Outgoing::LogMessage { message } => Some(message),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            logs,
+            vec![
+                "None 0 false",
+                "ctx open=true",
+                "Some(\"ctx\") 1 true",
+                "ctx open=false",
+                "None 0 false",
+            ]
+        );
+    }
+
+    #[test]
+    fn drop_policy_discards_messages_while_closed() {
+        let got = pi_messages(
+            PiQueuePolicy::Drop,
+            &[
+                "keyDown",
+                "propertyInspectorDidAppear",
+                "keyDown",
+                "propertyInspectorDidDisappear",
+                "keyDown",
+            ],
+        );
+        assert_eq!(got, vec![json!(2)]);
+    }
+
+    #[test]
+    fn send_policy_sends_regardless() {
+        let got = pi_messages(PiQueuePolicy::Send, &["keyDown", "keyDown"]);
+        assert_eq!(got, vec![json!(1), json!(2)]);
+    }
+
+    #[test]
+    fn replay_policy_evicts_the_oldest_and_replays_in_order_on_open() {
+        let got = pi_messages(
+            PiQueuePolicy::ReplayOnOpen { max: 2 },
+            &[
+                "keyDown",
+                "keyDown",
+                "keyDown",
+                "propertyInspectorDidAppear",
+                "keyDown",
+            ],
+        );
+        assert_eq!(got, vec![json!(2), json!(3), json!(4)]);
+    }
+
+    #[test]
+    fn closing_the_pi_starts_a_fresh_hold() {
+        // what was replayed on the first open is not replayed again on the second
+        let got = pi_messages(
+            PiQueuePolicy::ReplayOnOpen { max: 4 },
+            &[
+                "keyDown",
+                "propertyInspectorDidAppear",
+                "propertyInspectorDidDisappear",
+                "keyDown",
+                "propertyInspectorDidAppear",
+            ],
+        );
+        assert_eq!(got, vec![json!(1), json!(2)]);
+    }
+
+    #[test]
+    fn held_messages_are_forgotten_when_the_instance_disappears() {
+        let got = pi_messages(
+            PiQueuePolicy::ReplayOnOpen { max: 4 },
+            &[
+                "keyDown",
+                "willDisappear",
+                "willAppear",
+                "propertyInspectorDidAppear",
+            ],
+        );
+        assert!(got.is_empty(), "{got:?}");
+    }
+}
@@ -1,12 +1,18 @@
 // plugin/builder.rs
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::actions::{ActionFactory, ActionId};
 use crate::adapters::Adapter;
-use crate::context::{Context, Extensions};
+use crate::context::{Context, Extensions, WriteBehind};
+use crate::events::TopicId;
 use crate::hooks::AppHooks;
+use crate::input::Layout;
+use crate::pi::PiQueuePolicy;
+use crate::recorder::{RecordEntry, RecordOptions, RecordTarget};
 use crate::sd_protocol::SdClient;
+use crate::typed_globals::GlobalsSchema;
 
 /// The assembled plugin: actions, adapters, hooks, and extensions.
 #[derive(Default)]
@@ -15,6 +21,13 @@ pub struct Plugin {
     exts: Extensions,
     hooks: AppHooks,
     adapters: Vec<Arc<dyn Adapter + Send + Sync>>,
+    pi_queue: PiQueuePolicy,
+    record: Option<RecordTarget>,
+    record_opts: RecordOptions,
+    layout: Layout,
+    globals_schema: Option<GlobalsSchema>,
+    globals_write_behind: Option<WriteBehind>,
+    defer_will_appear: Option<Duration>,
 }
 
 impl Plugin {
@@ -35,6 +48,13 @@ impl Plugin {
             exts,
             hooks,
             adapters,
+            pi_queue: PiQueuePolicy::default(),
+            record: None,
+            record_opts: RecordOptions::default(),
+            layout: Layout::default(),
+            globals_schema: None,
+            globals_write_behind: None,
+            defer_will_appear: None,
         }
     }
 
@@ -91,6 +111,102 @@ impl Plugin {
         self
     }
 
+    /// What to do with PI messages while the target PI is closed (chainable).
+    pub fn set_pi_queue_policy(mut self, policy: PiQueuePolicy) -> Self {
+        self.pi_queue = policy;
+        self
+    }
+
+    /// Record this session next to the logs written by `logger::init` (chainable).
+    /// Also enabled by setting `SD_RECORD=1`. Without `logger::init`, nothing is
+    /// recorded; use [`Plugin::record_session_in`].
+    pub fn record_session(mut self) -> Self {
+        self.record = Some(RecordTarget::LogDir);
+        self
+    }
+
+    /// Record this session into a new file in `dir`, rotated like
+    /// [`Plugin::record_session`] (chainable).
+    pub fn record_session_in(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.record = Some(RecordTarget::Dir(dir.into()));
+        self
+    }
+
+    /// Record this session into `path` (chainable).
+    pub fn record_to(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.record = Some(RecordTarget::File(path.into()));
+        self
+    }
+
+    /// Also record the payload of `topic` on publish (chainable), so `replay` can
+    /// publish it again. Other topics are recorded by name only.
+    pub fn record_topic<T>(mut self, topic: TopicId<T>) -> Self
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + Send + Sync + 'static,
+    {
+        self.record_opts.add_topic(topic);
+        self
+    }
+
+    /// Edit every recorded entry before it hits the disk, e.g. to blank tokens
+    /// stored in settings (chainable). Secrets from `didReceiveSecrets` are
+    /// always masked.
+    pub fn record_redact<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&mut RecordEntry) + Send + Sync + 'static,
+    {
+        self.record_opts.redact = Some(Arc::new(f));
+        self
+    }
+
+    /// How many sessions to keep in the session directory, including the current one
+    /// (chainable). Defaults to 8.
+    pub fn record_keep_sessions(mut self, keep: usize) -> Self {
+        self.record_opts.keep_sessions = keep.max(1);
+        self
+    }
+
+    /// Keyboard layout for key names: `Context::parse_key` and display names
+    /// (chainable). Defaults to US.
+    ///
+    /// Typing text does not go through it: Windows sends Unicode directly, and the
+    /// Linux backend takes its own layout (`LinuxSynth::with_layout(cx.keyboard_layout())`).
+    pub fn keyboard_layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Version and migrations for typed global settings (chainable).
+    /// Older snapshots from Stream Deck are migrated on arrival and pushed back.
+    ///
+    /// Panics unless every version from 1 up to the current one has a migration.
+    pub fn global_settings_schema(mut self, schema: GlobalsSchema) -> Self {
+        let missing = schema.missing_migrations();
+        assert!(
+            missing.is_empty(),
+            "global settings schema v{}: no migration from v{:?}",
+            schema.version(),
+            missing
+        );
+        self.globals_schema = Some(schema);
+        self
+    }
+
+    /// Coalesce global settings pushes instead of sending one per write (chainable).
+    /// Pending writes are flushed when the window passes and, best effort, when
+    /// Stream Deck closes the connection; see [`WriteBehind`].
+    pub fn global_settings_write_behind(mut self, write_behind: WriteBehind) -> Self {
+        self.globals_write_behind = Some(write_behind);
+        self
+    }
+
+    /// Hold back action `will_appear` (and action events after it) until the first
+    /// global settings snapshot arrives, or at most `max_wait` (chainable).
+    pub fn defer_will_appear_until_hydrated(mut self, max_wait: Duration) -> Self {
+        self.defer_will_appear = Some(max_wait);
+        self
+    }
+
     /// Build a Context using this plugin’s Extensions.
     pub(crate) fn make_context(
         &self,
@@ -99,6 +215,9 @@ impl Plugin {
         bus: Arc<dyn crate::bus::Bus>,
     ) -> Context {
         Context::new(sd, plugin_uuid, self.exts.clone(), bus)
+            .with_layout(self.layout)
+            .with_globals_schema(self.globals_schema.clone())
+            .with_globals_write_behind(self.globals_write_behind)
     }
 
     // ----- accessors kept for runtime -----
@@ -119,6 +238,26 @@ impl Plugin {
         &self.adapters
     }
 
+    pub fn pi_queue_policy(&self) -> PiQueuePolicy {
+        self.pi_queue
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    pub(crate) fn record_target(&self) -> Option<&RecordTarget> {
+        self.record.as_ref()
+    }
+
+    pub(crate) fn record_options(&self) -> &RecordOptions {
+        &self.record_opts
+    }
+
+    pub(crate) fn will_appear_defer(&self) -> Option<Duration> {
+        self.defer_will_appear
+    }
+
     pub fn exts(&self) -> Extensions {
         self.exts.clone()
     }
@@ -0,0 +1,456 @@
+// recorder.rs
+//! Opt-in session recorder: every incoming event, outgoing message and bus publish
+//! goes to a JSON-lines file so a bug report can be replayed (see `replay`).
+//!
+//! Secrets received from Stream Deck are always masked; anything else sensitive
+//! (tokens in settings, ...) is up to the plugin's redaction hook. Sessions in the
+//! log directory are rotated like the log files: only the newest few are kept, and
+//! a session stops recording once it reaches `MAX_SESSION_BYTES`.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+
+use crate::events::{ErasedTopic, TopicId};
+use crate::logger;
+use crate::sd_protocol::{Outgoing, StreamDeckEvent};
+
+/// Env var that enables recording: `1`/`true` records into the log dir, anything else is a file path.
+pub const RECORD_ENV: &str = "SD_RECORD";
+
+/// Placeholder written in place of redacted values.
+pub const REDACTED: &str = "<redacted>";
+
+const SESSION_PREFIX: &str = "session";
+const SESSION_EXT: &str = "jsonl";
+const DEFAULT_KEEP_SESSIONS: usize = 8;
+/// Size at which a session stops recording (64 MiB).
+const MAX_SESSION_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Where to write a recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RecordTarget {
+    /// `session-YYYYMMDD-HHMMSS-PID.jsonl` next to the logs of `logger::init`.
+    LogDir,
+    /// Same file names in a chosen directory.
+    Dir(PathBuf),
+    /// An explicit file.
+    File(PathBuf),
+}
+
+impl RecordTarget {
+    pub(crate) fn from_env() -> Option<Self> {
+        let v = std::env::var(RECORD_ENV).ok()?;
+        match v.trim() {
+            "" | "0" | "false" => None,
+            "1" | "true" => Some(Self::LogDir),
+            path => Some(Self::File(PathBuf::from(path))),
+        }
+    }
+}
+
+/// One line of a recording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum RecordEntry {
+    /// First line of every recording.
+    Start { t_ms: u64, plugin_uuid: String },
+    /// Event received from Stream Deck.
+    Incoming { t_ms: u64, event: StreamDeckEvent },
+    /// Message sent to Stream Deck.
+    Outgoing { t_ms: u64, message: Outgoing },
+    /// Bus publish. The payload is only kept for topics registered with
+    /// `Plugin::record_topic`; the bus itself is type-erased.
+    Publish {
+        t_ms: u64,
+        topic: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        payload: Option<Value>,
+    },
+}
+
+impl RecordEntry {
+    /// Milliseconds since the start of the session.
+    pub fn t_ms(&self) -> u64 {
+        match self {
+            Self::Start { t_ms, .. }
+            | Self::Incoming { t_ms, .. }
+            | Self::Outgoing { t_ms, .. }
+            | Self::Publish { t_ms, .. } => *t_ms,
+        }
+    }
+}
+
+/// Edits an entry before it is written; used to strip secrets from recordings.
+pub type RecordRedactor = Arc<dyn Fn(&mut RecordEntry) + Send + Sync>;
+
+type TopicEncoder = Arc<dyn Fn(&ErasedTopic) -> Option<Value> + Send + Sync>;
+type TopicDecoder = Arc<dyn Fn(Value) -> Option<ErasedTopic> + Send + Sync>;
+
+/// Turns a registered topic's payload into JSON for the recording and back for replay.
+#[derive(Clone)]
+pub(crate) struct TopicCodec {
+    pub(crate) encode: TopicEncoder,
+    pub(crate) decode: TopicDecoder,
+}
+
+/// Recording knobs set on the plugin builder.
+#[derive(Clone)]
+pub(crate) struct RecordOptions {
+    pub(crate) redact: Option<RecordRedactor>,
+    pub(crate) topics: HashMap<&'static str, TopicCodec>,
+    pub(crate) keep_sessions: usize,
+}
+
+impl Default for RecordOptions {
+    fn default() -> Self {
+        Self {
+            redact: None,
+            topics: HashMap::new(),
+            keep_sessions: DEFAULT_KEEP_SESSIONS,
+        }
+    }
+}
+
+impl RecordOptions {
+    pub(crate) fn add_topic<T>(&mut self, id: TopicId<T>)
+    where
+        T: Serialize + DeserializeOwned + Send + Sync + 'static,
+    {
+        let name = id.name;
+        let encode = move |ev: &ErasedTopic| {
+            let value = ev.downcast(TopicId::<T>::new(name))?;
+            serde_json::to_value(value)
+                .map_err(|e| warn!("⚠️ topic {} does not serialize: {}", name, e))
+                .ok()
+        };
+        let decode = move |value: Value| {
+            serde_json::from_value::<T>(value)
+                .map(|v| ErasedTopic::new(TopicId::<T>::new(name), v))
+                .map_err(|e| warn!("⚠️ recorded topic {} does not decode: {}", name, e))
+                .ok()
+        };
+        self.topics.insert(
+            name,
+            TopicCodec {
+                encode: Arc::new(encode),
+                decode: Arc::new(decode),
+            },
+        );
+    }
+}
+
+/// Appends `RecordEntry` lines to a file; flushed per line so a crash keeps the tail.
+pub(crate) struct Recorder {
+    out: BufWriter<File>,
+    path: PathBuf,
+    started: Instant,
+    opts: RecordOptions,
+    written: u64,
+    max_bytes: u64,
+    failed: bool,
+}
+
+impl Recorder {
+    pub(crate) fn open(
+        target: &RecordTarget,
+        plugin_uuid: &str,
+        opts: RecordOptions,
+    ) -> io::Result<Self> {
+        let path = match target {
+            RecordTarget::LogDir => {
+                let dir = logger::current_logs_dir().ok_or_else(|| {
+                    io::Error::other("logger::init was not called; use Plugin::record_session_in")
+                })?;
+                new_session_path(dir, opts.keep_sessions)
+            }
+            RecordTarget::Dir(dir) => {
+                fs::create_dir_all(dir)?;
+                new_session_path(dir, opts.keep_sessions)
+            }
+            RecordTarget::File(p) => p.clone(),
+        };
+        Self::create(path, plugin_uuid, opts)
+    }
+
+    fn create(path: PathBuf, plugin_uuid: &str, opts: RecordOptions) -> io::Result<Self> {
+        let mut rec = Self {
+            out: BufWriter::new(File::create(&path)?),
+            path,
+            started: Instant::now(),
+            opts,
+            written: 0,
+            max_bytes: MAX_SESSION_BYTES,
+            failed: false,
+        };
+        rec.write(&RecordEntry::Start {
+            t_ms: 0,
+            plugin_uuid: plugin_uuid.to_string(),
+        });
+        Ok(rec)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn incoming(&mut self, event: &StreamDeckEvent) {
+        let t_ms = self.elapsed_ms();
+        self.write(&RecordEntry::Incoming {
+            t_ms,
+            event: event.clone(),
+        });
+    }
+
+    pub(crate) fn outgoing(&mut self, message: &Outgoing) {
+        let t_ms = self.elapsed_ms();
+        self.write(&RecordEntry::Outgoing {
+            t_ms,
+            message: message.clone(),
+        });
+    }
+
+    pub(crate) fn publish(&mut self, event: &ErasedTopic) {
+        let t_ms = self.elapsed_ms();
+        let payload = self
+            .opts
+            .topics
+            .get(event.name())
+            .and_then(|codec| (codec.encode)(event));
+        self.write(&RecordEntry::Publish {
+            t_ms,
+            topic: event.name().to_string(),
+            payload,
+        });
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    fn write(&mut self, entry: &RecordEntry) {
+        if self.failed {
+            return;
+        }
+        let mut entry = entry.clone();
+        redact_secrets(&mut entry);
+        if let Some(redact) = &self.opts.redact {
+            redact(&mut entry);
+        }
+        let res = serde_json::to_vec(&entry)
+            .map_err(io::Error::from)
+            .and_then(|mut line| {
+                line.push(b'\n');
+                if self.written + line.len() as u64 > self.max_bytes {
+                    return Err(io::Error::other(format!(
+                        "session reached {} bytes",
+                        self.max_bytes
+                    )));
+                }
+                self.out.write_all(&line)?;
+                self.written += line.len() as u64;
+                self.out.flush()
+            });
+        if let Err(e) = res {
+            // warn once, then stay quiet
+            warn!("⚠️ session recording stopped: {:?}", e);
+            self.failed = true;
+        }
+    }
+}
+
+/// Mask every value of a `didReceiveSecrets` payload; keys stay for context.
+fn redact_secrets(entry: &mut RecordEntry) {
+    if let RecordEntry::Incoming {
+        event: StreamDeckEvent::DidReceiveSecrets { secrets },
+        ..
+    } = entry
+    {
+        for v in secrets.values_mut() {
+            *v = Value::from(REDACTED);
+        }
+    }
+}
+
+/// Read a JSON-lines recording. Blank lines are skipped.
+pub fn read_recording(path: impl AsRef<Path>) -> io::Result<Vec<RecordEntry>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {e}", i + 1))
+        })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// A fresh session file in `dir`, after deleting all but the newest `keep - 1` sessions.
+fn new_session_path(dir: &Path, keep: usize) -> PathBuf {
+    logger::cleanup_old_files(dir, SESSION_PREFIX, SESSION_EXT, keep.saturating_sub(1));
+    let path = logger::run_file_path(dir, SESSION_PREFIX, SESSION_EXT);
+    // a second session within the same second and process must not overwrite the first
+    let mut unique = path.clone();
+    let mut n = 1;
+    while unique.exists() {
+        n += 1;
+        unique = path.with_extension(format!("{n}.{SESSION_EXT}"));
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Map, json};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sd-rec-{}-{}.jsonl", name, std::process::id()))
+    }
+
+    fn secrets() -> StreamDeckEvent {
+        let mut secrets = Map::new();
+        secrets.insert("apiKey".into(), json!("hunter2"));
+        StreamDeckEvent::DidReceiveSecrets { secrets }
+    }
+
+    #[test]
+    fn secrets_are_masked_and_hook_runs() {
+        let path = temp_path("redact");
+        let opts = RecordOptions {
+            redact: Some(Arc::new(|e: &mut RecordEntry| {
+                if let RecordEntry::Outgoing {
+                    message: Outgoing::SetGlobalSettings { payload, .. },
+                    ..
+                } = e
+                {
+                    payload.remove("token");
+                }
+            })),
+            ..RecordOptions::default()
+        };
+        let mut rec = Recorder::create(path.clone(), "uuid", opts).unwrap();
+        rec.incoming(&secrets());
+        let mut payload = Map::new();
+        payload.insert("token".into(), json!("abc"));
+        payload.insert("volume".into(), json!(3));
+        rec.outgoing(&Outgoing::SetGlobalSettings {
+            context: "uuid".into(),
+            payload,
+        });
+        drop(rec);
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(!text.contains("hunter2"));
+        assert!(!text.contains("abc"));
+        assert!(text.contains(REDACTED));
+        assert!(text.contains("volume"));
+    }
+
+    #[test]
+    fn registered_topics_keep_their_payload() {
+        const VOLUME: TopicId<u32> = TopicId::new("volume");
+        const OTHER: TopicId<u32> = TopicId::new("other");
+        let path = temp_path("topic");
+        let mut opts = RecordOptions::default();
+        opts.add_topic(VOLUME);
+        let mut rec = Recorder::create(path.clone(), "uuid", opts).unwrap();
+        rec.publish(&ErasedTopic::new(VOLUME, 7));
+        rec.publish(&ErasedTopic::new(OTHER, 9));
+        drop(rec);
+
+        let entries = read_recording(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let published: Vec<_> = entries
+            .into_iter()
+            .filter_map(|e| match e {
+                RecordEntry::Publish { topic, payload, .. } => Some((topic, payload)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            published,
+            vec![("volume".into(), Some(json!(7))), ("other".into(), None)]
+        );
+    }
+
+    #[test]
+    fn recording_stops_at_size_cap() {
+        let path = temp_path("cap");
+        let mut rec = Recorder::create(path.clone(), "uuid", RecordOptions::default()).unwrap();
+        rec.max_bytes = rec.written + 10;
+        rec.incoming(&secrets());
+        assert!(rec.failed);
+        drop(rec);
+
+        let entries = read_recording(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(entries.len(), 1, "only the start line fits");
+    }
+
+    #[test]
+    fn old_sessions_are_cleaned_up() {
+        let dir = std::env::temp_dir().join(format!("sd-rec-dir-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for i in 0..5 {
+            std::fs::write(dir.join(format!("{SESSION_PREFIX}-{i}.{SESSION_EXT}")), "").unwrap();
+        }
+        std::fs::write(dir.join("plugin-0.log"), "").unwrap();
+        logger::cleanup_old_files(&dir, SESSION_PREFIX, SESSION_EXT, 2);
+
+        let mut left: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .map(|e| e.file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        let _ = std::fs::remove_dir_all(&dir);
+        let sessions = left.iter().filter(|n| n.ends_with(".jsonl")).count();
+        assert_eq!(sessions, 2);
+        assert!(left.contains(&"plugin-0.log".to_string()));
+    }
+
+    #[test]
+    fn sessions_rotate_in_one_directory() {
+        let dir = std::env::temp_dir().join(format!("sd-rec-rotate-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let target = RecordTarget::Dir(dir.clone());
+        let opts = RecordOptions {
+            keep_sessions: 2,
+            ..RecordOptions::default()
+        };
+
+        // each launch has its own registration uuid; the directory stays the same
+        let mut last = PathBuf::new();
+        for uuid in ["launch-1", "launch-2", "launch-3"] {
+            let rec = Recorder::open(&target, uuid, opts.clone()).unwrap();
+            assert_eq!(rec.path().parent(), Some(dir.as_path()));
+            last = rec.path().to_path_buf();
+        }
+        let left = std::fs::read_dir(&dir).unwrap().flatten().count();
+        let newest_kept = last.exists();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(left, 2);
+        assert!(newest_kept);
+    }
+}
@@ -0,0 +1,500 @@
+// replay.rs
+//! Feed a recording into a `Plugin` without a socket and diff what it sends.
+//!
+//! Incoming events are replayed in order, as fast as possible; timestamps are ignored.
+//! Adapters are not started (their traffic depends on the outside world); instead,
+//! recorded bus publishes the plugin does not produce itself during replay are
+//! published again, in order. That needs the payload, so only topics registered
+//! with `Plugin::record_topic` come back. Hooks and actions run as in the live runtime.
+
+use std::{collections::HashMap, io, path::Path, sync::Arc};
+
+use crossbeam_channel::{Receiver, unbounded};
+use tracing::warn;
+
+use crate::{
+    bus::Emitter,
+    events::RuntimeMsg,
+    plugin::Plugin,
+    recorder::{RecordEntry, read_recording},
+    runtime::{Core, Flow},
+    sd_protocol::{Outgoing, SdClient},
+};
+
+const REPLAY_UUID: &str = "replay";
+
+/// Cap on the alignment table; longer diverging stretches are compared by position.
+const MAX_ALIGN_CELLS: usize = 4_000_000;
+
+/// One place where produced and recorded outgoing traffic differ.
+///
+/// The two sequences are aligned first (longest common subsequence), so a single
+/// missing or extra message shows up once instead of shifting everything after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayMismatch {
+    /// Index into `ReplayReport::expected` (`None` for an extra produced message).
+    pub expected_index: Option<usize>,
+    /// Index into `ReplayReport::produced` (`None` for a missing message).
+    pub actual_index: Option<usize>,
+    /// What the recording has (`None` if the replay produced an extra message).
+    pub expected: Option<Outgoing>,
+    /// What the replay produced (`None` if it did not produce this message).
+    pub actual: Option<Outgoing>,
+}
+
+/// Result of a replay.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayReport {
+    /// Outgoing messages the plugin produced during replay.
+    pub produced: Vec<Outgoing>,
+    /// Outgoing messages in the recording.
+    pub expected: Vec<Outgoing>,
+    pub mismatches: Vec<ReplayMismatch>,
+    /// Recorded publishes that could not be published again (topic not registered
+    /// with `Plugin::record_topic`, or its payload no longer decodes).
+    pub skipped_publishes: Vec<String>,
+}
+
+impl ReplayReport {
+    /// True if the plugin sent exactly what was recorded.
+    pub fn is_match(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Replay `entries` through `plugin` and compare outgoing messages.
+pub fn replay(plugin: &Plugin, entries: &[RecordEntry]) -> ReplayReport {
+    let plugin_uuid = entries
+        .iter()
+        .find_map(|e| match e {
+            RecordEntry::Start { plugin_uuid, .. } => Some(plugin_uuid.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| REPLAY_UUID.to_string());
+
+    let (rt_tx, rt_rx) = unbounded::<RuntimeMsg>();
+    let sd = Arc::new(SdClient::new(rt_tx.clone(), plugin_uuid.clone()));
+    let bus = Arc::new(Emitter::new(rt_tx));
+    let cx = plugin.make_context(sd, plugin_uuid, bus);
+
+    // same startup sequence as the live runtime, minus register + adapters
+    plugin.hooks().fire_init(&cx);
+    cx.sd().get_global_settings();
+
+    let mut core = Core::new(plugin, cx, None);
+    let mut run = Run::default();
+
+    let mut exited = run.drain(&mut core, &rt_rx);
+    for entry in entries {
+        if exited {
+            break;
+        }
+        let msg = match entry {
+            RecordEntry::Incoming { event, .. } => RuntimeMsg::Incoming(event.clone()),
+            RecordEntry::Publish { topic, payload, .. } => {
+                // the plugin published this itself during replay; don't send it twice
+                if let Some(n) = run.own_publishes.get_mut(topic.as_str())
+                    && *n > 0
+                {
+                    *n -= 1;
+                    continue;
+                }
+                let codec = plugin.record_options().topics.get(topic.as_str());
+                match (codec, payload) {
+                    (Some(codec), Some(payload)) => match (codec.decode)(payload.clone()) {
+                        Some(event) => RuntimeMsg::Publish(Arc::new(event)),
+                        None => {
+                            run.skipped.push(topic.clone());
+                            continue;
+                        }
+                    },
+                    _ => {
+                        warn!("⚠️ replay: no payload for topic {}; not published", topic);
+                        run.skipped.push(topic.clone());
+                        continue;
+                    }
+                }
+            }
+            _ => continue,
+        };
+        let mut send = |out| run.produced.push(out);
+        exited = matches!(core.handle(msg, &mut send), Flow::Exit) || run.drain(&mut core, &rt_rx);
+    }
+    core.release_deferred();
+    run.drain(&mut core, &rt_rx);
+    if core.flush_globals() {
+        run.drain(&mut core, &rt_rx);
+    }
+    core.shutdown();
+
+    let expected: Vec<Outgoing> = entries
+        .iter()
+        .filter_map(|e| match e {
+            RecordEntry::Outgoing { message, .. } => Some(message.clone()),
+            _ => None,
+        })
+        .collect();
+
+    let mismatches = diff(&expected, &run.produced);
+    ReplayReport {
+        produced: run.produced,
+        expected,
+        mismatches,
+        skipped_publishes: run.skipped,
+    }
+}
+
+/// Read a recording from disk and replay it.
+pub fn replay_file(plugin: &Plugin, path: impl AsRef<Path>) -> io::Result<ReplayReport> {
+    let entries = read_recording(path)?;
+    Ok(replay(plugin, &entries))
+}
+
+/// State of one replay run.
+#[derive(Default)]
+struct Run {
+    produced: Vec<Outgoing>,
+    /// Topics the plugin published during replay, not yet matched to a recorded publish.
+    own_publishes: HashMap<&'static str, usize>,
+    skipped: Vec<String>,
+}
+
+impl Run {
+    /// Handle everything queued so far. Returns true if the plugin asked to exit.
+    fn drain(&mut self, core: &mut Core<'_>, rx: &Receiver<RuntimeMsg>) -> bool {
+        while let Ok(msg) = rx.try_recv() {
+            if let RuntimeMsg::Publish(event) = &msg {
+                *self.own_publishes.entry(event.name()).or_default() += 1;
+            }
+            let mut send = |out| self.produced.push(out);
+            if let Flow::Exit = core.handle(msg, &mut send) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+fn diff(expected: &[Outgoing], actual: &[Outgoing]) -> Vec<ReplayMismatch> {
+    // common head and tail are matches; align what is left in between
+    let head = expected
+        .iter()
+        .zip(actual)
+        .take_while(|(e, a)| e == a)
+        .count();
+    let tail = expected[head..]
+        .iter()
+        .rev()
+        .zip(actual[head..].iter().rev())
+        .take_while(|(e, a)| e == a)
+        .count();
+    let exp = &expected[head..expected.len() - tail];
+    let act = &actual[head..actual.len() - tail];
+
+    let mut out = Vec::new();
+    let mut gap_e = Vec::new();
+    let mut gap_a = Vec::new();
+    for step in align(exp, act) {
+        match step {
+            Step::Same => flush_gap(&mut out, &mut gap_e, &mut gap_a, expected, actual),
+            Step::Missing(i) => gap_e.push(head + i),
+            Step::Extra(j) => gap_a.push(head + j),
+        }
+    }
+    flush_gap(&mut out, &mut gap_e, &mut gap_a, expected, actual);
+    out
+}
+
+enum Step {
+    Same,
+    /// Index into the expected slice.
+    Missing(usize),
+    /// Index into the actual slice.
+    Extra(usize),
+}
+
+/// LCS alignment of two slices as a sequence of steps.
+fn align(exp: &[Outgoing], act: &[Outgoing]) -> Vec<Step> {
+    let (n, m) = (exp.len(), act.len());
+    if (n + 1).saturating_mul(m + 1) > MAX_ALIGN_CELLS {
+        // too big to align; pair by position
+        let mut steps: Vec<Step> = Vec::with_capacity(n + m);
+        for i in 0..n.max(m) {
+            match (exp.get(i), act.get(i)) {
+                (Some(e), Some(a)) if e == a => steps.push(Step::Same),
+                (e, a) => {
+                    if e.is_some() {
+                        steps.push(Step::Missing(i));
+                    }
+                    if a.is_some() {
+                        steps.push(Step::Extra(i));
+                    }
+                }
+            }
+        }
+        return steps;
+    }
+
+    // lcs[i][j] = LCS length of exp[i..] and act[j..]
+    let w = m + 1;
+    let mut lcs = vec![0u32; (n + 1) * w];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i * w + j] = if exp[i] == act[j] {
+                lcs[(i + 1) * w + j + 1] + 1
+            } else {
+                lcs[(i + 1) * w + j].max(lcs[i * w + j + 1])
+            };
+        }
+    }
+
+    let mut steps = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && exp[i] == act[j] {
+            steps.push(Step::Same);
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[i * w + j + 1] >= lcs[(i + 1) * w + j]) {
+            steps.push(Step::Extra(j));
+            j += 1;
+        } else {
+            steps.push(Step::Missing(i));
+            i += 1;
+        }
+    }
+    steps
+}
+
+/// Turn one run of unmatched messages into mismatches; missing and extra messages
+/// in the same run are paired up as "changed".
+fn flush_gap(
+    out: &mut Vec<ReplayMismatch>,
+    gap_e: &mut Vec<usize>,
+    gap_a: &mut Vec<usize>,
+    expected: &[Outgoing],
+    actual: &[Outgoing],
+) {
+    let len = gap_e.len().max(gap_a.len());
+    for k in 0..len {
+        let ei = gap_e.get(k).copied();
+        let ai = gap_a.get(k).copied();
+        out.push(ReplayMismatch {
+            expected_index: ei,
+            actual_index: ai,
+            expected: ei.map(|i| expected[i].clone()),
+            actual: ai.map(|j| actual[j].clone()),
+        });
+    }
+    gap_e.clear();
+    gap_a.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::actions::{Action, ActionFactory};
+    use crate::bus::BusTyped;
+    use crate::context::Context;
+    use crate::events::{ErasedTopic, TopicId};
+    use crate::sd_protocol::{SetTitlePayload, parse_incoming, views::KeyDown};
+
+    const SCORE: TopicId<u32> = TopicId::new("score");
+    const PRESSED: TopicId<()> = TopicId::new("pressed");
+
+    /// Counts presses in its title; shows scores published by an (absent) adapter.
+    #[derive(Default)]
+    struct Counter {
+        presses: u32,
+    }
+
+    impl Action for Counter {
+        fn id(&self) -> &str {
+            "com.example.counter"
+        }
+
+        fn topics(&self) -> &'static [&'static str] {
+            &["score"]
+        }
+
+        fn key_down(&mut self, cx: &Context, ev: &KeyDown) {
+            self.presses += 1;
+            cx.sd()
+                .set_title_simple(ev.context, self.presses.to_string());
+            cx.bus().publish_t(PRESSED, ());
+        }
+
+        fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
+            if let Some(score) = event.downcast(SCORE) {
+                cx.sd().set_title_simple(ctx_id, format!("score {score}"));
+            }
+        }
+    }
+
+    fn plugin() -> Plugin {
+        Plugin::new()
+            .add_action(ActionFactory::new("com.example.counter", Counter::default))
+            .record_topic(SCORE)
+    }
+
+    fn incoming(event: &str) -> RecordEntry {
+        let text = json!({
+            "event": event,
+            "action": "com.example.counter",
+            "context": "ctx",
+            "device": "dev",
+            "payload": { "controller": "Keypad", "settings": {}, "isInMultiAction": false },
+        });
+        RecordEntry::Incoming {
+            t_ms: 0,
+            event: parse_incoming(&text.to_string()).unwrap(),
+        }
+    }
+
+    fn title(text: &str) -> RecordEntry {
+        RecordEntry::Outgoing {
+            t_ms: 0,
+            message: Outgoing::SetTitle {
+                context: "ctx".into(),
+                payload: SetTitlePayload {
+                    title: Some(text.into()),
+                    state: None,
+                    target: None,
+                },
+            },
+        }
+    }
+
+    fn publish(topic: &str, payload: Option<serde_json::Value>) -> RecordEntry {
+        RecordEntry::Publish {
+            t_ms: 0,
+            topic: topic.into(),
+            payload,
+        }
+    }
+
+    /// What a live session of `plugin()` records: a press, an adapter score, another press.
+    fn session() -> Vec<RecordEntry> {
+        vec![
+            RecordEntry::Start {
+                t_ms: 0,
+                plugin_uuid: "uuid".into(),
+            },
+            RecordEntry::Outgoing {
+                t_ms: 0,
+                message: Outgoing::GetGlobalSettings {
+                    context: "uuid".into(),
+                },
+            },
+            incoming("willAppear"),
+            incoming("keyDown"),
+            title("1"),
+            publish("pressed", None),
+            publish("score", Some(json!(7))),
+            title("score 7"),
+            incoming("keyDown"),
+            title("2"),
+            publish("pressed", None),
+        ]
+    }
+
+    #[test]
+    fn replay_reproduces_a_session_with_adapter_publishes() {
+        let report = replay(&plugin(), &session());
+        assert!(report.is_match(), "{:#?}", report.mismatches);
+        assert!(report.skipped_publishes.is_empty());
+        assert_eq!(report.produced.len(), 4);
+    }
+
+    #[test]
+    fn replay_reports_changed_behaviour() {
+        let mut entries = session();
+        entries[4] = title("one");
+        let report = replay(&plugin(), &entries);
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].expected_index, Some(1));
+        assert_eq!(report.mismatches[0].actual_index, Some(1));
+    }
+
+    #[test]
+    fn unregistered_topics_are_skipped_and_reported() {
+        let plugin =
+            Plugin::new().add_action(ActionFactory::new("com.example.counter", Counter::default));
+        let report = replay(&plugin, &session());
+        assert_eq!(report.skipped_publishes, vec!["score".to_string()]);
+        assert_eq!(report.mismatches.len(), 1, "the score title is missing");
+        assert_eq!(report.mismatches[0].actual, None);
+    }
+
+    fn log(s: &str) -> Outgoing {
+        Outgoing::LogMessage {
+            message: s.to_string(),
+        }
+    }
+
+    fn seq(s: &str) -> Vec<Outgoing> {
+        s.chars().map(|c| log(&c.to_string())).collect()
+    }
+
+    #[test]
+    fn equal_sequences_have_no_mismatches() {
+        assert!(diff(&seq("abcd"), &seq("abcd")).is_empty());
+    }
+
+    #[test]
+    fn one_missing_message_is_one_mismatch() {
+        let got = diff(&seq("abcde"), &seq("abde"));
+        assert_eq!(
+            got,
+            vec![ReplayMismatch {
+                expected_index: Some(2),
+                actual_index: None,
+                expected: Some(log("c")),
+                actual: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn one_extra_message_is_one_mismatch() {
+        let got = diff(&seq("abde"), &seq("abxde"));
+        assert_eq!(
+            got,
+            vec![ReplayMismatch {
+                expected_index: None,
+                actual_index: Some(2),
+                expected: None,
+                actual: Some(log("x")),
+            }]
+        );
+    }
+
+    #[test]
+    fn changed_message_pairs_expected_with_actual() {
+        let got = diff(&seq("abcd"), &seq("abXd"));
+        assert_eq!(got.len(), 1);
+        assert_eq!(got[0].expected_index, Some(2));
+        assert_eq!(got[0].actual_index, Some(2));
+        assert_eq!(got[0].expected, Some(log("c")));
+        assert_eq!(got[0].actual, Some(log("X")));
+    }
+
+    #[test]
+    fn scattered_differences_stay_local() {
+        // a dropped message early and an extra one late; the rest lines up
+        let got = diff(&seq("abcdefgh"), &seq("acdefgXh"));
+        let pairs: Vec<_> = got
+            .iter()
+            .map(|m| (m.expected_index, m.actual_index))
+            .collect();
+        assert_eq!(pairs, vec![(Some(1), None), (None, Some(6))]);
+    }
+
+    #[test]
+    fn empty_sides() {
+        assert_eq!(diff(&seq(""), &seq("ab")).len(), 2);
+        assert_eq!(diff(&seq("ab"), &seq("")).len(), 2);
+    }
+}
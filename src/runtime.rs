@@ -4,18 +4,21 @@ use std::{
     panic::{AssertUnwindSafe, catch_unwind},
     sync::{Arc, Mutex},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crate::{
     action_manager::{ActionManager, dispatch},
     adapters_manager::AdapterManager,
-    bus::Emitter,
+    bus::{BusTyped, Emitter},
+    context::Context,
     events::{AdapterControl, AdapterTarget, RuntimeMsg},
     hooks::AppHooks,
     launch::LaunchArgs,
+    pi::{PI_VISIBILITY, PiInfo, PiOutbox, PiQueuePolicy, PiVisibility},
     plugin::Plugin,
-    sd_protocol::{self, SdClient, StreamDeckEvent, parse_incoming_owned, serialize_outgoing},
+    recorder::{RecordTarget, Recorder},
+    sd_protocol::{self, SdClient, StreamDeckEvent, parse_incoming, serialize_outgoing},
 };
 use crossbeam_channel::{select, unbounded};
 use tracing::{debug, error, info, trace, warn};
@@ -56,6 +59,9 @@ pub fn run_with_defaults(plugin: Plugin, args: LaunchArgs) -> anyhow::Result<()>
 
 /// Run the plugin runtime (non-generic;
 fn run_inner(plugin: Plugin, args: LaunchArgs, url: &str) -> anyhow::Result<()> {
+    // a panicking action must not leave synthesized keys pressed
+    crate::input::install_panic_hook();
+
     // ---------- connect ----------
     info!("🔗 connecting websocket: {}", url);
 
@@ -114,11 +120,7 @@ fn run_inner(plugin: Plugin, args: LaunchArgs, url: &str) -> anyhow::Result<()>
                 for incoming in reader.incoming_messages() {
                     match incoming {
                         Ok(OwnedMessage::Text(text)) => {
-                            let parsed = serde_json::from_str::<
-                                serde_json::Map<String, serde_json::Value>,
-                            >(&text)
-                            .map_err(|e| format!("json parse error: {e}"))
-                            .and_then(parse_incoming_owned);
+                            let parsed = parse_incoming(&text);
 
                             match parsed {
                                 Ok(ev) => {
@@ -165,142 +167,376 @@ fn run_inner(plugin: Plugin, args: LaunchArgs, url: &str) -> anyhow::Result<()>
         });
     }
 
-    // ---------- adapters ----------
-    let mut adapter_mgr = AdapterManager::new(plugin.adapters(), cx.bus());
+    // ---------- core: hooks, actions, adapters ----------
+    let recorder = open_recorder(&plugin, &args.plugin_uuid);
+    let mut core = Core::new(&plugin, cx, recorder);
 
     // Start adapters with Eager policy right away
-    adapter_mgr.start_by_policy(&cx, crate::adapters::StartPolicy::Eager);
-    // ---------- hooks + action manager ----------
-    let hooks: AppHooks = plugin.hooks().clone();
-    let mut mgr: ActionManager = ActionManager::new(plugin.actions().clone());
+    core.start_eager_adapters();
 
     // ---------- tiny burst buffer for outgoing ----------
     let mut outq: VecDeque<sd_protocol::Outgoing> = VecDeque::new();
 
     // ---------- main loop ----------
-    use RuntimeMsg::*;
     loop {
         select! {
             recv(rt_rx) -> msg => {
-                match msg {
-                    // ---------- incoming SD events ----------
-                    Ok(Incoming(ev)) => {
-                        hooks.fire_incoming(&cx, &ev);
-
-                        // fire hooks and adapters
-                        match &ev {
-                            StreamDeckEvent::ApplicationDidLaunch { application } => {
-                                adapter_mgr.on_application_did_launch(&cx);
-                                hooks.fire_application_did_launch(&cx, application);
-                            }
-                            StreamDeckEvent::ApplicationDidTerminate { application } => {
-                                adapter_mgr.on_application_did_terminate();
-                                hooks.fire_application_did_terminate(&cx, application);
-                            }
-                            StreamDeckEvent::DeviceDidConnect { device, device_info } => {
-                                hooks.fire_device_did_connect(&cx, device, device_info);
-                            }
-                            StreamDeckEvent::DeviceDidDisconnect { device } => {
-                                hooks.fire_device_did_disconnect(&cx, device);
-                            }
-                            StreamDeckEvent::DeviceDidChange { device, device_info } => {
-                                hooks.fire_device_did_change(&cx, device, device_info);
-                            }
-                            StreamDeckEvent::DidReceiveDeepLink { url } => {
-                                hooks.fire_did_receive_deep_link(&cx, url);
-                            }
-                            StreamDeckEvent::DidReceiveGlobalSettings { settings } => {
-                                cx.globals().hydrate_from_sd(settings.clone());
-                                hooks.fire_did_receive_global_settings(&cx, settings);
+                let msg = match msg {
+                    Ok(msg) => msg,
+                    Err(err) => {
+                        error!( "❌ runtime channel error: {:?}", err);
+                        break;
+                    } // bus closed
+                };
+                let flow = core.handle(msg, &mut |out| {
+                    let was_empty = outq.is_empty();
+                    outq.push_back(out);
+                    if was_empty {
+                        // quick flush, more than 8 messages per tick is unlikely
+                        drain_outgoing(&mut outq, &writer);
+                    }
+                });
+                if let Flow::Exit = flow {
+                    // Stream Deck sent a Close frame (quitting or unloading the plugin).
+                    // We have not answered it, so the socket still takes frames, but
+                    // Stream Deck may no longer read them: this push is best effort.
+                    if core.flush_globals() {
+                        while let Ok(msg) = rt_rx.try_recv() {
+                            if let RuntimeMsg::Outgoing(_) = msg {
+                                core.handle(msg, &mut |out| outq.push_back(out));
                             }
-                            _ => {}
                         }
-
-                        // dispatch to actions
-                        dispatch(&mut mgr, &cx, &plugin, ev);
                     }
+                    break;
+                }
+            }
+
+            default(Duration::from_millis(100)) => {
+                drain_outgoing(&mut outq, &writer);
+                core.tick();
+            }
+        }
+    }
+
+    // ---------- shutdown ----------
+    while !outq.is_empty() {
+        let before = outq.len();
+        drain_outgoing(&mut outq, &writer);
+        if outq.len() == before {
+            break;
+        }
+    }
+    core.shutdown();
+    crate::input::release_all();
+
+    info!("🔚 runtime shutdown complete");
+
+    Ok(())
+}
+
+/// What the main loop should do after a message.
+pub(crate) enum Flow {
+    Continue,
+    Exit,
+}
+
+/// Everything the main loop owns, independent of the websocket.
+/// Driven by the live runtime and by the replay driver.
+pub(crate) struct Core<'p> {
+    plugin: &'p Plugin,
+    cx: Context,
+    hooks: AppHooks,
+    mgr: ActionManager,
+    adapter_mgr: AdapterManager,
+    // PI messages held while their PI is closed
+    pi_policy: PiQueuePolicy,
+    pi_outbox: PiOutbox,
+    recorder: Option<Recorder>,
+    // action events held until global settings are hydrated
+    deferred: Deferred,
+}
+
+/// Action events waiting for the first global settings snapshot.
+#[derive(Default)]
+struct Deferred {
+    max_wait: Option<Duration>,
+    since: Option<Instant>,
+    events: VecDeque<StreamDeckEvent>,
+}
+
+impl Deferred {
+    /// Whether `ev` must wait: a `WillAppear` before hydration, or anything
+    /// behind an event that is already waiting (keeps per-context order).
+    fn holds(&self, cx: &Context, ev: &StreamDeckEvent) -> bool {
+        self.max_wait.is_some()
+            && (!self.events.is_empty()
+                || (matches!(ev, StreamDeckEvent::WillAppear { .. })
+                    && !cx.globals().is_hydrated()))
+    }
+
+    fn push(&mut self, ev: StreamDeckEvent) {
+        self.since.get_or_insert_with(Instant::now);
+        self.events.push_back(ev);
+    }
+
+    fn expired(&self) -> bool {
+        matches!((self.max_wait, self.since), (Some(max), Some(since)) if since.elapsed() >= max)
+    }
+
+    /// Dispatch everything held, in arrival order.
+    fn release(&mut self, mgr: &mut ActionManager, cx: &Context, plugin: &Plugin) {
+        self.since = None;
+        for ev in self.events.drain(..) {
+            dispatch(mgr, cx, plugin, ev);
+        }
+    }
+}
+
+impl<'p> Core<'p> {
+    pub(crate) fn new(plugin: &'p Plugin, cx: Context, recorder: Option<Recorder>) -> Self {
+        Self {
+            plugin,
+            hooks: plugin.hooks().clone(),
+            mgr: ActionManager::new(plugin.actions().clone()),
+            adapter_mgr: AdapterManager::new(plugin.adapters(), cx.bus()),
+            pi_policy: plugin.pi_queue_policy(),
+            pi_outbox: PiOutbox::default(),
+            recorder,
+            deferred: Deferred {
+                max_wait: plugin.will_appear_defer(),
+                ..Deferred::default()
+            },
+            cx,
+        }
+    }
 
-                    // ---------- outgoing SD messages ----------
-                    Ok(Outgoing(msg)) => {
-                        hooks.fire_outgoing(&cx, &msg);
-                        let was_empty = outq.is_empty();
-                        outq.push_back(msg);
-                        if was_empty {
-                            // quick flush, more than 8 messages per tick is unlikely
-                            drain_outgoing(&mut outq, &writer);
+    pub(crate) fn start_eager_adapters(&mut self) {
+        self.adapter_mgr
+            .start_by_policy(&self.cx, crate::adapters::StartPolicy::Eager);
+    }
+
+    /// Handle one runtime message. Messages that should reach Stream Deck go to `send`.
+    pub(crate) fn handle(
+        &mut self,
+        msg: RuntimeMsg,
+        send: &mut dyn FnMut(sd_protocol::Outgoing),
+    ) -> Flow {
+        use RuntimeMsg::*;
+        let cx = &self.cx;
+        let hooks = &self.hooks;
+        cx.globals().flush_due();
+
+        match msg {
+            // ---------- incoming SD events ----------
+            Incoming(ev) => {
+                if let Some(rec) = self.recorder.as_mut() {
+                    rec.incoming(&ev);
+                }
+                hooks.fire_incoming(cx, &ev);
+
+                // fire hooks and adapters
+                match &ev {
+                    StreamDeckEvent::ApplicationDidLaunch { application } => {
+                        self.adapter_mgr.on_application_did_launch(cx);
+                        hooks.fire_application_did_launch(cx, application);
+                    }
+                    StreamDeckEvent::ApplicationDidTerminate { application } => {
+                        self.adapter_mgr.on_application_did_terminate();
+                        hooks.fire_application_did_terminate(cx, application);
+                    }
+                    StreamDeckEvent::DeviceDidConnect {
+                        device,
+                        device_info,
+                    } => {
+                        hooks.fire_device_did_connect(cx, device, device_info);
+                    }
+                    StreamDeckEvent::DeviceDidDisconnect { device } => {
+                        hooks.fire_device_did_disconnect(cx, device);
+                    }
+                    StreamDeckEvent::DeviceDidChange {
+                        device,
+                        device_info,
+                    } => {
+                        hooks.fire_device_did_change(cx, device, device_info);
+                    }
+                    StreamDeckEvent::DidReceiveDeepLink { url } => {
+                        hooks.fire_did_receive_deep_link(cx, url);
+                    }
+                    StreamDeckEvent::DidReceiveGlobalSettings { settings } => {
+                        let (settings, diff) = cx.globals().hydrate_from_sd(settings.clone());
+                        hooks.fire_did_receive_global_settings(cx, &settings);
+                        if !diff.is_empty() {
+                            hooks.fire_global_settings_changed(cx, &diff);
                         }
                     }
-
-                    Ok(Publish(event)) => {
-                        hooks.fire_action_notify(&cx, &event);
-                        hooks.fire_adapter_notify(&cx, &AdapterTarget::All, event.as_ref());
-                        let name = event.name();
-                        mgr.notify_topic(&cx, name, Arc::clone(&event));
-                        adapter_mgr.notify_topic_name(name, event);
+                    StreamDeckEvent::PropertyInspectorDidAppear {
+                        action,
+                        context,
+                        device,
+                    } => {
+                        let info = PiInfo {
+                            action: action.clone(),
+                            context: context.clone(),
+                            device: device.clone(),
+                        };
+                        cx.pi_tracker().opened(info.clone());
+                        cx.bus()
+                            .publish_t(PI_VISIBILITY, PiVisibility { info, open: true });
+                        // replay anything held back while it was closed
+                        for payload in self.pi_outbox.take(context) {
+                            cx.sd().send_to_property_inspector(context.clone(), payload);
+                        }
                     }
-                    // ---------- typed action notify ----------
-                    Ok(ActionNotify { target, event }) => {
-                        hooks.fire_action_notify(&cx, &event);
-                        mgr.notify_target(&cx, target, event);
+                    StreamDeckEvent::PropertyInspectorDidDisappear { context, .. } => {
+                        if let Some(info) = cx.pi_tracker().closed(context) {
+                            cx.bus()
+                                .publish_t(PI_VISIBILITY, PiVisibility { info, open: false });
+                        }
                     }
-
-                    // ---------- typed adapter notify ----------
-                    Ok(AdapterNotify { target, event }) => {
-                        hooks.fire_adapter_notify(&cx, &target, event.as_ref());
-                        adapter_mgr.notify_target(target, event);
+                    StreamDeckEvent::WillDisappear { context, .. } => {
+                        self.pi_outbox.forget(context);
+                        if let Some(info) = cx.pi_tracker().closed(context) {
+                            cx.bus()
+                                .publish_t(PI_VISIBILITY, PiVisibility { info, open: false });
+                        }
                     }
+                    _ => {}
+                }
+
+                // dispatch to actions (after anything held for hydration)
+                if !self.deferred.events.is_empty() && cx.globals().is_hydrated() {
+                    self.deferred.release(&mut self.mgr, cx, self.plugin);
+                }
+                if self.deferred.holds(cx, &ev) {
+                    self.deferred.push(ev);
+                } else {
+                    dispatch(&mut self.mgr, cx, self.plugin, ev);
+                }
+            }
 
-                    // ---------- adapter control ----------
-                    Ok(RuntimeMsg::Adapter(ctl)) => {
-                        hooks.fire_adapter_control(&cx, &ctl);
-                        match ctl {
-                            AdapterControl::Start(target) => match target {
-                                AdapterTarget::All         => adapter_mgr.start_all(&cx),
-                                AdapterTarget::Policy(p)   => adapter_mgr.start_by_policy(&cx, p),
-                                AdapterTarget::Name(n)     => adapter_mgr.start_by_name(&cx, n),
-                                AdapterTarget::Label(l)    => adapter_mgr.start_by_label(&cx, l),
-                            },
-                            AdapterControl::Stop(target) => match target {
-                                AdapterTarget::All         => adapter_mgr.stop_all(),
-                                AdapterTarget::Policy(p)   => adapter_mgr.stop_by_policy(p),
-                                AdapterTarget::Name(n)     => adapter_mgr.stop_by_name(n),
-                                AdapterTarget::Label(l)    => adapter_mgr.stop_by_label(l),
-                            },
-                            AdapterControl::Restart(target) => match target {
-                                AdapterTarget::All         => adapter_mgr.restart_all(&cx),
-                                AdapterTarget::Policy(p)   => adapter_mgr.restart_by_policy(&cx, p),
-                                AdapterTarget::Name(n)     => adapter_mgr.restart_by_name(&cx, n),
-                                AdapterTarget::Label(l)    => adapter_mgr.restart_by_label(&cx, l),
-                            },
+            // ---------- outgoing SD messages ----------
+            Outgoing(msg) => {
+                // hold back PI messages while the PI is closed (per policy)
+                let msg = match msg {
+                    sd_protocol::Outgoing::SendToPropertyInspector { context, payload }
+                        if !cx.pi_open_for(&context) =>
+                    {
+                        match self.pi_outbox.hold(self.pi_policy, &context, payload) {
+                            Some(payload) => {
+                                sd_protocol::Outgoing::SendToPropertyInspector { context, payload }
+                            }
+                            None => return Flow::Continue,
                         }
                     }
+                    other => other,
+                };
+                hooks.fire_outgoing(cx, &msg);
+                if let Some(rec) = self.recorder.as_mut() {
+                    rec.outgoing(&msg);
+                }
+                send(msg);
+            }
 
-                    // ---------- exit ----------
-                    Ok(Exit) => {
-                        hooks.fire_exit(&cx);
-                        info!( "🔚 runtime exit requested");
-                        break;
-                    }
+            Publish(event) => {
+                if let Some(rec) = self.recorder.as_mut() {
+                    rec.publish(&event);
+                }
+                hooks.fire_action_notify(cx, &event);
+                hooks.fire_adapter_notify(cx, &AdapterTarget::All, event.as_ref());
+                let name = event.name();
+                self.mgr.notify_topic(cx, name, Arc::clone(&event));
+                self.adapter_mgr.notify_topic_name(name, event);
+            }
+            // ---------- typed action notify ----------
+            ActionNotify { target, event } => {
+                hooks.fire_action_notify(cx, &event);
+                self.mgr.notify_target(cx, target, event);
+            }
 
-                    Err(err) => {
-                        error!( "❌ runtime channel error: {:?}", err);
-                        break;
-                    }, // bus closed
+            // ---------- typed adapter notify ----------
+            AdapterNotify { target, event } => {
+                hooks.fire_adapter_notify(cx, &target, event.as_ref());
+                self.adapter_mgr.notify_target(target, event);
+            }
+
+            // ---------- adapter control ----------
+            Adapter(ctl) => {
+                hooks.fire_adapter_control(cx, &ctl);
+                let am = &mut self.adapter_mgr;
+                match ctl {
+                    AdapterControl::Start(target) => match target {
+                        AdapterTarget::All => am.start_all(cx),
+                        AdapterTarget::Policy(p) => am.start_by_policy(cx, p),
+                        AdapterTarget::Name(n) => am.start_by_name(cx, n),
+                        AdapterTarget::Label(l) => am.start_by_label(cx, l),
+                    },
+                    AdapterControl::Stop(target) => match target {
+                        AdapterTarget::All => am.stop_all(),
+                        AdapterTarget::Policy(p) => am.stop_by_policy(p),
+                        AdapterTarget::Name(n) => am.stop_by_name(n),
+                        AdapterTarget::Label(l) => am.stop_by_label(l),
+                    },
+                    AdapterControl::Restart(target) => match target {
+                        AdapterTarget::All => am.restart_all(cx),
+                        AdapterTarget::Policy(p) => am.restart_by_policy(cx, p),
+                        AdapterTarget::Name(n) => am.restart_by_name(cx, n),
+                        AdapterTarget::Label(l) => am.restart_by_label(cx, l),
+                    },
                 }
             }
 
-            default(Duration::from_millis(100)) => {
-                drain_outgoing(&mut outq, &writer);
-                hooks.fire_tick(&cx);
-                adapter_mgr.tick();
+            // ---------- exit ----------
+            Exit => {
+                hooks.fire_exit(cx);
+                info!("🔚 runtime exit requested");
+                return Flow::Exit;
             }
         }
+        Flow::Continue
     }
 
-    // ---------- shutdown ----------
-    adapter_mgr.shutdown();
+    /// Periodic work (every ~100ms in the live runtime).
+    pub(crate) fn tick(&mut self) {
+        self.hooks.fire_tick(&self.cx);
+        self.adapter_mgr.tick();
+        self.cx.globals().flush_due();
+        if self.deferred.expired() {
+            warn!("⚠️ no global settings from Stream Deck yet; releasing held action events");
+            // stop holding; a late snapshot is still picked up through hooks/watchers
+            self.deferred.max_wait = None;
+            self.release_deferred();
+        }
+    }
 
-    info!("🔚 runtime shutdown complete");
+    /// Dispatch action events held for hydration now.
+    pub(crate) fn release_deferred(&mut self) {
+        self.deferred.release(&mut self.mgr, &self.cx, self.plugin);
+    }
 
-    Ok(())
+    /// Push pending write-behind global settings. Returns true if a push was queued.
+    pub(crate) fn flush_globals(&self) -> bool {
+        self.cx.globals().flush()
+    }
+
+    pub(crate) fn shutdown(self) {
+        self.adapter_mgr.shutdown();
+    }
+}
+
+/// Open the session recorder if enabled by the plugin builder or `SD_RECORD`.
+fn open_recorder(plugin: &Plugin, plugin_uuid: &str) -> Option<Recorder> {
+    let target = plugin
+        .record_target()
+        .cloned()
+        .or_else(RecordTarget::from_env)?;
+    match Recorder::open(&target, plugin_uuid, plugin.record_options().clone()) {
+        Ok(rec) => {
+            info!("⏺ recording session to {}", rec.path().display());
+            Some(rec)
+        }
+        Err(e) => {
+            error!("❌ failed to open session recording: {:?}", e);
+            None
+        }
+    }
 }
@@ -38,19 +38,19 @@ impl SdState {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Size {
     pub columns: i64,
     pub rows: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Copy)]
+#[derive(Debug, Clone, Serialize, Deserialize, Copy, PartialEq, Eq)]
 pub struct Coordinates {
     pub column: i64,
     pub row: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DeviceInfo {
     pub name: String,
     #[serde(rename = "type")]
@@ -58,47 +58,219 @@ pub struct DeviceInfo {
     pub size: Size,
 }
 
-#[derive(Debug, Clone)]
-pub struct TitleParameters {
-    pub font_family: String,
-    pub font_size: i64,
-    pub font_style: String,
-    pub font_underline: bool,
-    pub show_title: bool,
-    pub title_alignment: String,
-    pub title_color: String,
+/// Vertical placement of the title on the key.
+/// Values this crate does not know yet are kept in `Other`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TitleAlignment {
+    Top,
+    Middle,
+    Bottom,
+    Other(String),
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct TitleParametersWire {
-    font_family: String,
-    font_size: i64,
-    font_style: String,
-    font_underline: bool,
-    show_title: bool,
-    title_alignment: String,
-    title_color: String,
+impl TitleAlignment {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TitleAlignment::Top => "top",
+            TitleAlignment::Middle => "middle",
+            TitleAlignment::Bottom => "bottom",
+            TitleAlignment::Other(s) => s,
+        }
+    }
 }
-impl From<TitleParametersWire> for TitleParameters {
-    fn from(w: TitleParametersWire) -> Self {
-        Self {
-            font_family: w.font_family,
-            font_size: w.font_size,
-            font_style: w.font_style,
-            font_underline: w.font_underline,
-            show_title: w.show_title,
-            title_alignment: w.title_alignment,
-            title_color: w.title_color,
+
+impl From<&str> for TitleAlignment {
+    fn from(s: &str) -> Self {
+        match s {
+            "top" => TitleAlignment::Top,
+            "middle" => TitleAlignment::Middle,
+            "bottom" => TitleAlignment::Bottom,
+            other => TitleAlignment::Other(other.to_string()),
+        }
+    }
+}
+
+/// Font style as chosen in the Stream Deck title editor.
+/// `Default` is the empty string Stream Deck sends when no style was picked;
+/// styles this crate does not know yet are kept in `Other`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FontStyle {
+    Default,
+    Regular,
+    Bold,
+    Italic,
+    BoldItalic,
+    Other(String),
+}
+
+impl FontStyle {
+    #[inline]
+    pub fn is_bold(&self) -> bool {
+        matches!(self, FontStyle::Bold | FontStyle::BoldItalic)
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            FontStyle::Default => "",
+            FontStyle::Regular => "Regular",
+            FontStyle::Bold => "Bold",
+            FontStyle::Italic => "Italic",
+            FontStyle::BoldItalic => "Bold Italic",
+            FontStyle::Other(s) => s,
+        }
+    }
+}
+
+impl From<&str> for FontStyle {
+    fn from(s: &str) -> Self {
+        match s {
+            "" => FontStyle::Default,
+            "Regular" => FontStyle::Regular,
+            "Bold" => FontStyle::Bold,
+            "Italic" => FontStyle::Italic,
+            "Bold Italic" => FontStyle::BoldItalic,
+            other => FontStyle::Other(other.to_string()),
+        }
+    }
+}
+
+/// Serialize/deserialize a string-backed enum with an `Other(String)` fallback.
+macro_rules! lenient_str_serde {
+    ($ty:ty) => {
+        impl Serialize for $ty {
+            fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                s.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(d)?;
+                Ok(<$ty>::from(s.as_str()))
+            }
+        }
+    };
+}
+
+lenient_str_serde!(TitleAlignment);
+lenient_str_serde!(FontStyle);
+
+/// Error returned when a `#rrggbb` / `#rgb` color string can't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid color '{0}' (expected #rrggbb or #rgb)")]
+pub struct ParseColorError(pub String);
+
+/// An RGB color as used by `titleColor` (`#rrggbb` on the wire).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const WHITE: Rgb = Rgb::new(0xff, 0xff, 0xff);
+    pub const BLACK: Rgb = Rgb::new(0, 0, 0);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+}
+
+impl std::str::FromStr for Rgb {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseColorError(s.to_string());
+        let hex = s.trim().strip_prefix('#').ok_or_else(err)?;
+        if !hex.is_ascii() {
+            return Err(err());
+        }
+        let nibble = |i: usize| u8::from_str_radix(&hex[i..=i], 16).map_err(|_| err());
+        let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| err());
+        match hex.len() {
+            3 => Ok(Rgb::new(nibble(0)? * 17, nibble(1)? * 17, nibble(2)? * 17)),
+            6 => Ok(Rgb::new(byte(0)?, byte(2)?, byte(4)?)),
+            _ => Err(err()),
         }
     }
 }
 
+impl std::fmt::Display for Rgb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+impl Serialize for Rgb {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rgb {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(d)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// `titleColor` as sent by Stream Deck: usually `#rrggbb`, anything else is kept as-is.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TitleColor {
+    Rgb(Rgb),
+    Other(String),
+}
+
+impl TitleColor {
+    /// The color if it is in a format this crate understands.
+    pub fn rgb(&self) -> Option<Rgb> {
+        match self {
+            TitleColor::Rgb(c) => Some(*c),
+            TitleColor::Other(_) => None,
+        }
+    }
+}
+
+impl From<Rgb> for TitleColor {
+    fn from(c: Rgb) -> Self {
+        TitleColor::Rgb(c)
+    }
+}
+
+impl Serialize for TitleColor {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        match self {
+            TitleColor::Rgb(c) => c.serialize(s),
+            TitleColor::Other(raw) => s.serialize_str(raw),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TitleColor {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(d)?;
+        Ok(s.parse().map_or(TitleColor::Other(s), TitleColor::Rgb))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TitleParameters {
+    pub font_family: String,
+    pub font_size: i64,
+    pub font_style: FontStyle,
+    pub font_underline: bool,
+    pub show_title: bool,
+    pub title_alignment: TitleAlignment,
+    pub title_color: TitleColor,
+}
+
 // =========================
 // Incoming: event enum
 // =========================
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum StreamDeckEvent {
     ApplicationDidLaunch {
         application: String,
@@ -154,6 +326,17 @@ pub enum StreamDeckEvent {
         context: String,
         payload: Map<String, Value>,
     },
+    /// Reply to `getResources` (Stream Deck 7.1+).
+    DidReceiveResources {
+        action: String,
+        context: String,
+        device: String,
+        resources: Map<String, Value>,
+    },
+    /// Reply to `getSecrets` (Stream Deck 6.9+).
+    DidReceiveSecrets {
+        secrets: Map<String, Value>,
+    },
     DidReceiveSettings {
         action: String,
         context: String,
@@ -362,6 +545,13 @@ pub mod views {
         pub context: &'a str,
         pub payload: &'a Map<String, Value>,
     }
+
+    pub struct DidReceiveResources<'a> {
+        pub action: &'a str,
+        pub context: &'a str,
+        pub device: &'a str,
+        pub resources: &'a Map<String, Value>,
+    }
 }
 
 // Global
@@ -392,6 +582,10 @@ impl std::fmt::Display for StreamDeckEvent {
                 f,
                 "DidReceivePropertyInspectorMessage(action={action}, context={context})"
             ),
+            DidReceiveResources {
+                action, context, ..
+            } => write!(f, "DidReceiveResources(action={action}, context={context})"),
+            DidReceiveSecrets { .. } => write!(f, "DidReceiveSecrets"),
             DidReceiveSettings {
                 action, context, ..
             } => write!(f, "DidReceiveSettings(action={action}, context={context})"),
@@ -449,6 +643,18 @@ pub fn parse_incoming_owned(mut m: Map<String, Value>) -> Result<StreamDeckEvent
             .ok_or_else(|| format!("missing {key}"))
     }
 
+    // Move an object out of the payload, or fail naming the missing field.
+    fn take_object(payload: &mut Option<Value>, key: &str) -> Result<Map<String, Value>, String> {
+        match payload
+            .as_mut()
+            .and_then(Value::as_object_mut)
+            .and_then(|p| p.remove(key))
+        {
+            Some(Value::Object(obj)) => Ok(obj),
+            _ => Err(format!("missing payload.{key}")),
+        }
+    }
+
     // Pull top-level fields (strings are cheap to clone).
     let event = must_str(&m, "event")?.to_string();
     let action = m.get("action").and_then(Value::as_str).map(str::to_string);
@@ -458,6 +664,19 @@ pub fn parse_incoming_owned(mut m: Map<String, Value>) -> Result<StreamDeckEvent
     // Mutable access to payload so we can move things out without cloning.
     let mut payload = m.remove("payload"); // Option<Value>
 
+    // PI messages carry an arbitrary payload: hand it over untouched (a "settings"
+    // or "titleParameters" key in it is the plugin's own data).
+    if event == "sendToPlugin" {
+        return Ok(DidReceivePropertyInspectorMessage {
+            action: action.ok_or("missing action")?,
+            context: context.ok_or("missing context")?,
+            payload: match payload {
+                Some(Value::Object(obj)) => obj,
+                _ => return Err("missing payload".to_string()),
+            },
+        });
+    }
+
     // Move out settings object (no clone).
     let settings: Map<String, Value> = match payload
         .as_mut()
@@ -509,10 +728,9 @@ pub fn parse_incoming_owned(mut m: Map<String, Value>) -> Result<StreamDeckEvent
         .as_mut()
         .and_then(Value::as_object_mut)
         .and_then(|p| p.remove("titleParameters"))
-        .map(serde_json::from_value::<crate::sd_protocol::TitleParametersWire>)
+        .map(serde_json::from_value::<TitleParameters>)
         .transpose()
-        .map_err(|e| format!("bad titleParameters: {e}"))?
-        .map(Into::into);
+        .map_err(|e| format!("bad titleParameters: {e}"))?;
 
     match event.as_str() {
         "willAppear" => Ok(WillAppear {
@@ -703,24 +921,323 @@ pub fn parse_incoming_owned(mut m: Map<String, Value>) -> Result<StreamDeckEvent
                 .to_string(),
         }),
         "didReceiveGlobalSettings" => Ok(DidReceiveGlobalSettings { settings }),
-        "sendToPlugin" => Ok(DidReceivePropertyInspectorMessage {
+        "didReceiveResources" => Ok(DidReceiveResources {
             action: action.ok_or("missing action")?,
             context: context.ok_or("missing context")?,
-            payload: match payload {
-                Some(Value::Object(obj)) => obj,
-                _ => return Err("missing payload".to_string()),
-            },
+            device: device.ok_or("missing device")?,
+            resources: take_object(&mut payload, "resources")?,
+        }),
+        "didReceiveSecrets" => Ok(DidReceiveSecrets {
+            secrets: take_object(&mut payload, "secrets")?,
         }),
         "systemDidWakeUp" => Ok(SystemDidWakeUp),
         other => Err(format!("unknown StreamDeck event: {other}")),
     }
 }
 
+// =========================
+// Incoming: serialize (wire shape)
+// =========================
+
+impl StreamDeckEvent {
+    /// Wire event name (`"keyDown"`, `"sendToPlugin"`, ...).
+    pub fn event_name(&self) -> &'static str {
+        use StreamDeckEvent::*;
+        match self {
+            ApplicationDidLaunch { .. } => "applicationDidLaunch",
+            ApplicationDidTerminate { .. } => "applicationDidTerminate",
+            DeviceDidChange { .. } => "deviceDidChange",
+            DeviceDidConnect { .. } => "deviceDidConnect",
+            DeviceDidDisconnect { .. } => "deviceDidDisconnect",
+            DialDown { .. } => "dialDown",
+            DialRotate { .. } => "dialRotate",
+            DialUp { .. } => "dialUp",
+            DidReceiveDeepLink { .. } => "didReceiveDeepLink",
+            DidReceiveGlobalSettings { .. } => "didReceiveGlobalSettings",
+            DidReceivePropertyInspectorMessage { .. } => "sendToPlugin",
+            DidReceiveResources { .. } => "didReceiveResources",
+            DidReceiveSecrets { .. } => "didReceiveSecrets",
+            DidReceiveSettings { .. } => "didReceiveSettings",
+            KeyDown { .. } => "keyDown",
+            KeyUp { .. } => "keyUp",
+            PropertyInspectorDidAppear { .. } => "propertyInspectorDidAppear",
+            PropertyInspectorDidDisappear { .. } => "propertyInspectorDidDisappear",
+            SystemDidWakeUp => "systemDidWakeUp",
+            TitleParametersDidChange { .. } => "titleParametersDidChange",
+            TouchTap { .. } => "touchTap",
+            WillAppear { .. } => "willAppear",
+            WillDisappear { .. } => "willDisappear",
+        }
+    }
+
+    /// Build the JSON object Stream Deck sends for this event.
+    /// `parse_incoming_owned(ev.to_wire()) == Ok(ev)` for every event.
+    pub fn to_wire(&self) -> Map<String, Value> {
+        use StreamDeckEvent::*;
+
+        fn s(v: &str) -> Value {
+            Value::String(v.to_string())
+        }
+        fn coords(c: &Coordinates) -> Value {
+            serde_json::json!({ "column": c.column, "row": c.row })
+        }
+        fn json<T: Serialize>(v: &T) -> Value {
+            serde_json::to_value(v).unwrap_or(Value::Null)
+        }
+
+        let mut m = Map::new();
+        let mut payload = Map::new();
+        m.insert("event".into(), s(self.event_name()));
+
+        // shared "action instance" header
+        fn instance(m: &mut Map<String, Value>, action: &str, context: &str, device: &str) {
+            m.insert("action".into(), s(action));
+            m.insert("context".into(), s(context));
+            m.insert("device".into(), s(device));
+        }
+
+        match self {
+            ApplicationDidLaunch { application } | ApplicationDidTerminate { application } => {
+                payload.insert("application".into(), s(application));
+            }
+            DeviceDidChange {
+                device,
+                device_info,
+            }
+            | DeviceDidConnect {
+                device,
+                device_info,
+            } => {
+                m.insert("device".into(), s(device));
+                m.insert("deviceInfo".into(), json(device_info));
+            }
+            DeviceDidDisconnect { device } => {
+                m.insert("device".into(), s(device));
+            }
+            DialDown {
+                action,
+                context,
+                device,
+                settings,
+                controller,
+                coordinates,
+            }
+            | DialUp {
+                action,
+                context,
+                device,
+                settings,
+                controller,
+                coordinates,
+            } => {
+                instance(&mut m, action, context, device);
+                payload.insert("controller".into(), s(controller));
+                payload.insert("coordinates".into(), coords(coordinates));
+                payload.insert("settings".into(), Value::Object(settings.clone()));
+            }
+            DialRotate {
+                action,
+                context,
+                device,
+                settings,
+                controller,
+                coordinates,
+                pressed,
+                ticks,
+            } => {
+                instance(&mut m, action, context, device);
+                payload.insert("controller".into(), s(controller));
+                payload.insert("coordinates".into(), coords(coordinates));
+                payload.insert("settings".into(), Value::Object(settings.clone()));
+                payload.insert("pressed".into(), Value::Bool(*pressed));
+                payload.insert("ticks".into(), Value::from(*ticks));
+            }
+            DidReceiveDeepLink { url } => {
+                payload.insert("url".into(), s(url));
+            }
+            DidReceiveGlobalSettings { settings } => {
+                payload.insert("settings".into(), Value::Object(settings.clone()));
+            }
+            DidReceivePropertyInspectorMessage {
+                action,
+                context,
+                payload: p,
+            } => {
+                m.insert("action".into(), s(action));
+                m.insert("context".into(), s(context));
+                payload = p.clone();
+            }
+            DidReceiveResources {
+                action,
+                context,
+                device,
+                resources,
+            } => {
+                instance(&mut m, action, context, device);
+                payload.insert("resources".into(), Value::Object(resources.clone()));
+            }
+            DidReceiveSecrets { secrets } => {
+                payload.insert("secrets".into(), Value::Object(secrets.clone()));
+            }
+            DidReceiveSettings {
+                action,
+                context,
+                device,
+                settings,
+                controller,
+                is_in_multi_action,
+                state,
+                coordinates,
+            }
+            | KeyDown {
+                action,
+                context,
+                device,
+                settings,
+                controller,
+                is_in_multi_action,
+                state,
+                coordinates,
+            }
+            | KeyUp {
+                action,
+                context,
+                device,
+                settings,
+                controller,
+                is_in_multi_action,
+                state,
+                coordinates,
+            }
+            | WillAppear {
+                action,
+                context,
+                device,
+                settings,
+                controller,
+                is_in_multi_action,
+                state,
+                coordinates,
+            }
+            | WillDisappear {
+                action,
+                context,
+                device,
+                settings,
+                controller,
+                is_in_multi_action,
+                state,
+                coordinates,
+            } => {
+                instance(&mut m, action, context, device);
+                payload.insert("controller".into(), s(controller));
+                if let Some(c) = coordinates {
+                    payload.insert("coordinates".into(), coords(c));
+                }
+                payload.insert("isInMultiAction".into(), Value::Bool(*is_in_multi_action));
+                payload.insert("settings".into(), Value::Object(settings.clone()));
+                if let Some(st) = state {
+                    payload.insert("state".into(), Value::from(st.as_u8()));
+                }
+            }
+            PropertyInspectorDidAppear {
+                action,
+                context,
+                device,
+            }
+            | PropertyInspectorDidDisappear {
+                action,
+                context,
+                device,
+            } => {
+                instance(&mut m, action, context, device);
+            }
+            SystemDidWakeUp => {}
+            TitleParametersDidChange {
+                action,
+                context,
+                device,
+                settings,
+                controller,
+                coordinates,
+                state,
+                title,
+                title_parameters,
+            } => {
+                instance(&mut m, action, context, device);
+                payload.insert("controller".into(), s(controller));
+                payload.insert("coordinates".into(), coords(coordinates));
+                payload.insert("settings".into(), Value::Object(settings.clone()));
+                if let Some(st) = state {
+                    payload.insert("state".into(), Value::from(st.as_u8()));
+                }
+                payload.insert("title".into(), s(title));
+                payload.insert("titleParameters".into(), json(title_parameters));
+            }
+            TouchTap {
+                action,
+                context,
+                device,
+                settings,
+                controller,
+                coordinates,
+                hold,
+                tap_pos,
+            } => {
+                instance(&mut m, action, context, device);
+                payload.insert("controller".into(), s(controller));
+                payload.insert("coordinates".into(), coords(coordinates));
+                payload.insert("settings".into(), Value::Object(settings.clone()));
+                payload.insert("hold".into(), Value::Bool(*hold));
+                payload.insert("tapPos".into(), serde_json::json!([tap_pos.0, tap_pos.1]));
+            }
+        }
+
+        let has_payload = !payload.is_empty()
+            || matches!(
+                self,
+                DidReceivePropertyInspectorMessage { .. }
+                    | DidReceiveGlobalSettings { .. }
+                    | DidReceiveResources { .. }
+                    | DidReceiveSecrets { .. }
+            );
+        if has_payload {
+            m.insert("payload".into(), Value::Object(payload));
+        }
+        m
+    }
+}
+
+impl Serialize for StreamDeckEvent {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        self.to_wire().serialize(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for StreamDeckEvent {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let m = Map::<String, Value>::deserialize(d)?;
+        parse_incoming_owned(m).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Serialize an incoming event back to the exact wire JSON (for recording/fake hosts).
+pub fn serialize_incoming(ev: &StreamDeckEvent) -> serde_json::Result<String> {
+    serde_json::to_string(&ev.to_wire())
+}
+
+/// Parse an incoming event from wire JSON text.
+pub fn parse_incoming(text: &str) -> Result<StreamDeckEvent, String> {
+    serde_json::from_str::<Map<String, Value>>(text)
+        .map_err(|e| format!("json parse error: {e}"))
+        .and_then(parse_incoming_owned)
+}
+
 // =========================
 // Outgoing: typed payloads
 // =========================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Target {
     Both,
@@ -728,7 +1245,7 @@ pub enum Target {
     Software,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SetTitlePayload {
     /// Title to display; None resets to the user-configured title.
     pub title: Option<String>,
@@ -738,7 +1255,7 @@ pub struct SetTitlePayload {
     pub target: Option<Target>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SetImagePayload {
     /// Path or base64 with data URI.
     pub image: Option<String>,
@@ -748,7 +1265,7 @@ pub struct SetImagePayload {
     pub target: Option<Target>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TriggerPayload {
     pub long_touch: Option<String>,
@@ -757,15 +1274,33 @@ pub struct TriggerPayload {
     pub touch: Option<String>,
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SwitchToProfilePayload {
+    /// Profile name as listed in the manifest; None switches back to the previous profile.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Zero-based page to show (Stream Deck 6.5+).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+}
+
 // =========================
 // Outgoing: public enum
 // =========================
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Outgoing {
     GetGlobalSettings {
         context: String,
     },
+    /// Ask for the action's resources; answered by `didReceiveResources` (Stream Deck 7.1+).
+    GetResources {
+        context: String,
+    },
+    /// Ask for the plugin's secrets; answered by `didReceiveSecrets` (Stream Deck 6.9+).
+    GetSecrets {
+        context: String,
+    },
     GetSettings {
         context: String,
     },
@@ -795,6 +1330,11 @@ pub enum Outgoing {
         context: String,
         payload: SetImagePayload,
     },
+    /// Replace the action's resources (Stream Deck 7.1+).
+    SetResources {
+        context: String,
+        payload: Map<String, Value>,
+    },
     SetSettings {
         context: String,
         payload: Map<String, Value>,
@@ -817,6 +1357,11 @@ pub enum Outgoing {
     ShowOk {
         context: String,
     },
+    SwitchToProfile {
+        context: String,
+        device: String,
+        payload: SwitchToProfilePayload,
+    },
 }
 
 // Internal: serializable shape
@@ -826,6 +1371,12 @@ enum WireOutgoing<'a> {
     #[serde(rename = "getGlobalSettings")]
     GetGlobalSettings { context: &'a str },
 
+    #[serde(rename = "getResources")]
+    GetResources { context: &'a str },
+
+    #[serde(rename = "getSecrets")]
+    GetSecrets { context: &'a str },
+
     #[serde(rename = "getSettings")]
     GetSettings { context: &'a str },
 
@@ -865,6 +1416,12 @@ enum WireOutgoing<'a> {
         payload: &'a SetImagePayload,
     },
 
+    #[serde(rename = "setResources")]
+    SetResources {
+        context: &'a str,
+        payload: &'a Map<String, Value>,
+    },
+
     #[serde(rename = "setSettings")]
     SetSettings {
         context: &'a str,
@@ -894,6 +1451,13 @@ enum WireOutgoing<'a> {
 
     #[serde(rename = "showOk")]
     ShowOk { context: &'a str },
+
+    #[serde(rename = "switchToProfile")]
+    SwitchToProfile {
+        context: &'a str,
+        device: &'a str,
+        payload: &'a SwitchToProfilePayload,
+    },
 }
 
 #[derive(Serialize)]
@@ -918,6 +1482,8 @@ impl<'a> From<&'a Outgoing> for WireOutgoing<'a> {
         use Outgoing::*;
         match o {
             GetGlobalSettings { context } => WireOutgoing::GetGlobalSettings { context },
+            GetResources { context } => WireOutgoing::GetResources { context },
+            GetSecrets { context } => WireOutgoing::GetSecrets { context },
             GetSettings { context } => WireOutgoing::GetSettings { context },
             LogMessage { message } => WireOutgoing::LogMessage {
                 payload: WireLogMessage { message },
@@ -937,6 +1503,7 @@ impl<'a> From<&'a Outgoing> for WireOutgoing<'a> {
                 WireOutgoing::SetGlobalSettings { context, payload }
             }
             SetImage { context, payload } => WireOutgoing::SetImage { context, payload },
+            SetResources { context, payload } => WireOutgoing::SetResources { context, payload },
             SetSettings { context, payload } => WireOutgoing::SetSettings { context, payload },
             SetState { context, state } => WireOutgoing::SetState {
                 context,
@@ -948,6 +1515,43 @@ impl<'a> From<&'a Outgoing> for WireOutgoing<'a> {
             }
             ShowAlert { context } => WireOutgoing::ShowAlert { context },
             ShowOk { context } => WireOutgoing::ShowOk { context },
+            SwitchToProfile {
+                context,
+                device,
+                payload,
+            } => WireOutgoing::SwitchToProfile {
+                context,
+                device,
+                payload,
+            },
+        }
+    }
+}
+
+impl Outgoing {
+    /// Wire event name (`"setTitle"`, `"switchToProfile"`, ...).
+    pub fn event_name(&self) -> &'static str {
+        use Outgoing::*;
+        match self {
+            GetGlobalSettings { .. } => "getGlobalSettings",
+            GetResources { .. } => "getResources",
+            GetSecrets { .. } => "getSecrets",
+            GetSettings { .. } => "getSettings",
+            LogMessage { .. } => "logMessage",
+            OpenUrl { .. } => "openUrl",
+            SendToPropertyInspector { .. } => "sendToPropertyInspector",
+            SetFeedback { .. } => "setFeedback",
+            SetFeedbackLayout { .. } => "setFeedbackLayout",
+            SetGlobalSettings { .. } => "setGlobalSettings",
+            SetImage { .. } => "setImage",
+            SetResources { .. } => "setResources",
+            SetSettings { .. } => "setSettings",
+            SetState { .. } => "setState",
+            SetTitle { .. } => "setTitle",
+            SetTriggerDescription { .. } => "setTriggerDescription",
+            ShowAlert { .. } => "showAlert",
+            ShowOk { .. } => "showOk",
+            SwitchToProfile { .. } => "switchToProfile",
         }
     }
 }
@@ -957,6 +1561,180 @@ pub fn serialize_outgoing(msg: &Outgoing) -> serde_json::Result<String> {
     serde_json::to_string(&w)
 }
 
+/// Parse an outgoing message from wire JSON text (captured traffic, fake hosts).
+pub fn parse_outgoing(text: &str) -> serde_json::Result<Outgoing> {
+    serde_json::from_str(text)
+}
+
+impl Serialize for Outgoing {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        WireOutgoing::from(self).serialize(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for Outgoing {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        WireOutgoingOwned::deserialize(d).map(Into::into)
+    }
+}
+
+// Internal: owned mirror of `WireOutgoing` for the parse direction
+#[derive(Deserialize)]
+#[serde(tag = "event")]
+enum WireOutgoingOwned {
+    #[serde(rename = "getGlobalSettings")]
+    GetGlobalSettings { context: String },
+
+    #[serde(rename = "getResources")]
+    GetResources { context: String },
+
+    #[serde(rename = "getSecrets")]
+    GetSecrets { context: String },
+
+    #[serde(rename = "getSettings")]
+    GetSettings { context: String },
+
+    #[serde(rename = "logMessage")]
+    LogMessage { payload: OwnedLogMessage },
+
+    #[serde(rename = "openUrl")]
+    OpenUrl { payload: OwnedOpenUrl },
+
+    #[serde(rename = "sendToPropertyInspector")]
+    SendToPropertyInspector { context: String, payload: Value },
+
+    #[serde(rename = "setFeedback")]
+    SetFeedback { context: String, payload: Value },
+
+    #[serde(rename = "setFeedbackLayout")]
+    SetFeedbackLayout {
+        context: String,
+        payload: OwnedLayout,
+    },
+
+    #[serde(rename = "setGlobalSettings")]
+    SetGlobalSettings {
+        context: String,
+        payload: Map<String, Value>,
+    },
+
+    #[serde(rename = "setImage")]
+    SetImage {
+        context: String,
+        payload: SetImagePayload,
+    },
+
+    #[serde(rename = "setResources")]
+    SetResources {
+        context: String,
+        payload: Map<String, Value>,
+    },
+
+    #[serde(rename = "setSettings")]
+    SetSettings {
+        context: String,
+        payload: Map<String, Value>,
+    },
+
+    #[serde(rename = "setState")]
+    SetState {
+        context: String,
+        payload: OwnedState,
+    },
+
+    #[serde(rename = "setTitle")]
+    SetTitle {
+        context: String,
+        payload: SetTitlePayload,
+    },
+
+    #[serde(rename = "setTriggerDescription")]
+    SetTriggerDescription {
+        context: String,
+        payload: TriggerPayload,
+    },
+
+    #[serde(rename = "showAlert")]
+    ShowAlert { context: String },
+
+    #[serde(rename = "showOk")]
+    ShowOk { context: String },
+
+    #[serde(rename = "switchToProfile")]
+    SwitchToProfile {
+        context: String,
+        device: String,
+        #[serde(default)]
+        payload: SwitchToProfilePayload,
+    },
+}
+
+#[derive(Deserialize)]
+struct OwnedLogMessage {
+    message: String,
+}
+#[derive(Deserialize)]
+struct OwnedOpenUrl {
+    url: String,
+}
+#[derive(Deserialize)]
+struct OwnedLayout {
+    layout: String,
+}
+#[derive(Deserialize)]
+struct OwnedState {
+    state: SdState,
+}
+
+impl From<WireOutgoingOwned> for Outgoing {
+    fn from(w: WireOutgoingOwned) -> Self {
+        use WireOutgoingOwned as W;
+        match w {
+            W::GetGlobalSettings { context } => Outgoing::GetGlobalSettings { context },
+            W::GetResources { context } => Outgoing::GetResources { context },
+            W::GetSecrets { context } => Outgoing::GetSecrets { context },
+            W::GetSettings { context } => Outgoing::GetSettings { context },
+            W::LogMessage { payload } => Outgoing::LogMessage {
+                message: payload.message,
+            },
+            W::OpenUrl { payload } => Outgoing::OpenUrl { url: payload.url },
+            W::SendToPropertyInspector { context, payload } => {
+                Outgoing::SendToPropertyInspector { context, payload }
+            }
+            W::SetFeedback { context, payload } => Outgoing::SetFeedback { context, payload },
+            W::SetFeedbackLayout { context, payload } => Outgoing::SetFeedbackLayout {
+                context,
+                layout: payload.layout,
+            },
+            W::SetGlobalSettings { context, payload } => {
+                Outgoing::SetGlobalSettings { context, payload }
+            }
+            W::SetImage { context, payload } => Outgoing::SetImage { context, payload },
+            W::SetResources { context, payload } => Outgoing::SetResources { context, payload },
+            W::SetSettings { context, payload } => Outgoing::SetSettings { context, payload },
+            W::SetState { context, payload } => Outgoing::SetState {
+                context,
+                state: payload.state,
+            },
+            W::SetTitle { context, payload } => Outgoing::SetTitle { context, payload },
+            W::SetTriggerDescription { context, payload } => {
+                Outgoing::SetTriggerDescription { context, payload }
+            }
+            W::ShowAlert { context } => Outgoing::ShowAlert { context },
+            W::ShowOk { context } => Outgoing::ShowOk { context },
+            W::SwitchToProfile {
+                context,
+                device,
+                payload,
+            } => Outgoing::SwitchToProfile {
+                context,
+                device,
+                payload,
+            },
+        }
+    }
+}
+
 // =========================
 // Thin, typed client
 // =========================
@@ -990,6 +1768,18 @@ impl SdClient {
             context: self.plugin_uuid.clone(),
         });
     }
+    /// Ask for the action's resources; the reply goes to that action's `did_receive_resources`.
+    pub fn get_resources(&self, context: impl Into<String>) {
+        self.send(Outgoing::GetResources {
+            context: context.into(),
+        });
+    }
+    /// Ask for the plugin's secrets; the reply arrives as `DidReceiveSecrets`.
+    pub fn get_secrets(&self) {
+        self.send(Outgoing::GetSecrets {
+            context: self.plugin_uuid.clone(),
+        });
+    }
     pub fn get_settings(&self, context: impl Into<String>) {
         self.send(Outgoing::GetSettings {
             context: context.into(),
@@ -1009,6 +1799,15 @@ impl SdClient {
             payload,
         });
     }
+    /// Serialize `msg` and send it to the property inspector.
+    pub fn send_to_property_inspector_t<T: Serialize>(
+        &self,
+        context: impl Into<String>,
+        msg: &T,
+    ) -> serde_json::Result<()> {
+        self.send_to_property_inspector(context, serde_json::to_value(msg)?);
+        Ok(())
+    }
     pub fn set_feedback(&self, context: impl Into<String>, payload: Value) {
         self.send(Outgoing::SetFeedback {
             context: context.into(),
@@ -1043,6 +1842,12 @@ impl SdClient {
             },
         });
     }
+    pub fn set_resources(&self, context: impl Into<String>, resources: Map<String, Value>) {
+        self.send(Outgoing::SetResources {
+            context: context.into(),
+            payload: resources,
+        });
+    }
     pub fn set_settings(&self, context: impl Into<String>, settings: Map<String, Value>) {
         self.send(Outgoing::SetSettings {
             context: context.into(),
@@ -1111,4 +1916,668 @@ impl SdClient {
             context: context.into(),
         });
     }
+    /// Switch `device` to one of the plugin's profiles (optionally to a page).
+    /// Only works for profiles declared in the manifest.
+    pub fn switch_to_profile(
+        &self,
+        device: impl Into<String>,
+        profile: Option<String>,
+        page: Option<u32>,
+    ) {
+        self.send(Outgoing::SwitchToProfile {
+            context: self.plugin_uuid.clone(),
+            device: device.into(),
+            payload: SwitchToProfilePayload { profile, page },
+        });
+    }
+    /// Switch `device` back to the profile that was active before.
+    pub fn switch_to_previous_profile(&self, device: impl Into<String>) {
+        self.switch_to_profile(device, None, None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn title_event(params: Value) -> String {
+        json!({
+            "event": "titleParametersDidChange",
+            "action": "com.example.act",
+            "context": "ctx",
+            "device": "dev",
+            "payload": {
+                "controller": "Keypad",
+                "coordinates": { "column": 1, "row": 2 },
+                "settings": {},
+                "state": 0,
+                "title": "Hi",
+                "titleParameters": params,
+            }
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn title_parameters_known_values() {
+        let ev = parse_incoming(&title_event(json!({
+            "fontFamily": "",
+            "fontSize": 12,
+            "fontStyle": "Bold Italic",
+            "fontUnderline": false,
+            "showTitle": true,
+            "titleAlignment": "bottom",
+            "titleColor": "#ff8000",
+        })))
+        .unwrap();
+        let StreamDeckEvent::TitleParametersDidChange {
+            title_parameters: p,
+            ..
+        } = ev
+        else {
+            panic!("wrong event: {ev:?}");
+        };
+        assert_eq!(p.font_style, FontStyle::BoldItalic);
+        assert!(p.font_style.is_bold());
+        assert_eq!(p.title_alignment, TitleAlignment::Bottom);
+        assert_eq!(p.title_color.rgb(), Some(Rgb::new(0xff, 0x80, 0)));
+    }
+
+    #[test]
+    fn title_parameters_unknown_values_still_parse() {
+        let params = json!({
+            "fontFamily": "Verdana",
+            "fontSize": 9,
+            "fontStyle": "Condensed",
+            "fontUnderline": true,
+            "showTitle": true,
+            "titleAlignment": "baseline",
+            "titleColor": "rgba(1,2,3,0.5)",
+        });
+        let ev = parse_incoming(&title_event(params.clone())).unwrap();
+        let StreamDeckEvent::TitleParametersDidChange {
+            title_parameters: p,
+            ..
+        } = ev
+        else {
+            panic!("wrong event: {ev:?}");
+        };
+        assert_eq!(p.font_style, FontStyle::Other("Condensed".into()));
+        assert_eq!(p.title_alignment, TitleAlignment::Other("baseline".into()));
+        assert_eq!(p.title_color, TitleColor::Other("rgba(1,2,3,0.5)".into()));
+        // unknown values go back out unchanged
+        assert_eq!(serde_json::to_value(&p).unwrap(), params);
+    }
+
+    // ---- outgoing fixtures ----
+
+    /// Captured wire JSON for every outgoing command, keyed by event name.
+    const OUTGOING_FIXTURES: &[(&str, &str)] = &[
+        (
+            "getGlobalSettings",
+            include_str!("../fixtures/outgoing/getGlobalSettings.json"),
+        ),
+        (
+            "getResources",
+            include_str!("../fixtures/outgoing/getResources.json"),
+        ),
+        (
+            "getSecrets",
+            include_str!("../fixtures/outgoing/getSecrets.json"),
+        ),
+        (
+            "getSettings",
+            include_str!("../fixtures/outgoing/getSettings.json"),
+        ),
+        (
+            "logMessage",
+            include_str!("../fixtures/outgoing/logMessage.json"),
+        ),
+        ("openUrl", include_str!("../fixtures/outgoing/openUrl.json")),
+        (
+            "sendToPropertyInspector",
+            include_str!("../fixtures/outgoing/sendToPropertyInspector.json"),
+        ),
+        (
+            "setFeedback",
+            include_str!("../fixtures/outgoing/setFeedback.json"),
+        ),
+        (
+            "setFeedbackLayout",
+            include_str!("../fixtures/outgoing/setFeedbackLayout.json"),
+        ),
+        (
+            "setGlobalSettings",
+            include_str!("../fixtures/outgoing/setGlobalSettings.json"),
+        ),
+        (
+            "setImage",
+            include_str!("../fixtures/outgoing/setImage.json"),
+        ),
+        (
+            "setResources",
+            include_str!("../fixtures/outgoing/setResources.json"),
+        ),
+        (
+            "setSettings",
+            include_str!("../fixtures/outgoing/setSettings.json"),
+        ),
+        (
+            "setState",
+            include_str!("../fixtures/outgoing/setState.json"),
+        ),
+        (
+            "setTitle",
+            include_str!("../fixtures/outgoing/setTitle.json"),
+        ),
+        (
+            "setTriggerDescription",
+            include_str!("../fixtures/outgoing/setTriggerDescription.json"),
+        ),
+        (
+            "showAlert",
+            include_str!("../fixtures/outgoing/showAlert.json"),
+        ),
+        ("showOk", include_str!("../fixtures/outgoing/showOk.json")),
+        (
+            "switchToProfile",
+            include_str!("../fixtures/outgoing/switchToProfile.json"),
+        ),
+    ];
+
+    #[test]
+    fn outgoing_fixtures_round_trip() {
+        for (name, text) in OUTGOING_FIXTURES {
+            let msg = parse_outgoing(text).unwrap_or_else(|e| panic!("{name}: {e}"));
+            assert_eq!(msg.event_name(), *name);
+
+            let wire: Value = serde_json::from_str(&serialize_outgoing(&msg).unwrap()).unwrap();
+            let captured: Value = serde_json::from_str(text).unwrap();
+            assert_eq!(
+                wire, captured,
+                "{name} does not serialize back to the fixture"
+            );
+            assert_eq!(parse_outgoing(&wire.to_string()).unwrap(), msg);
+        }
+    }
+
+    #[test]
+    fn outgoing_fixtures_cover_every_command() {
+        // adding a variant breaks this match until it names a fixture, and the
+        // build breaks until that fixture exists
+        macro_rules! fixture {
+            ($name:literal) => {
+                (
+                    $name,
+                    include_str!(concat!("../fixtures/outgoing/", $name, ".json")),
+                )
+            };
+        }
+        fn fixture_for(o: &Outgoing) -> (&'static str, &'static str) {
+            use Outgoing::*;
+            match o {
+                GetGlobalSettings { .. } => fixture!("getGlobalSettings"),
+                GetResources { .. } => fixture!("getResources"),
+                GetSecrets { .. } => fixture!("getSecrets"),
+                GetSettings { .. } => fixture!("getSettings"),
+                LogMessage { .. } => fixture!("logMessage"),
+                OpenUrl { .. } => fixture!("openUrl"),
+                SendToPropertyInspector { .. } => fixture!("sendToPropertyInspector"),
+                SetFeedback { .. } => fixture!("setFeedback"),
+                SetFeedbackLayout { .. } => fixture!("setFeedbackLayout"),
+                SetGlobalSettings { .. } => fixture!("setGlobalSettings"),
+                SetImage { .. } => fixture!("setImage"),
+                SetResources { .. } => fixture!("setResources"),
+                SetSettings { .. } => fixture!("setSettings"),
+                SetState { .. } => fixture!("setState"),
+                SetTitle { .. } => fixture!("setTitle"),
+                SetTriggerDescription { .. } => fixture!("setTriggerDescription"),
+                ShowAlert { .. } => fixture!("showAlert"),
+                ShowOk { .. } => fixture!("showOk"),
+                SwitchToProfile { .. } => fixture!("switchToProfile"),
+            }
+        }
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/outgoing");
+
+        for (name, text) in OUTGOING_FIXTURES {
+            let (fixture, expected) = fixture_for(&parse_outgoing(text).unwrap());
+            assert_eq!(fixture, *name, "{name}.json holds a {fixture} message");
+            assert_eq!(expected, *text);
+        }
+        // every fixture on disk is checked above
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            let stem = path.file_stem().unwrap().to_str().unwrap();
+            assert!(
+                OUTGOING_FIXTURES.iter().any(|(name, _)| *name == stem),
+                "{} is not in OUTGOING_FIXTURES",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn switch_to_previous_profile_omits_payload_fields() {
+        let msg = Outgoing::SwitchToProfile {
+            context: "plugin".into(),
+            device: "dev".into(),
+            payload: SwitchToProfilePayload::default(),
+        };
+        let wire: Value = serde_json::to_value(&msg).unwrap();
+        assert_eq!(
+            wire,
+            json!({ "event": "switchToProfile", "context": "plugin", "device": "dev", "payload": {} })
+        );
+        // Stream Deck also accepts the message without a payload
+        let bare = r#"{"event":"switchToProfile","context":"plugin","device":"dev"}"#;
+        assert_eq!(parse_outgoing(bare).unwrap(), msg);
+    }
+
+    #[test]
+    fn default_font_style_is_empty_string() {
+        let v = serde_json::to_value(FontStyle::Default).unwrap();
+        assert_eq!(v, json!(""));
+        assert_eq!(
+            serde_json::from_value::<FontStyle>(v).unwrap(),
+            FontStyle::Default
+        );
+    }
+}
+
+#[cfg(test)]
+mod proptests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn text() -> impl Strategy<Value = String> {
+        "[a-zA-Z0-9 ._:/-]{0,12}"
+    }
+
+    fn json_value() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::Bool),
+            any::<i64>().prop_map(Value::from),
+            text().prop_map(Value::String),
+        ];
+        leaf.prop_recursive(3, 16, 4, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+                prop::collection::btree_map(text(), inner, 0..4)
+                    .prop_map(|m| Value::Object(m.into_iter().collect())),
+            ]
+        })
+    }
+
+    /// Objects whose keys may collide with payload fields the parser looks at.
+    fn object() -> impl Strategy<Value = Map<String, Value>> {
+        let key = prop_oneof![
+            text(),
+            Just("settings".to_string()),
+            Just("titleParameters".to_string()),
+            Just("state".to_string()),
+        ];
+        prop::collection::btree_map(key, json_value(), 0..4).prop_map(|m| m.into_iter().collect())
+    }
+
+    fn coords() -> impl Strategy<Value = Coordinates> {
+        (any::<i64>(), any::<i64>()).prop_map(|(column, row)| Coordinates { column, row })
+    }
+
+    fn sd_state() -> impl Strategy<Value = Option<SdState>> {
+        prop_oneof![
+            Just(None),
+            Just(Some(SdState::Primary)),
+            Just(Some(SdState::Secondary)),
+        ]
+    }
+
+    fn device_info() -> impl Strategy<Value = DeviceInfo> {
+        (text(), any::<i64>(), any::<i64>(), any::<i64>()).prop_map(|(name, t, columns, rows)| {
+            DeviceInfo {
+                name,
+                r#type: t,
+                size: Size { columns, rows },
+            }
+        })
+    }
+
+    fn title_parameters() -> impl Strategy<Value = TitleParameters> {
+        let style = prop_oneof![
+            Just(FontStyle::Default),
+            Just(FontStyle::Regular),
+            Just(FontStyle::Bold),
+            Just(FontStyle::Italic),
+            Just(FontStyle::BoldItalic),
+            "x[a-z]{0,6}".prop_map(FontStyle::Other),
+        ];
+        let align = prop_oneof![
+            Just(TitleAlignment::Top),
+            Just(TitleAlignment::Middle),
+            Just(TitleAlignment::Bottom),
+            "x[a-z]{0,6}".prop_map(TitleAlignment::Other),
+        ];
+        let color = prop_oneof![
+            any::<(u8, u8, u8)>().prop_map(|(r, g, b)| TitleColor::Rgb(Rgb::new(r, g, b))),
+            "rgba\\([0-9,]{0,8}\\)".prop_map(TitleColor::Other),
+        ];
+        (
+            text(),
+            any::<i64>(),
+            style,
+            any::<bool>(),
+            any::<bool>(),
+            align,
+            color,
+        )
+            .prop_map(
+                |(font_family, font_size, font_style, font_underline, show_title, a, c)| {
+                    TitleParameters {
+                        font_family,
+                        font_size,
+                        font_style,
+                        font_underline,
+                        show_title,
+                        title_alignment: a,
+                        title_color: c,
+                    }
+                },
+            )
+    }
+
+    /// `(action, context, device, settings, controller)`
+    fn instance() -> impl Strategy<Value = (String, String, String, Map<String, Value>, String)> {
+        (text(), text(), text(), object(), text())
+    }
+
+    fn event() -> impl Strategy<Value = StreamDeckEvent> {
+        use StreamDeckEvent::*;
+        // key-like events share one shape
+        let keyish = (
+            0..5u8,
+            instance(),
+            any::<bool>(),
+            sd_state(),
+            prop::option::of(coords()),
+        )
+            .prop_map(
+                |(
+                    kind,
+                    (action, context, device, settings, controller),
+                    is_in_multi_action,
+                    state,
+                    coordinates,
+                )| {
+                    match kind {
+                        0 => WillAppear {
+                            action,
+                            context,
+                            device,
+                            settings,
+                            controller,
+                            is_in_multi_action,
+                            state,
+                            coordinates,
+                        },
+                        1 => WillDisappear {
+                            action,
+                            context,
+                            device,
+                            settings,
+                            controller,
+                            is_in_multi_action,
+                            state,
+                            coordinates,
+                        },
+                        2 => KeyDown {
+                            action,
+                            context,
+                            device,
+                            settings,
+                            controller,
+                            is_in_multi_action,
+                            state,
+                            coordinates,
+                        },
+                        3 => KeyUp {
+                            action,
+                            context,
+                            device,
+                            settings,
+                            controller,
+                            is_in_multi_action,
+                            state,
+                            coordinates,
+                        },
+                        _ => DidReceiveSettings {
+                            action,
+                            context,
+                            device,
+                            settings,
+                            controller,
+                            is_in_multi_action,
+                            state,
+                            coordinates,
+                        },
+                    }
+                },
+            );
+        let dial = (any::<bool>(), instance(), coords()).prop_map(
+            |(down, (action, context, device, settings, controller), coordinates)| {
+                if down {
+                    DialDown {
+                        action,
+                        context,
+                        device,
+                        settings,
+                        controller,
+                        coordinates,
+                    }
+                } else {
+                    DialUp {
+                        action,
+                        context,
+                        device,
+                        settings,
+                        controller,
+                        coordinates,
+                    }
+                }
+            },
+        );
+        let rotate = (instance(), coords(), any::<bool>(), any::<i64>()).prop_map(
+            |((action, context, device, settings, controller), coordinates, pressed, ticks)| {
+                DialRotate {
+                    action,
+                    context,
+                    device,
+                    settings,
+                    controller,
+                    coordinates,
+                    pressed,
+                    ticks,
+                }
+            },
+        );
+        let touch = (instance(), coords(), any::<bool>(), any::<(i64, i64)>()).prop_map(
+            |((action, context, device, settings, controller), coordinates, hold, tap_pos)| {
+                TouchTap {
+                    action,
+                    context,
+                    device,
+                    settings,
+                    controller,
+                    coordinates,
+                    hold,
+                    tap_pos,
+                }
+            },
+        );
+        let title = (instance(), coords(), sd_state(), text(), title_parameters()).prop_map(
+            |(
+                (action, context, device, settings, controller),
+                coordinates,
+                state,
+                title,
+                title_parameters,
+            )| {
+                TitleParametersDidChange {
+                    action,
+                    context,
+                    device,
+                    settings,
+                    controller,
+                    coordinates,
+                    state,
+                    title,
+                    title_parameters,
+                }
+            },
+        );
+        let pi = (any::<bool>(), text(), text(), text()).prop_map(
+            |(appear, action, context, device)| {
+                if appear {
+                    PropertyInspectorDidAppear {
+                        action,
+                        context,
+                        device,
+                    }
+                } else {
+                    PropertyInspectorDidDisappear {
+                        action,
+                        context,
+                        device,
+                    }
+                }
+            },
+        );
+        let globalish = prop_oneof![
+            text().prop_map(|application| ApplicationDidLaunch { application }),
+            text().prop_map(|application| ApplicationDidTerminate { application }),
+            (text(), device_info()).prop_map(|(device, device_info)| DeviceDidChange {
+                device,
+                device_info
+            }),
+            (text(), device_info()).prop_map(|(device, device_info)| DeviceDidConnect {
+                device,
+                device_info
+            }),
+            text().prop_map(|device| DeviceDidDisconnect { device }),
+            text().prop_map(|url| DidReceiveDeepLink { url }),
+            object().prop_map(|settings| DidReceiveGlobalSettings { settings }),
+            object().prop_map(|secrets| DidReceiveSecrets { secrets }),
+            Just(SystemDidWakeUp),
+        ];
+        let messages = prop_oneof![
+            (text(), text(), object()).prop_map(|(action, context, payload)| {
+                DidReceivePropertyInspectorMessage {
+                    action,
+                    context,
+                    payload,
+                }
+            }),
+            (text(), text(), text(), object()).prop_map(|(action, context, device, resources)| {
+                DidReceiveResources {
+                    action,
+                    context,
+                    device,
+                    resources,
+                }
+            }),
+        ];
+        prop_oneof![keyish, dial, rotate, touch, title, pi, globalish, messages]
+    }
+
+    fn outgoing() -> impl Strategy<Value = Outgoing> {
+        use Outgoing::*;
+        let target = prop::option::of(prop_oneof![
+            Just(Target::Both),
+            Just(Target::Hardware),
+            Just(Target::Software),
+        ]);
+        let opt_text = || prop::option::of(text());
+        prop_oneof![
+            text().prop_map(|context| GetGlobalSettings { context }),
+            text().prop_map(|context| GetResources { context }),
+            text().prop_map(|context| GetSecrets { context }),
+            text().prop_map(|context| GetSettings { context }),
+            text().prop_map(|message| LogMessage { message }),
+            text().prop_map(|url| OpenUrl { url }),
+            (text(), json_value())
+                .prop_map(|(context, payload)| SendToPropertyInspector { context, payload }),
+            (text(), json_value()).prop_map(|(context, payload)| SetFeedback { context, payload }),
+            (text(), text()).prop_map(|(context, layout)| SetFeedbackLayout { context, layout }),
+            (text(), object())
+                .prop_map(|(context, payload)| SetGlobalSettings { context, payload }),
+            (text(), opt_text(), sd_state(), target.clone()).prop_map(
+                |(context, image, state, target)| SetImage {
+                    context,
+                    payload: SetImagePayload {
+                        image,
+                        state,
+                        target
+                    }
+                }
+            ),
+            (text(), object()).prop_map(|(context, payload)| SetResources { context, payload }),
+            (text(), object()).prop_map(|(context, payload)| SetSettings { context, payload }),
+            (
+                text(),
+                prop_oneof![Just(SdState::Primary), Just(SdState::Secondary)]
+            )
+                .prop_map(|(context, state)| SetState { context, state }),
+            (text(), opt_text(), sd_state(), target).prop_map(|(context, title, state, target)| {
+                SetTitle {
+                    context,
+                    payload: SetTitlePayload {
+                        title,
+                        state,
+                        target,
+                    },
+                }
+            }),
+            (text(), opt_text(), opt_text(), opt_text(), opt_text()).prop_map(
+                |(context, long_touch, push, rotate, touch)| {
+                    SetTriggerDescription {
+                        context,
+                        payload: TriggerPayload {
+                            long_touch,
+                            push,
+                            rotate,
+                            touch,
+                        },
+                    }
+                }
+            ),
+            text().prop_map(|context| ShowAlert { context }),
+            text().prop_map(|context| ShowOk { context }),
+            (text(), text(), opt_text(), prop::option::of(any::<u32>())).prop_map(
+                |(context, device, profile, page)| SwitchToProfile {
+                    context,
+                    device,
+                    payload: SwitchToProfilePayload { profile, page },
+                }
+            ),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn incoming_round_trips_through_wire_map(ev in event()) {
+            prop_assert_eq!(parse_incoming_owned(ev.to_wire()), Ok(ev));
+        }
+
+        #[test]
+        fn incoming_round_trips_through_text(ev in event()) {
+            let text = serialize_incoming(&ev).unwrap();
+            prop_assert_eq!(parse_incoming(&text), Ok(ev));
+        }
+
+        #[test]
+        fn outgoing_round_trips_through_text(msg in outgoing()) {
+            let text = serialize_outgoing(&msg).unwrap();
+            prop_assert_eq!(parse_outgoing(&text).unwrap(), msg);
+        }
+    }
 }
@@ -0,0 +1,265 @@
+// title.rs
+//! Fit a title into the key area without overflowing.
+//!
+//! Stream Deck renders titles itself, so this is an estimate: glyph widths are
+//! approximated from the font size (Stream Deck sizes are relative to a 72px key).
+//! Good enough to keep generated titles (counters, names, status text) readable.
+
+use crate::sd_protocol::TitleParameters;
+
+/// Key size title font sizes are relative to, in pixels.
+pub const KEY_PX_1X: u32 = 72;
+/// The same key rendered at 2x.
+pub const KEY_PX_2X: u32 = 144;
+
+/// Inner padding (per side, at 1x) Stream Deck keeps free around the title.
+const PAD_1X: f32 = 4.0;
+/// Average glyph advance as a fraction of the font size.
+const ADVANCE_EM: f32 = 0.6;
+/// Extra advance for bold styles.
+const ADVANCE_EM_BOLD: f32 = 0.66;
+/// Line height as a fraction of the font size.
+const LINE_EM: f32 = 1.2;
+
+const ELLIPSIS: char = '…';
+
+/// What to do when the text does not fit on one line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TitleOverflow {
+    /// Wrap on word boundaries; ellipsize the last line if there are too many lines.
+    #[default]
+    Wrap,
+    /// Keep a single line per explicit line break; cut with `…`.
+    Ellipsize,
+}
+
+/// Line capacity of a key for a given font size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TitleBox {
+    /// Approximate number of narrow glyphs per line.
+    pub columns: usize,
+    /// Number of lines that fit vertically.
+    pub lines: usize,
+}
+
+impl TitleBox {
+    /// Estimate the title box for `font_size` (points at 1x) on a key of `key_px` pixels.
+    pub fn estimate(font_size: i64, bold: bool, key_px: u32) -> Self {
+        let scale = key_px.max(1) as f32 / KEY_PX_1X as f32;
+        let font_px = font_size.max(1) as f32 * scale;
+        let usable = (key_px as f32 - 2.0 * PAD_1X * scale).max(font_px);
+        let advance = font_px * if bold { ADVANCE_EM_BOLD } else { ADVANCE_EM };
+
+        Self {
+            columns: ((usable / advance).floor() as usize).max(1),
+            lines: ((usable / (font_px * LINE_EM)).floor() as usize).max(1),
+        }
+    }
+}
+
+impl TitleParameters {
+    /// Estimate how much text fits with these parameters on a key of `key_px` pixels.
+    pub fn title_box(&self, key_px: u32) -> TitleBox {
+        TitleBox::estimate(self.font_size, self.font_style.is_bold(), key_px)
+    }
+
+    /// Wrap or ellipsize `text` so it fits with these parameters.
+    pub fn fit_title(&self, text: &str, key_px: u32, overflow: TitleOverflow) -> String {
+        fit_title_in(text, self.title_box(key_px), overflow)
+    }
+}
+
+/// Fit `text` for a plain (non-bold) font of `font_size` on a `key_px` key.
+pub fn fit_title(text: &str, font_size: i64, key_px: u32, overflow: TitleOverflow) -> String {
+    fit_title_in(text, TitleBox::estimate(font_size, false, key_px), overflow)
+}
+
+/// Fit `text` into an explicit box. Lines are joined with `\n`.
+pub fn fit_title_in(text: &str, bx: TitleBox, overflow: TitleOverflow) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut truncated = false;
+
+    for para in text.lines() {
+        match overflow {
+            TitleOverflow::Ellipsize => out.push(ellipsize(para.trim(), bx.columns)),
+            TitleOverflow::Wrap => out.extend(wrap(para, bx.columns)),
+        }
+        if out.len() > bx.lines {
+            truncated = true;
+            break;
+        }
+    }
+
+    if out.len() > bx.lines {
+        out.truncate(bx.lines);
+        truncated = true;
+    }
+    if truncated && let Some(last) = out.last_mut() {
+        *last = force_ellipsis(last, bx.columns);
+    }
+    out.join("\n")
+}
+
+/// Display width in "narrow glyph" units. Wide scripts and emoji count double.
+fn units(c: char) -> usize {
+    if (c as u32) >= 0x1100 { 2 } else { 1 }
+}
+
+fn width(s: &str) -> usize {
+    s.chars().map(units).sum()
+}
+
+/// Take the longest prefix of `s` that is at most `cols` wide.
+fn take_cols(s: &str, cols: usize) -> (&str, &str) {
+    let mut w = 0;
+    for (i, c) in s.char_indices() {
+        w += units(c);
+        // always take at least one glyph so callers make progress
+        if w > cols && i > 0 {
+            return s.split_at(i);
+        }
+    }
+    (s, "")
+}
+
+fn ellipsize(s: &str, cols: usize) -> String {
+    if width(s) <= cols {
+        s.to_string()
+    } else {
+        force_ellipsis(s, cols)
+    }
+}
+
+/// Cut `s` so that `s + …` fits into `cols`.
+fn force_ellipsis(s: &str, cols: usize) -> String {
+    if cols <= 1 {
+        return ELLIPSIS.to_string();
+    }
+    let (head, _) = take_cols(s, cols.saturating_sub(1));
+    let mut out = head.trim_end().to_string();
+    out.push(ELLIPSIS);
+    out
+}
+
+/// Greedy word wrap; words longer than a line are hard-broken.
+fn wrap(para: &str, cols: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut cur = String::new();
+
+    for word in para.split_whitespace() {
+        let sep = usize::from(!cur.is_empty());
+        if width(&cur) + sep + width(word) <= cols {
+            if sep == 1 {
+                cur.push(' ');
+            }
+            cur.push_str(word);
+            continue;
+        }
+        if !cur.is_empty() {
+            lines.push(std::mem::take(&mut cur));
+        }
+        let mut rest = word;
+        while width(rest) > cols {
+            let (head, tail) = take_cols(rest, cols);
+            lines.push(head.to_string());
+            rest = tail;
+        }
+        cur.push_str(rest);
+    }
+
+    if !cur.is_empty() || lines.is_empty() {
+        lines.push(cur);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOX: TitleBox = TitleBox {
+        columns: 4,
+        lines: 3,
+    };
+
+    #[test]
+    fn estimate_scales_with_font_and_key_size() {
+        assert_eq!(
+            TitleBox::estimate(10, false, KEY_PX_1X),
+            TitleBox {
+                columns: 10,
+                lines: 5
+            }
+        );
+        // bold glyphs are wider
+        assert_eq!(TitleBox::estimate(10, true, KEY_PX_1X).columns, 9);
+        // font sizes are relative to a 72px key, so 2x fits the same text
+        assert_eq!(
+            TitleBox::estimate(10, false, KEY_PX_2X),
+            TitleBox::estimate(10, false, KEY_PX_1X)
+        );
+        // never less than one glyph on one line
+        assert_eq!(
+            TitleBox::estimate(500, true, KEY_PX_1X),
+            TitleBox {
+                columns: 1,
+                lines: 1
+            }
+        );
+    }
+
+    #[test]
+    fn words_wrap_on_spaces() {
+        assert_eq!(
+            fit_title_in("ab cd ef", BOX, TitleOverflow::Wrap),
+            "ab\ncd\nef"
+        );
+        assert_eq!(fit_title_in("a b", BOX, TitleOverflow::Wrap), "a b");
+    }
+
+    #[test]
+    fn words_longer_than_a_line_are_broken() {
+        assert_eq!(
+            fit_title_in("abcdefghij", BOX, TitleOverflow::Wrap),
+            "abcd\nefgh\nij"
+        );
+    }
+
+    #[test]
+    fn too_many_lines_end_in_an_ellipsis() {
+        let two = TitleBox {
+            columns: 4,
+            lines: 2,
+        };
+        assert_eq!(
+            fit_title_in("one two three", two, TitleOverflow::Wrap),
+            "one\ntwo…"
+        );
+        assert_eq!(
+            fit_title_in("abcd\nefgh\nijkl", two, TitleOverflow::Wrap),
+            "abcd\nefg…"
+        );
+    }
+
+    #[test]
+    fn explicit_line_breaks_are_kept() {
+        assert_eq!(fit_title_in("a\nb", BOX, TitleOverflow::Wrap), "a\nb");
+        assert_eq!(fit_title_in("a\n\nb", BOX, TitleOverflow::Wrap), "a\n\nb");
+        assert_eq!(
+            fit_title_in("hello\nx", BOX, TitleOverflow::Ellipsize),
+            "hel…\nx"
+        );
+    }
+
+    #[test]
+    fn empty_input_stays_empty() {
+        assert_eq!(fit_title_in("", BOX, TitleOverflow::Wrap), "");
+        assert_eq!(fit_title_in("", BOX, TitleOverflow::Ellipsize), "");
+        assert_eq!(fit_title("", 12, KEY_PX_1X, TitleOverflow::Wrap), "");
+    }
+
+    #[test]
+    fn wide_glyphs_count_double() {
+        assert_eq!(fit_title_in("中文字", BOX, TitleOverflow::Wrap), "中文\n字");
+    }
+}
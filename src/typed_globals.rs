@@ -0,0 +1,379 @@
+// lib/typed_globals.rs
+//! Typed view over `GlobalSettings`: a plugin declares one settings struct, reads it
+//! with `cx.globals_typed::<T>().get()` and writes through `update`, which pushes like
+//! any other global write. Older versions found in a Stream Deck snapshot are
+//! migrated on hydration when a [`GlobalsSchema`] is registered on the plugin.
+
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use tracing::{error, info, warn};
+
+use crate::context::GlobalSettings;
+
+/// A plugin's global settings struct.
+///
+/// Its fields are stored as top-level keys of the global settings, next to
+/// `VERSION_KEY`. Unknown keys are left alone on write.
+pub trait VersionedSettings:
+    Serialize + DeserializeOwned + Default + Send + Sync + 'static
+{
+    /// Schema version written with every update.
+    const VERSION: u32;
+    /// Key holding the version number.
+    const VERSION_KEY: &'static str = "version";
+}
+
+/// Upgrades the raw map by one version.
+pub type MigrationFn = Arc<dyn Fn(&mut Map<String, Value>) + Send + Sync>;
+
+/// Version and migrations for the plugin's global settings.
+/// Register with `Plugin::global_settings_schema`.
+#[derive(Clone)]
+pub struct GlobalsSchema {
+    version: u32,
+    version_key: &'static str,
+    migrations: BTreeMap<u32, MigrationFn>,
+}
+
+impl GlobalsSchema {
+    pub fn of<T: VersionedSettings>() -> Self {
+        Self {
+            version: T::VERSION,
+            version_key: T::VERSION_KEY,
+            migrations: BTreeMap::new(),
+        }
+    }
+
+    /// Migration from version `from` to `from + 1` (chainable).
+    pub fn migration<F>(mut self, from: u32, f: F) -> Self
+    where
+        F: Fn(&mut Map<String, Value>) + Send + Sync + 'static,
+    {
+        self.migrations.insert(from, Arc::new(f));
+        self
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Versions below the current one with no migration registered. A stored
+    /// map without a version key counts as v1, so the chain starts there.
+    pub fn missing_migrations(&self) -> Vec<u32> {
+        (1..self.version)
+            .filter(|from| !self.migrations.contains_key(from))
+            .collect()
+    }
+
+    /// Bring `map` up to the current version in place. Returns true if it changed.
+    ///
+    /// An empty map is left alone (nothing stored yet). A non-empty map without a
+    /// version key counts as version 1, i.e. written before the schema existed.
+    /// Migration stops at the first version without a step and records how far it got.
+    pub fn migrate(&self, map: &mut Map<String, Value>) -> bool {
+        if map.is_empty() {
+            return false;
+        }
+        let stored = match map.get(self.version_key) {
+            None => 1,
+            Some(v) => match v.as_u64().and_then(|n| u32::try_from(n).ok()) {
+                Some(n) => n,
+                None => {
+                    warn!(
+                        "⚠️ global settings version `{}` is not a number; leaving them as-is",
+                        v
+                    );
+                    return false;
+                }
+            },
+        };
+        if stored > self.version {
+            warn!(
+                "⚠️ global settings v{} are newer than this plugin (v{}); leaving them as-is",
+                stored, self.version
+            );
+            return false;
+        }
+
+        let mut reached = stored;
+        while reached < self.version {
+            let Some(f) = self.migrations.get(&reached) else {
+                error!(
+                    "❌ no global settings migration from v{}; stopping there",
+                    reached
+                );
+                break;
+            };
+            f(map);
+            reached += 1;
+        }
+        if reached == stored {
+            return false;
+        }
+        map.insert(self.version_key.to_string(), Value::from(reached));
+        info!("🔁 migrated global settings v{} → v{}", stored, reached);
+        true
+    }
+}
+
+/// Typed handle over `GlobalSettings`; cheap to clone.
+pub struct TypedGlobals<T> {
+    globals: GlobalSettings,
+    _t: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for TypedGlobals<T> {
+    fn clone(&self) -> Self {
+        Self {
+            globals: self.globals.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<T: VersionedSettings> TypedGlobals<T> {
+    pub(crate) fn new(globals: GlobalSettings) -> Self {
+        Self {
+            globals,
+            _t: PhantomData,
+        }
+    }
+
+    /// Current settings; `T::default()` if nothing is stored or it does not decode.
+    pub fn get(&self) -> T {
+        self.try_get().unwrap_or_else(|e| {
+            warn!("⚠️ global settings do not decode, using defaults: {}", e);
+            T::default()
+        })
+    }
+
+    /// Current settings, or the decode error.
+    pub fn try_get(&self) -> Result<T, serde_json::Error> {
+        decode(&self.globals.snapshot())
+    }
+
+    /// Replace the settings and push.
+    pub fn set(&self, value: T) {
+        self.update(|t| *t = value);
+    }
+
+    /// Edit the settings and push once. Returns `None`, leaving the store untouched,
+    /// if the stored settings do not decode as `T`, `T` does not serialize to an
+    /// object, or the store is unusable (poisoned lock).
+    ///
+    /// Keys `T` wrote before but skips now (e.g. an `Option` set to `None`) are
+    /// removed; keys `T` does not know are kept.
+    pub fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        self.globals.try_with_mut(|map| {
+            let mut value = match decode::<T>(map) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("❌ global settings do not decode; not updating them: {}", e);
+                    return None;
+                }
+            };
+            let old = fields(&value)?;
+            let ret = f(&mut value);
+            let new = fields(&value)?;
+            for key in old.keys() {
+                map.remove(key);
+            }
+            map.extend(new);
+            map.insert(T::VERSION_KEY.to_string(), Value::from(T::VERSION));
+            Some(ret)
+        })
+    }
+
+    /// The untyped store underneath.
+    pub fn raw(&self) -> &GlobalSettings {
+        &self.globals
+    }
+}
+
+/// `value` as a JSON object, logging why not.
+fn fields<T: VersionedSettings>(value: &T) -> Option<Map<String, Value>> {
+    match serde_json::to_value(value) {
+        Ok(Value::Object(fields)) => Some(fields),
+        Ok(_) => {
+            warn!("⚠️ typed global settings must serialize to an object");
+            None
+        }
+        Err(e) => {
+            warn!("⚠️ typed global settings do not serialize: {}", e);
+            None
+        }
+    }
+}
+
+fn decode<T: VersionedSettings>(map: &Map<String, Value>) -> Result<T, serde_json::Error> {
+    if map.is_empty() {
+        return Ok(T::default());
+    }
+    serde_json::from_value(Value::Object(map.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crossbeam_channel::Receiver;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+    use crate::events::RuntimeMsg;
+    use crate::plugin::Plugin;
+    use crate::sd_protocol::{Outgoing, SdClient};
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Prefs {
+        #[serde(default)]
+        volume: u8,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        nick: Option<String>,
+    }
+
+    impl VersionedSettings for Prefs {
+        const VERSION: u32 = 3;
+    }
+
+    fn store() -> (TypedGlobals<Prefs>, Receiver<RuntimeMsg>) {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let globals = GlobalSettings::new(Arc::new(SdClient::new(tx, "plugin")));
+        (TypedGlobals::new(globals), rx)
+    }
+
+    fn pushes(rx: &Receiver<RuntimeMsg>) -> Vec<Map<String, Value>> {
+        rx.try_iter()
+            .filter_map(|m| match m {
+                RuntimeMsg::Outgoing(Outgoing::SetGlobalSettings { payload, .. }) => Some(payload),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn object(v: Value) -> Map<String, Value> {
+        match v {
+            Value::Object(m) => m,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn update_writes_fields_and_version_and_keeps_foreign_keys() {
+        let (typed, rx) = store();
+        typed.raw().replace(object(json!({ "other": true })));
+        pushes(&rx);
+
+        assert_eq!(typed.update(|p| p.volume = 7), Some(()));
+        assert_eq!(
+            typed.raw().snapshot(),
+            object(json!({ "other": true, "volume": 7, "version": 3 }))
+        );
+        assert_eq!(pushes(&rx).len(), 1);
+        assert_eq!(typed.get().volume, 7);
+    }
+
+    #[test]
+    fn update_removes_keys_the_type_now_skips() {
+        let (typed, _rx) = store();
+        typed.set(Prefs {
+            volume: 1,
+            nick: Some("ace".into()),
+        });
+        assert!(typed.raw().get("nick").is_some());
+
+        typed.update(|p| p.nick = None);
+        assert_eq!(typed.raw().get("nick"), None);
+        assert_eq!(
+            typed.get(),
+            Prefs {
+                volume: 1,
+                nick: None
+            }
+        );
+    }
+
+    #[test]
+    fn update_aborts_when_stored_settings_do_not_decode() {
+        let (typed, rx) = store();
+        let stored = object(json!({ "volume": "loud", "version": 3 }));
+        typed.raw().replace(stored.clone());
+        pushes(&rx);
+
+        let mut ran = false;
+        assert_eq!(typed.update(|_| ran = true), None);
+        assert!(!ran);
+        assert_eq!(typed.raw().snapshot(), stored);
+        assert!(pushes(&rx).is_empty());
+    }
+
+    fn schema() -> GlobalsSchema {
+        GlobalsSchema::of::<Prefs>()
+            .migration(1, |m| {
+                if let Some(v) = m.remove("vol") {
+                    m.insert("volume".into(), v);
+                }
+            })
+            .migration(2, |m| {
+                m.insert("nick".into(), json!("anon"));
+            })
+    }
+
+    #[test]
+    fn migrate_runs_every_step_from_unversioned() {
+        let mut map = object(json!({ "vol": 4 }));
+        assert!(schema().migrate(&mut map));
+        assert_eq!(
+            map,
+            object(json!({ "volume": 4, "nick": "anon", "version": 3 }))
+        );
+        assert!(!schema().migrate(&mut map));
+    }
+
+    #[test]
+    fn migrate_stops_at_a_gap() {
+        let gap = GlobalsSchema::of::<Prefs>().migration(1, |m| {
+            m.insert("step1".into(), json!(true));
+        });
+        assert_eq!(gap.missing_migrations(), vec![2]);
+
+        let mut map = object(json!({ "volume": 1 }));
+        assert!(gap.migrate(&mut map));
+        assert_eq!(
+            map,
+            object(json!({ "volume": 1, "step1": true, "version": 2 }))
+        );
+
+        // stuck at v2: nothing runs, the stored version stays
+        assert!(!gap.migrate(&mut map));
+        assert_eq!(map["version"], json!(2));
+    }
+
+    #[test]
+    fn migrate_leaves_newer_and_invalid_versions_alone() {
+        for version in [json!(4), json!("2"), json!(4_294_967_297u64)] {
+            let mut map = object(json!({ "volume": 1, "version": version.clone() }));
+            let before = map.clone();
+            assert!(!schema().migrate(&mut map), "version {version}");
+            assert_eq!(map, before);
+        }
+        assert!(!schema().migrate(&mut Map::new()));
+    }
+
+    #[test]
+    fn complete_schema_registers() {
+        assert!(schema().missing_migrations().is_empty());
+        let _ = Plugin::new().global_settings_schema(schema());
+    }
+
+    #[test]
+    #[should_panic(expected = "no migration from v[2]")]
+    fn incomplete_schema_is_rejected_at_registration() {
+        let gap = GlobalsSchema::of::<Prefs>().migration(1, |_| {});
+        let _ = Plugin::new().global_settings_schema(gap);
+    }
+}